@@ -1,4 +1,6 @@
 pub mod build;
+pub mod disasm;
+pub mod heap;
 pub mod main;
 pub mod run;
 pub mod test;
@@ -1,8 +1,76 @@
 //! Command for building an Inko bytecode image from a source file.
 use crate::compiler;
+use crate::config::BYTECODE_IMAGE_EXT;
 use crate::error::Error;
+use crate::standalone;
+use crate::tempfile::Tempfile;
+use std::path::Path;
+
+/// The option used for building a standalone executable.
+const STANDALONE_OPTION: &str = "--standalone";
 
 /// Compiles Inko source code into a bytecode image.
+///
+/// All options are passed to the compiler as-is, except for the --standalone
+/// option. When this option is given, the image is embedded in a copy of the
+/// current executable, producing a single executable that runs the program.
 pub fn run(arguments: &[String]) -> Result<i32, Error> {
-    compiler::spawn(arguments)
+    if !arguments.iter().any(|arg| arg == STANDALONE_OPTION) {
+        return compiler::spawn(arguments);
+    }
+
+    let mut compiler_args = Vec::new();
+    let mut output = None;
+    let mut iter = arguments.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            STANDALONE_OPTION => {}
+            "-o" | "--output" => output = iter.next().cloned(),
+            "-c" | "--check" => {
+                return Err(Error::generic(
+                    "The --check and --standalone options can't be used \
+                    together"
+                        .to_string(),
+                ));
+            }
+            _ if arg.starts_with("--output=") => {
+                output = Some(arg["--output=".len()..].to_string());
+            }
+            _ => compiler_args.push(arg.clone()),
+        }
+    }
+
+    let output = if let Some(path) = output {
+        path
+    } else {
+        default_executable_path(&compiler_args)?
+    };
+
+    let image = Tempfile::new(BYTECODE_IMAGE_EXT)?;
+
+    compiler_args.push("-o".to_string());
+    compiler_args.push(image.path().clone());
+    compiler::spawn(&compiler_args)?;
+    standalone::create(image.path(), &output)?;
+
+    Ok(0)
+}
+
+/// Returns the path of the executable to produce when no output path is given.
+///
+/// Similar to the compiler's default output path, this is the name of the
+/// source file without its extension, in the current working directory. The
+/// source file is the last argument, as options come before it.
+fn default_executable_path(arguments: &[String]) -> Result<String, Error> {
+    arguments
+        .last()
+        .filter(|arg| !arg.starts_with('-'))
+        .and_then(|path| Path::new(path).file_stem())
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            Error::generic(
+                "You must specify a source file to compile".to_string(),
+            )
+        })
 }
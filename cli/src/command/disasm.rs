@@ -0,0 +1,237 @@
+//! Command for printing the contents of a bytecode image.
+use crate::error::Error;
+use crate::options::print_usage;
+use getopts::Options;
+use libinko::bytecode_parser;
+use libinko::compiled_code::CompiledCode;
+use libinko::config::Config;
+use libinko::module::Module;
+use libinko::object_pointer::ObjectPointer;
+use libinko::vm::instruction::{Instruction, Operand};
+use libinko::vm::state::State;
+
+const USAGE: &str = "Usage: inko disasm [OPTIONS] FILE
+
+Prints the contents of a bytecode image in a human readable format.
+
+For every module this prints the literals, and every compiled code object along
+with its instructions and catch table. Nested code objects are printed after
+the instructions of the code object they are defined in.
+
+Images containing invalid code objects are still printed, with the reason a
+code object is invalid printed before its instructions.
+
+Examples:
+
+    inko disasm hello.ibi       # Prints the contents of hello.ibi
+    inko disasm -O hello.ibi    # Prints the instructions as executed by the VM";
+
+/// The number of spaces to indent nested output with.
+const INDENT: usize = 2;
+
+/// Prints the contents of a bytecode image.
+pub fn run(arguments: &[String]) -> Result<i32, Error> {
+    let mut options = Options::new();
+
+    options.optflag("h", "help", "Shows this help message");
+    options.optflag(
+        "O",
+        "optimised",
+        "Prints instructions after combining them into superinstructions",
+    );
+
+    let matches = options.parse(arguments)?;
+
+    if matches.opt_present("h") {
+        print_usage(&options, USAGE);
+        return Ok(0);
+    }
+
+    let path = matches.free.get(0).ok_or_else(|| {
+        Error::generic("You must specify a bytecode image".to_string())
+    })?;
+
+    let mut config = Config::new();
+
+    config.populate_from_env();
+    config.optimise_bytecode = matches.opt_present("O");
+
+    // Invalid images are printed as well, so they can be inspected.
+    config.verify_bytecode = false;
+
+    let state = State::with_rc(config, &[]);
+    let mut image =
+        bytecode_parser::parse_file(&state, path).map_err(|err| {
+            format!("The bytecode image {} is invalid: {}", path, err)
+        })?;
+
+    let indexed = !image.lazy_modules.is_empty();
+
+    for lazy in &image.lazy_modules {
+        let module = lazy.parse(&state).map_err(|err| {
+            format!("The module {} is invalid: {}", lazy.name, err)
+        })?;
+
+        image.modules.push(module);
+    }
+
+    // Modules are parsed in parallel, so we sort them to ensure the output is
+    // the same every time.
+    image.modules.sort_by_key(|module| string(module.name()));
+
+    println!("Compiler version: {}", image.compiler_version);
+    println!("Entry point: {}", image.entry_point);
+    println!("Module index: {}", if indexed { "yes" } else { "no" });
+
+    if !image.metadata.is_empty() {
+        println!("Metadata:");
+
+        for (key, value) in &image.metadata {
+            println!("{}{}: {}", indent(1), key, value);
+        }
+    }
+
+    for module in &image.modules {
+        print_module(module);
+    }
+
+    Ok(0)
+}
+
+fn print_module(module: &Module) {
+    println!("\nModule {}", string(module.name()));
+    println!("{}Literals:", indent(1));
+
+    for (index, literal) in module.literals().iter().enumerate() {
+        println!("{}{}: {}", indent(2), index, format_literal(*literal));
+    }
+
+    print_code(module, &module.code(), 1);
+}
+
+fn print_code(module: &Module, code: &CompiledCode, depth: usize) {
+    let arguments: Vec<String> =
+        code.arguments.iter().map(|name| string(*name)).collect();
+
+    println!(
+        "\n{}Code {} ({}:{})",
+        indent(depth),
+        string(code.name),
+        string(code.file),
+        code.line
+    );
+
+    println!(
+        "{}Arguments: [{}] (required: {})",
+        indent(depth + 1),
+        arguments.join(", "),
+        code.required_arguments
+    );
+
+    println!("{}Locals: {}", indent(depth + 1), code.locals);
+    println!("{}Registers: {}", indent(depth + 1), code.registers);
+    println!("{}Captures: {}", indent(depth + 1), code.captures);
+
+    if let Err(err) =
+        bytecode_parser::verify_code(code, module.literals().len())
+    {
+        println!("{}Invalid: {}", indent(depth + 1), err);
+    }
+
+    if !code.catch_table.entries.is_empty() {
+        println!("{}Catch table:", indent(depth + 1));
+
+        for (index, entry) in code.catch_table.entries.iter().enumerate() {
+            println!(
+                "{}{}: {}..{} -> {}",
+                indent(depth + 2),
+                index,
+                entry.start,
+                entry.end,
+                entry.jump_to
+            );
+        }
+    }
+
+    println!("{}Instructions:", indent(depth + 1));
+
+    for (index, ins) in code.instructions.iter().enumerate() {
+        println!(
+            "{}{:04}  {:<24} {:<32} line {}",
+            indent(depth + 2),
+            index,
+            format!("{:?}", ins.opcode),
+            format_operands(module, ins),
+            ins.line
+        );
+    }
+
+    for child in &code.code_objects {
+        print_code(module, child, depth + 1);
+    }
+}
+
+fn format_operands(module: &Module, ins: &Instruction) -> String {
+    let mut arg = 0;
+    let mut operands = Vec::new();
+
+    for operand in ins.opcode.operands() {
+        let value = ins.arg(arg);
+        let formatted = match operand {
+            Operand::Register => format!("r{}", value),
+            Operand::RegisterRange => {
+                format!("[r{}; {}]", value, ins.arg(arg + 1))
+            }
+            Operand::Local => format!("local {}", value),
+            Operand::Literal => format_literal_index(module, value as usize),
+            Operand::WideLiteral => {
+                let index = ((value as usize) << 16)
+                    | (ins.arg(arg + 1) as usize & 0xFFFF);
+
+                format_literal_index(module, index)
+            }
+            Operand::CodeObject => format!("code {}", value),
+            Operand::Jump => format!("-> {}", value),
+            Operand::Global => format!("global {}", value),
+            Operand::Value => value.to_string(),
+        };
+
+        operands.push(formatted);
+        arg += operand.width();
+    }
+
+    operands.join(", ")
+}
+
+fn format_literal_index(module: &Module, index: usize) -> String {
+    if let Some(literal) = module.literals().get(index) {
+        format!("lit {} ({})", index, format_literal(*literal))
+    } else {
+        format!("lit {}", index)
+    }
+}
+
+fn format_literal(literal: ObjectPointer) -> String {
+    if let Ok(value) = literal.integer_value() {
+        value.to_string()
+    } else if let Ok(value) = literal.bigint_value() {
+        value.to_string()
+    } else if let Ok(value) = literal.float_value() {
+        format!("{:?}", value)
+    } else if let Ok(value) = literal.string_value() {
+        format!("{:?}", value.as_slice())
+    } else {
+        "?".to_string()
+    }
+}
+
+fn string(pointer: ObjectPointer) -> String {
+    pointer
+        .string_value()
+        .map(|value| value.to_owned_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
+
+fn indent(depth: usize) -> String {
+    " ".repeat(depth * INDENT)
+}
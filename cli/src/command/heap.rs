@@ -0,0 +1,123 @@
+//! Command for analysing heap snapshots.
+use crate::error::Error;
+use crate::options::print_usage;
+use getopts::Options;
+use libinko::gc::snapshot::HeapSnapshot;
+use std::path::Path;
+
+const USAGE: &str = "Usage: inko heap [OPTIONS] FILE
+
+Analyses a heap snapshot of a process.
+
+By default this prints the number of live objects per type, starting with the
+most common type. The --path option can be used to find out why an object is
+retained.
+
+Examples:
+
+    inko heap heap.snapshot                # Prints a summary of heap.snapshot
+    inko heap --limit 5 heap.snapshot      # Prints the 5 most common types
+    inko heap --path 0x7f1a heap.snapshot  # Prints how 0x7f1a is retained";
+
+/// The number of types to print by default.
+const DEFAULT_LIMIT: usize = 20;
+
+/// Analyses a heap snapshot.
+pub fn run(arguments: &[String]) -> Result<i32, Error> {
+    let mut options = Options::new();
+
+    options.optflag("h", "help", "Shows this help message");
+    options.optopt(
+        "l",
+        "limit",
+        "The number of types to print (default: 20)",
+        "NUMBER",
+    );
+    options.optopt(
+        "p",
+        "path",
+        "Prints the shortest path from a root to an object",
+        "ADDRESS",
+    );
+
+    let matches = options.parse(arguments)?;
+
+    if matches.opt_present("h") {
+        print_usage(&options, USAGE);
+        return Ok(0);
+    }
+
+    let path = matches.free.get(0).ok_or_else(|| {
+        Error::generic("You must specify a heap snapshot".to_string())
+    })?;
+
+    let snapshot = HeapSnapshot::read(Path::new(path)).map_err(|err| {
+        format!("The heap snapshot {} is invalid: {}", path, err)
+    })?;
+
+    if let Some(address) = matches.opt_str("p") {
+        return print_path(&snapshot, &address);
+    }
+
+    let limit = if let Some(limit) = matches.opt_str("l") {
+        limit.parse::<usize>().map_err(|_| {
+            Error::generic(format!("The limit {:?} is invalid", limit))
+        })?
+    } else {
+        DEFAULT_LIMIT
+    };
+
+    print_summary(&snapshot, limit);
+    Ok(0)
+}
+
+fn print_summary(snapshot: &HeapSnapshot, limit: usize) {
+    let mature = snapshot
+        .objects
+        .iter()
+        .filter(|object| object.mature)
+        .count();
+
+    println!("Process: {:#x}", snapshot.process);
+    println!(
+        "Objects: {} (young: {}, mature: {})",
+        snapshot.objects.len(),
+        snapshot.objects.len() - mature,
+        mature
+    );
+    println!("Roots: {}", snapshot.roots.len());
+    println!("\n{:>10}  Type", "Objects");
+
+    for (description, count) in snapshot.type_counts().iter().take(limit) {
+        println!("{:>10}  {}", count, description);
+    }
+}
+
+fn print_path(snapshot: &HeapSnapshot, address: &str) -> Result<i32, Error> {
+    let digits = address.strip_prefix("0x").unwrap_or(address);
+    let address = usize::from_str_radix(digits, 16).map_err(|_| {
+        Error::generic(format!("The address {:?} is invalid", address))
+    })?;
+
+    let (root, path) = snapshot.path_to(address).ok_or_else(|| {
+        Error::generic(format!(
+            "The object {:#x} isn't reachable from any root",
+            address
+        ))
+    })?;
+
+    println!("Root ({}):", root.kind);
+
+    for address in path {
+        let description = snapshot
+            .objects
+            .iter()
+            .find(|object| object.address == address)
+            .map(|object| object.description())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        println!("  {:#x} {}", address, description);
+    }
+
+    Ok(0)
+}
@@ -1,10 +1,14 @@
 //! The main entry point for the CLI.
 use crate::command::build;
+use crate::command::disasm;
+use crate::command::heap;
 use crate::command::run;
 use crate::command::test;
 use crate::config;
 use crate::error::Error;
 use crate::options::print_usage;
+use crate::standalone;
+use crate::vm;
 use getopts::{Options, ParsingStyle};
 use std::env;
 
@@ -15,6 +19,8 @@ Commands:
     run      Compiles and runs FILE
     build    Compiles FILE
     test     Runs Inko unit tests
+    disasm   Prints the contents of a bytecode image
+    heap     Analyses a heap snapshot
 
 If no explicit command is given, the run command is implied. Each command takes
 its own set of options.
@@ -25,11 +31,20 @@ Examples:
     inko run hello.inko           # Same
     inko build hello.inko         # Merely compiles the file into bytecode
     inko build --check hello.inko # Checks hello.inko for errors
+    inko disasm hello.ibi         # Prints the bytecode in hello.ibi
+    inko heap heap.snapshot       # Prints a summary of a heap snapshot
     inko run --help               # Prints the help message for the run command";
 
 /// Runs the default CLI command.
 pub fn run() -> Result<i32, Error> {
     let args: Vec<String> = env::args().collect();
+
+    // Standalone executables pass all arguments to the embedded program,
+    // instead of treating them as options for this executable.
+    if let Some(image) = standalone::embedded_image()? {
+        return vm::start_image(&image, &args[1..]);
+    }
+
     let mut options = Options::new();
 
     options.parsing_style(ParsingStyle::StopAtFirstFree);
@@ -56,6 +71,8 @@ pub fn run() -> Result<i32, Error> {
         Some("run") => run::run(&matches.free[1..]),
         Some("build") => build::run(&matches.free[1..]),
         Some("test") => test::run(&matches.free[1..]),
+        Some("disasm") => disasm::run(&matches.free[1..]),
+        Some("heap") => heap::run(&matches.free[1..]),
         Some(_) => run::run(&matches.free),
         None => Err(Error::generic(
             "You must specify a command or input file to run".to_string(),
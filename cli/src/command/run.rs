@@ -25,11 +25,18 @@ recommended to compile and run your program separately. For example:
     inko build hello.inko    # Produces ./hello.ibi
     inko run hello.ibi       # Runs the program
 
+Native extensions are dynamic libraries that provide additional external
+functions. These can be loaded using the --extension option, or by setting the
+INKO_EXTENSIONS environment variable.
+
 Examples:
 
     inko run hello.inko    # Compiles and runs the file hello.inko
     inko run hello.ibi     # Runs the bytecode image directly
 
+    # Runs the bytecode image with the extension in libfoo.so
+    inko run --extension libfoo.so hello.ibi
+
 Output formats:
 
     pretty (default)
@@ -55,6 +62,13 @@ pub fn run(arguments: &[String]) -> Result<i32, Error> {
         "FORMAT",
     );
 
+    options.optmulti(
+        "",
+        "extension",
+        "Loads the native extension stored in the dynamic library PATH",
+        "PATH",
+    );
+
     let matches = options.parse(arguments)?;
 
     if matches.opt_present("h") {
@@ -67,6 +81,7 @@ pub fn run(arguments: &[String]) -> Result<i32, Error> {
             &source,
             matches.opt_strs("i"),
             matches.opt_str("f"),
+            &matches.opt_strs("extension"),
             &matches.free,
         )
     } else if let Some(input) = matches.free.get(0) {
@@ -74,6 +89,7 @@ pub fn run(arguments: &[String]) -> Result<i32, Error> {
             input,
             matches.opt_strs("i"),
             matches.opt_str("f"),
+            &matches.opt_strs("extension"),
             &matches.free[1..],
         )
     } else {
@@ -88,18 +104,17 @@ fn run_file(
     input: &str,
     include: Vec<String>,
     format: Option<String>,
+    extensions: &[String],
     arguments: &[String],
 ) -> Result<i32, Error> {
-    let status = if input.ends_with(BYTECODE_IMAGE_EXT) {
-        vm::start(input, arguments)
+    if input.ends_with(BYTECODE_IMAGE_EXT) {
+        vm::start(input, arguments, extensions)
     } else {
         let image = Tempfile::new(BYTECODE_IMAGE_EXT)?;
 
         compile(input, image.path(), include, format)?;
-        vm::start(image.path(), arguments)
-    };
-
-    Ok(status)
+        vm::start(image.path(), arguments, extensions)
+    }
 }
 
 /// Runs Inko source code that is provided directly, instead of through a file.
@@ -109,6 +124,7 @@ pub fn run_eval(
     source: &str,
     include: Vec<String>,
     format: Option<String>,
+    extensions: &[String],
     arguments: &[String],
 ) -> Result<i32, Error> {
     let mut input = Tempfile::new(SOURCE_FILE_EXT)?;
@@ -118,7 +134,7 @@ pub fn run_eval(
     input.flush();
 
     compile(input.path(), image.path(), include, format)?;
-    Ok(vm::start(image.path(), arguments))
+    vm::start(image.path(), arguments, extensions)
 }
 
 /// Compiles the source code in the given input path, producing a bytecode image
@@ -60,6 +60,13 @@ pub fn run(arguments: &[String]) -> Result<i32, Error> {
         "FORMAT",
     );
 
+    options.optmulti(
+        "",
+        "extension",
+        "Loads the native extension stored in the dynamic library PATH",
+        "PATH",
+    );
+
     let matches = options.parse(arguments)?;
 
     if matches.opt_present("h") {
@@ -109,6 +116,7 @@ pub fn run(arguments: &[String]) -> Result<i32, Error> {
         &source,
         vec![root_dir.to_string_lossy().to_string()],
         matches.opt_str("f"),
+        &matches.opt_strs("extension"),
         &matches.free,
     )
 }
@@ -14,6 +14,7 @@ mod compiler;
 mod config;
 mod error;
 mod options;
+mod standalone;
 mod tempfile;
 mod vm;
 
@@ -0,0 +1,88 @@
+//! Executables that contain an embedded bytecode image.
+//!
+//! A standalone executable is a copy of the `inko` executable, with a bytecode
+//! image appended to it. The image is followed by a trailer, containing the
+//! size of the image and a signature. When the executable starts, it checks
+//! for this trailer and runs the embedded image if present.
+use crate::error::Error;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// The bytes that mark the end of an executable containing a bytecode image.
+const SIGNATURE: [u8; 8] = *b"inkoexe\0";
+
+/// The size of the trailer, which consists of the image size and signature.
+const TRAILER_SIZE: u64 = 16;
+
+/// Creates a standalone executable from a bytecode image.
+///
+/// The executable is created by copying the current executable, then appending
+/// the bytecode image to it.
+pub fn create(image_path: &str, output: &str) -> Result<(), Error> {
+    let image = fs::read(image_path)?;
+
+    fs::copy(env::current_exe()?, output)?;
+
+    let mut file = OpenOptions::new().append(true).open(output)?;
+
+    file.write_all(&image)?;
+    file.write_all(&(image.len() as u64).to_le_bytes())?;
+    file.write_all(&SIGNATURE)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Returns the bytecode image embedded in the current executable, if any.
+///
+/// Errors produced while reading the executable are treated as the executable
+/// not containing an image, as a regular `inko` executable shouldn't fail to
+/// start because of them. An error is only returned if the executable contains
+/// a trailer, but the size of the image is invalid.
+pub fn embedded_image() -> Result<Option<Vec<u8>>, Error> {
+    let mut file = match env::current_exe().and_then(File::open) {
+        Ok(file) => file,
+        Err(_) => return Ok(None),
+    };
+
+    let file_size = match file.metadata() {
+        Ok(meta) if meta.len() >= TRAILER_SIZE => meta.len(),
+        _ => return Ok(None),
+    };
+
+    let mut trailer = [0; TRAILER_SIZE as usize];
+
+    if !read_at(&mut file, file_size - TRAILER_SIZE, &mut trailer)
+        || trailer[8..] != SIGNATURE
+    {
+        return Ok(None);
+    }
+
+    let mut size_bytes = [0; 8];
+
+    size_bytes.copy_from_slice(&trailer[0..8]);
+
+    let image_size = u64::from_le_bytes(size_bytes);
+
+    if image_size > file_size - TRAILER_SIZE {
+        return Err(Error::generic(
+            "The size of the embedded bytecode image is invalid".to_string(),
+        ));
+    }
+
+    let mut image = vec![0; image_size as usize];
+
+    if !read_at(&mut file, file_size - TRAILER_SIZE - image_size, &mut image) {
+        return Ok(None);
+    }
+
+    Ok(Some(image))
+}
+
+/// Fills the buffer with the bytes starting at the given offset, returning
+/// true if this succeeded.
+fn read_at(file: &mut File, offset: u64, buffer: &mut [u8]) -> bool {
+    file.seek(SeekFrom::Start(offset)).is_ok()
+        && file.read_exact(buffer).is_ok()
+}
@@ -1,15 +1,49 @@
 //! Functions for interacting with the Inko VM.
+use crate::error::Error;
+use libinko::bytecode_parser;
 use libinko::config::Config;
+use libinko::external_functions::ExternalFunctions;
 use libinko::vm::machine::Machine;
 use libinko::vm::state::State;
+use std::path::PathBuf;
 
-pub fn start(path: &str, arguments: &[String]) -> i32 {
+fn machine(
+    arguments: &[String],
+    extensions: &[String],
+) -> Result<Machine, Error> {
     let mut config = Config::new();
 
     config.populate_from_env();
+    config
+        .extensions
+        .extend(extensions.iter().map(PathBuf::from));
 
-    let machine = Machine::new(State::with_rc(config, arguments));
+    let mut functions = ExternalFunctions::setup()?;
+
+    functions.load_extensions(&config.extensions)?;
+
+    Ok(Machine::new(State::with_external_functions(
+        config, arguments, functions,
+    )))
+}
+
+pub fn start(
+    path: &str,
+    arguments: &[String],
+    extensions: &[String],
+) -> Result<i32, Error> {
+    let machine = machine(arguments, extensions)?;
 
     machine.start(path);
-    machine.state.current_exit_status()
+    Ok(machine.state.current_exit_status())
+}
+
+/// Starts the VM using a bytecode image stored in memory.
+pub fn start_image(image: &[u8], arguments: &[String]) -> Result<i32, Error> {
+    let machine = machine(arguments, &[])?;
+    let image = bytecode_parser::parse(&machine.state, &mut &image[..])
+        .map_err(|err| format!("The bytecode image is invalid: {}", err))?;
+
+    machine.start_image(image);
+    Ok(machine.state.current_exit_status())
 }
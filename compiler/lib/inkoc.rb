@@ -4,6 +4,8 @@ require 'set'
 require 'pathname'
 require 'forwardable'
 require 'json'
+require 'time'
+require 'zlib'
 
 require 'inkoc/version'
 require 'inkoc/inspect'
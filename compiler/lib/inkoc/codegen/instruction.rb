@@ -78,6 +78,7 @@ module Inkoc
         ProcessAddDeferToCaller
         ProcessCurrent
         ProcessIdentifier
+        ProcessReceiveMatching
         ProcessReceiveMessage
         ProcessSendMessage
         ProcessSetBlocking
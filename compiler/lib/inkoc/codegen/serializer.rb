@@ -4,7 +4,7 @@ module Inkoc
   module Codegen
     class Serializer
       SIGNATURE = 'inko'.bytes
-      VERSION = 1
+      VERSION = 3
 
       INTEGER_LITERAL = 0
       FLOAT_LITERAL = 1
@@ -42,20 +42,42 @@ module Inkoc
 
       def serialize(output)
         mods = @compiler.modules
+        indexed = @compiler.state.config.module_index?
+        payload = []
+
+        if indexed
+          module_index(mods, payload)
+        else
+          u64(mods.length, payload)
+
+          mods.each do |mod|
+            code_module(mod, payload)
+          end
+        end
 
         SIGNATURE.each do |byte|
           u8(byte, output)
         end
 
         u8(VERSION, output)
+        string(Inkoc::VERSION, output)
+        metadata(output)
         entry_point(output)
-        u64(mods.length, output)
+        boolean(indexed, output)
+        u32(Zlib.crc32(payload.pack('C*')), output)
 
-        mods.each do |mod|
-          code_module(mod, output)
-        end
+        output.concat(payload)
+      end
 
-        output
+      def metadata(output)
+        entries = @compiler.state.config.metadata
+
+        u64(entries.length, output)
+
+        entries.each do |key, value|
+          string(key, output)
+          string(value, output)
+        end
       end
 
       def entry_point(output)
@@ -70,8 +92,7 @@ module Inkoc
 
         size_before = output.length
 
-        array(mod.literals.to_a, :literal, output)
-        compiled_code(mod.body, output)
+        module_body(mod, output)
 
         size = output.length - size_before
 
@@ -88,6 +109,39 @@ module Inkoc
         output[start + 7] = ((size >> 56) & 0xFF)
       end
 
+      def module_body(mod, output)
+        array(mod.literals.to_a, :literal, output)
+        compiled_code(mod.body, output)
+      end
+
+      # Serializes the modules, preceded by an index of the name, offset and
+      # size of every module. This allows the VM to only parse a module when
+      # it's first used.
+      def module_index(mods, output)
+        bodies = mods.map do |mod|
+          body = []
+
+          module_body(mod, body)
+          body
+        end
+
+        u64(mods.length, output)
+
+        offset = 0
+
+        mods.zip(bodies) do |mod, body|
+          string(mod.name.to_s, output)
+          u64(offset, output)
+          u64(body.length, output)
+
+          offset += body.length
+        end
+
+        bodies.each do |body|
+          output.concat(body)
+        end
+      end
+
       def string(str, output)
         str = str.to_s
 
@@ -49,12 +49,21 @@ module Inkoc
       main_mod = compile(name, path)
 
       if @state.config.compile?
+        add_default_metadata(path)
         Codegen::Serializer.new(self, main_mod).serialize_to_file(output)
       end
 
       output
     end
 
+    # path - The absolute file path of the main module, as a Pathname.
+    def add_default_metadata(path)
+      metadata = @state.config.metadata
+
+      metadata['build_time'] ||= Time.now.utc.iso8601
+      metadata['source_root'] ||= path.dirname.to_s
+    end
+
     # name - The QualifiedName of the module.
     # path - The absolute file path of the module to compile, as a Pathname.
     def compile(name, path)
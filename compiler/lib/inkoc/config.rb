@@ -55,6 +55,7 @@ module Inkoc
     HASHER_TYPE = 'DefaultHasher'
     GENERATOR_TYPE = 'Generator'
     CHILD_PROCESS_TYPE = 'ChildProcess'
+    PROCESS_EXIT_TYPE = 'ProcessExit'
     SELF_TYPE = 'Self'
     NEVER_TYPE = 'Never'
     ANY_TYPE = 'Any'
@@ -104,21 +105,31 @@ module Inkoc
       'loop' => %w[loop while]
     }.freeze
 
-    attr_reader :source_directories
+    attr_reader :source_directories, :metadata
 
     def self.std_module_name(name)
       "#{STD_MODULE}#{MODULE_SEPARATOR}#{name}"
     end
 
-    def initialize(compile: true)
+    def initialize(compile: true, module_index: false)
       @source_directories = Set.new([runtime_directory])
       @compile = compile
+      @module_index = module_index
+      @metadata = {}
+    end
+
+    def add_metadata(key, value)
+      @metadata[key.to_s] = value.to_s
     end
 
     def compile?
       @compile
     end
 
+    def module_index?
+      @module_index
+    end
+
     def add_source_directories(directories)
       directories.each do |dir|
         @source_directories << Pathname.new(File.expand_path(dir))
@@ -1144,6 +1144,10 @@ module Inkoc
         builtin_prototype_instruction(PrototypeID::CHILD_PROCESS, node, body)
       end
 
+      def on_raw_get_process_exit_prototype(node, body)
+        builtin_prototype_instruction(PrototypeID::PROCESS_EXIT, node, body)
+      end
+
       def on_raw_get_block_prototype(node, body)
         builtin_prototype_instruction(PrototypeID::BLOCK, node, body)
       end
@@ -1209,6 +1213,10 @@ module Inkoc
         raw_unary_instruction(:ProcessReceiveMessage, node, body)
       end
 
+      def on_raw_process_receive_matching(node, body)
+        raw_binary_instruction(:ProcessReceiveMatching, node, body)
+      end
+
       def on_raw_process_current(node, body)
         raw_nullary_instruction(:ProcessCurrent, node, body)
       end
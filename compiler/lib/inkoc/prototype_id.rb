@@ -24,5 +24,6 @@ module Inkoc
     GENERATOR = 19
     TRAIT = 20
     CHILD_PROCESS = 21
+    PROCESS_EXIT = 22
   end
 end
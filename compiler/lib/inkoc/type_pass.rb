@@ -417,6 +417,10 @@ module Inkoc
       typedb.child_process_type
     end
 
+    def on_raw_get_process_exit_prototype(*)
+      typedb.process_exit_type
+    end
+
     def on_raw_get_array_prototype(*)
       typedb.array_type
     end
@@ -473,6 +477,10 @@ module Inkoc
       new_object_type
     end
 
+    def on_raw_process_receive_matching(node, *)
+      new_object_type
+    end
+
     def on_raw_process_current(node, _)
       typedb.process_type.new_instance
     end
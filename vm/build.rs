@@ -1,5 +1,21 @@
+use std::env;
+use std::process::Command;
+
 fn main() {
     if !cfg!(target_pointer_width = "64") {
         panic!("The Inko virtual machine requires a 64-bits architecture");
     }
+
+    // Native extensions must be compiled using the same version of Rust, as
+    // they use the Rust ABI. We expose the version so we can verify this when
+    // loading an extension.
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=INKO_RUSTC_VERSION={}", version.trim());
 }
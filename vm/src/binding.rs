@@ -84,6 +84,21 @@ impl Binding {
         found
     }
 
+    /// Returns the parent binding `depth` steps up from the current binding,
+    /// if it defines the local variable with the given index.
+    ///
+    /// Bytecode can't be used to verify the number of local variables of a
+    /// parent binding, so this must be checked whenever accessing its local
+    /// variables.
+    pub fn find_parent_with_local(
+        &self,
+        depth: usize,
+        index: u16,
+    ) -> Option<&RcBinding> {
+        self.find_parent(depth)
+            .filter(|binding| (index as usize) < binding.locals().len())
+    }
+
     /// Returns an immutable reference to this binding's local variables.
     pub fn locals(&self) -> &Chunk<ObjectPointer> {
         unsafe { &*self.locals.get() }
@@ -258,6 +273,16 @@ mod tests {
         assert!(binding4.find_parent(3).is_none());
     }
 
+    #[test]
+    fn test_find_parent_with_local() {
+        let binding1 = Binding::new(2, ObjectPointer::integer(1), None);
+        let binding2 = binding_with_parent(binding1, 0);
+
+        assert!(binding2.find_parent_with_local(0, 1).is_some());
+        assert!(binding2.find_parent_with_local(0, 2).is_none());
+        assert!(binding2.find_parent_with_local(1, 0).is_none());
+    }
+
     #[test]
     fn test_locals() {
         let ptr = ObjectPointer::integer(5);
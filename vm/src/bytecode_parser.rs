@@ -8,17 +8,22 @@
 //! same endianness means we don't have to flip bits around on these CPUs.
 use crate::catch_table::{CatchEntry, CatchTable};
 use crate::compiled_code::CompiledCode;
+use crate::inline_cache::inline_caches;
+#[cfg(feature = "jit")]
+use crate::jit::Jit;
 use crate::module::Module;
 use crate::object_pointer::ObjectPointer;
-use crate::vm::instruction::{Instruction, Opcode};
+use crate::peephole;
+use crate::vm::instruction::{Instruction, Opcode, Operand};
 use crate::vm::state::State;
+use crc32fast::Hasher;
 use crossbeam_channel::bounded;
 use crossbeam_utils::thread::scope;
 use num_bigint::BigInt;
 use std::f64;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read};
-use std::mem;
+use std::io::{self, BufReader, Read};
 use std::str;
 
 macro_rules! read_slice {
@@ -48,19 +53,22 @@ macro_rules! read_byte {
 }
 
 /// The bytes that every bytecode file must start with.
-const SIGNATURE_BYTES: [u8; 4] = [105, 110, 107, 111]; // "inko"
+pub const SIGNATURE_BYTES: [u8; 4] = [105, 110, 107, 111]; // "inko"
 
 /// The current version of the bytecode format.
-const VERSION: u8 = 1;
+pub const VERSION: u8 = 3;
+
+/// The maximum number of metadata entries an image can contain.
+const MAX_METADATA_ENTRIES: u64 = u16::MAX as u64;
 
 /// The tag that marks the start of an integer literal.
 const LITERAL_INTEGER: u8 = 0;
 
 /// The tag that marks the start of a float literal.
-const LITERAL_FLOAT: u8 = 1;
+pub const LITERAL_FLOAT: u8 = 1;
 
 /// The tag that marks the start of a string literal.
-const LITERAL_STRING: u8 = 2;
+pub const LITERAL_STRING: u8 = 2;
 
 /// The tag that marks the start of a big integer literal.
 const LITERAL_BIGINT: u8 = 3;
@@ -82,6 +90,110 @@ pub enum ParserError {
     MissingByte,
     SizeTooLarge,
     TooManyInstructionArguments,
+    InvalidOpcode(u8),
+    EmptyInstructions,
+    MissingTerminator,
+    InvalidRegister {
+        instruction: usize,
+        register: usize,
+    },
+    InvalidLocal {
+        instruction: usize,
+        local: u16,
+    },
+    InvalidLiteralIndex {
+        instruction: usize,
+        literal: usize,
+    },
+    InvalidCodeObject {
+        instruction: usize,
+        code_object: u16,
+    },
+    InvalidJump {
+        instruction: usize,
+        target: u16,
+    },
+    InvalidCatchEntry {
+        entry: usize,
+    },
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParserError::InvalidFile => write!(f, "The file is invalid"),
+            ParserError::InvalidSignature => {
+                write!(f, "The bytecode signature is invalid")
+            }
+            ParserError::InvalidVersion => {
+                write!(f, "The bytecode version is not supported")
+            }
+            ParserError::InvalidString => write!(f, "A string is invalid"),
+            ParserError::InvalidByteArray => {
+                write!(f, "A byte array is invalid")
+            }
+            ParserError::InvalidInteger => write!(f, "An integer is invalid"),
+            ParserError::InvalidBigInteger => {
+                write!(f, "A big integer is invalid")
+            }
+            ParserError::InvalidFloat => write!(f, "A float is invalid"),
+            ParserError::InvalidLiteral => write!(f, "A literal is invalid"),
+            ParserError::MissingByte => write!(f, "A byte is missing"),
+            ParserError::SizeTooLarge => write!(f, "A size is too large"),
+            ParserError::TooManyInstructionArguments => {
+                write!(f, "An instruction has too many arguments")
+            }
+            ParserError::InvalidOpcode(byte) => {
+                write!(f, "The opcode {} is invalid", byte)
+            }
+            ParserError::EmptyInstructions => {
+                write!(f, "The instruction sequence is empty")
+            }
+            ParserError::MissingTerminator => {
+                write!(f, "The last instruction doesn't return, throw, or jump")
+            }
+            ParserError::InvalidRegister {
+                instruction,
+                register,
+            } => write!(
+                f,
+                "Instruction {} uses the undefined register {}",
+                instruction, register
+            ),
+            ParserError::InvalidLocal { instruction, local } => write!(
+                f,
+                "Instruction {} uses the undefined local variable {}",
+                instruction, local
+            ),
+            ParserError::InvalidLiteralIndex {
+                instruction,
+                literal,
+            } => write!(
+                f,
+                "Instruction {} uses the undefined literal {}",
+                instruction, literal
+            ),
+            ParserError::InvalidCodeObject {
+                instruction,
+                code_object,
+            } => write!(
+                f,
+                "Instruction {} uses the undefined code object {}",
+                instruction, code_object
+            ),
+            ParserError::InvalidJump {
+                instruction,
+                target,
+            } => write!(
+                f,
+                "Instruction {} jumps to the undefined instruction {}",
+                instruction, target
+            ),
+            ParserError::InvalidCatchEntry { entry } => {
+                write!(f, "The catch table entry {} is invalid", entry)
+            }
+        }
+    }
 }
 
 pub type ParserResult<T> = Result<T, ParserError>;
@@ -89,11 +201,84 @@ pub type BytecodeResult = ParserResult<Vec<Module>>;
 
 /// A parsed bytecode image.
 pub struct Image {
+    /// The version of the compiler that produced the image.
+    pub compiler_version: String,
+
+    /// The key/value pairs stored in the metadata section of the image, such
+    /// as the time the image was built at.
+    pub metadata: Vec<(String, String)>,
+
     /// The first module to run.
     pub entry_point: String,
 
     /// All the modules that have been parsed.
     pub modules: Vec<Module>,
+
+    /// The modules that are to be parsed when they are first used.
+    ///
+    /// This is only populated for images that include a module index.
+    pub lazy_modules: Vec<LazyModule>,
+}
+
+/// A module that has yet to be parsed.
+pub struct LazyModule {
+    /// The fully qualified name of the module.
+    pub name: String,
+
+    /// The bytecode of the module.
+    bytecode: Vec<u8>,
+}
+
+impl LazyModule {
+    pub fn new(name: String, bytecode: Vec<u8>) -> Self {
+        LazyModule { name, bytecode }
+    }
+
+    /// Parses and verifies the bytecode of the module.
+    pub fn parse(&self, state: &State) -> Result<Module, String> {
+        let module = read_module(state, &mut &self.bytecode[..])?;
+        let name_ptr = module.name();
+        let name = name_ptr.string_value()?;
+
+        if name.as_slice() != self.name {
+            return Err(format!(
+                "The module index refers to the module {}, but the bytecode \
+                defines the module {}",
+                self.name, name
+            ));
+        }
+
+        Ok(module)
+    }
+}
+
+/// A reader that computes the CRC32 checksum of all the bytes read from a
+/// stream.
+struct ChecksumReader<'a> {
+    stream: &'a mut dyn Read,
+    hasher: Hasher,
+}
+
+impl<'a> ChecksumReader<'a> {
+    fn new(stream: &'a mut dyn Read) -> Self {
+        ChecksumReader {
+            stream,
+            hasher: Hasher::new(),
+        }
+    }
+
+    fn checksum(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+}
+
+impl<'a> Read for ChecksumReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.stream.read(buf)?;
+
+        self.hasher.update(&buf[0..read]);
+        Ok(read)
+    }
 }
 
 /// Parses a bytecode image stored in a file.
@@ -118,21 +303,148 @@ pub fn parse(state: &State, stream: &mut dyn Read) -> Result<Image, String> {
         ));
     }
 
+    let compiler_version = read_string(stream)?;
+    let metadata = read_metadata(stream)?;
     let entry_point = read_string(stream)?;
-    let modules = read_modules(state, stream)?;
+    let indexed = read_bool(stream)?;
+    let checksum = read_u32(stream)?;
+    let (modules, lazy_modules) = if indexed {
+        (Vec::new(), read_module_index(stream, checksum)?)
+    } else {
+        (read_modules(state, stream, checksum)?, Vec::new())
+    };
 
     Ok(Image {
+        compiler_version,
+        metadata,
         entry_point,
         modules,
+        lazy_modules,
     })
 }
 
+fn read_metadata(
+    stream: &mut dyn Read,
+) -> Result<Vec<(String, String)>, String> {
+    let amount = read_u64_with_limit(stream, MAX_METADATA_ENTRIES)? as usize;
+    let mut entries = Vec::with_capacity(amount);
+
+    for _ in 0..amount {
+        let key = read_string(stream)?;
+        let value = read_string(stream)?;
+
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
+
+fn verify_checksum(
+    stream: &ChecksumReader,
+    checksum: u32,
+) -> Result<(), String> {
+    if stream.checksum() == checksum {
+        return Ok(());
+    }
+
+    Err(format!(
+        "The checksum {:#010x} of the modules doesn't match the expected \
+        checksum {:#010x}, meaning the image is corrupt",
+        stream.checksum(),
+        checksum
+    ))
+}
+
+/// Reads the module index and the bytecode of every module, without parsing
+/// the modules.
+fn read_module_index(
+    input: &mut dyn Read,
+    checksum: u32,
+) -> Result<Vec<LazyModule>, String> {
+    let mut stream = ChecksumReader::new(input);
+    let num_modules = read_u64_with_limit(&mut stream, u32::MAX as u64)?;
+    let mut entries = Vec::new();
+
+    for _ in 0..num_modules {
+        let name = read_string(&mut stream)?;
+        let offset = read_u64(&mut stream)?;
+        let size = read_u64(&mut stream)?;
+
+        entries.push((name, offset, size));
+    }
+
+    // The bytecode of every module is read into its own buffer, instead of
+    // reading all modules into a single buffer and copying the bytecode out of
+    // it. This requires the modules to be read in the order they are stored.
+    entries.sort_by_key(|(_, offset, _)| *offset);
+
+    let mut modules = Vec::with_capacity(entries.len());
+    let mut position = 0;
+
+    for (name, offset, size) in entries {
+        let invalid = || {
+            format!(
+                "The module index entry for the module {} refers to bytes \
+                that don't belong to the module",
+                name
+            )
+        };
+
+        if offset < position {
+            return Err(invalid());
+        }
+
+        skip_bytes(&mut stream, offset - position)?;
+
+        let mut bytecode = Vec::new();
+
+        // Taking the bytes from the stream (instead of allocating a buffer of
+        // the given size) ensures a corrupt size can't allocate more memory
+        // than the size of the image.
+        (&mut stream)
+            .take(size)
+            .read_to_end(&mut bytecode)
+            .map_err(|e| e.to_string())?;
+
+        if (bytecode.len() as u64) < size {
+            return Err(invalid());
+        }
+
+        position = offset + size;
+
+        modules.push(LazyModule::new(name, bytecode));
+    }
+
+    // The checksum covers all remaining bytes, even those not used by any
+    // module.
+    io::copy(&mut stream, &mut io::sink()).map_err(|e| e.to_string())?;
+    verify_checksum(&stream, checksum)?;
+
+    Ok(modules)
+}
+
+/// Reads and discards the given number of bytes.
+fn skip_bytes(stream: &mut dyn Read, amount: u64) -> Result<(), String> {
+    let skipped = io::copy(&mut stream.take(amount), &mut io::sink())
+        .map_err(|e| e.to_string())?;
+
+    if skipped < amount {
+        return Err("The module index refers to bytes beyond the end of the \
+            image"
+            .to_string());
+    }
+
+    Ok(())
+}
+
 fn read_modules(
     state: &State,
-    stream: &mut dyn Read,
+    input: &mut dyn Read,
+    checksum: u32,
 ) -> Result<Vec<Module>, String> {
+    let mut stream = ChecksumReader::new(input);
     let concurrency = state.config.bytecode_threads;
-    let num_modules = read_u64(stream)? as usize;
+    let num_modules = read_u64(&mut stream)? as usize;
     let (in_sender, in_receiver) = bounded::<Vec<u8>>(num_modules);
 
     scope(|s| {
@@ -154,7 +466,7 @@ fn read_modules(
         }
 
         for _ in 0..num_modules {
-            let amount = read_u64(stream)? as usize;
+            let amount = read_u64(&mut stream)? as usize;
             let chunk = read_vec!(stream, amount);
 
             in_sender
@@ -162,6 +474,10 @@ fn read_modules(
                 .expect("Failed to send a chunk of bytecode");
         }
 
+        // Parser threads may produce confusing errors when the image is
+        // corrupt, so we check the checksum before joining them.
+        verify_checksum(&stream, checksum)?;
+
         // We need to drop the sender before joining. If we don't, parser
         // threads won't terminate until the end of this scope. But since we are
         // joining those threads, we'd never reach that point.
@@ -180,6 +496,11 @@ fn read_modules(
 fn read_module(state: &State, stream: &mut dyn Read) -> Result<Module, String> {
     let literals = read_literals_vector(state, stream)?;
     let compiled_code = read_compiled_code(state, stream, &literals)?;
+
+    if state.config.verify_bytecode {
+        verify_compiled_code(&compiled_code, literals.len())?;
+    }
+
     let module = Module::new(compiled_code.name, compiled_code, literals);
 
     Ok(module)
@@ -276,7 +597,9 @@ fn read_code_vector(
 }
 
 fn read_instruction(stream: &mut dyn Read) -> Result<Instruction, String> {
-    let ins_type: Opcode = unsafe { mem::transmute(read_u8(stream)?) };
+    let opcode_byte = read_u8(stream)?;
+    let ins_type = Opcode::from_u8(opcode_byte)
+        .ok_or_else(|| ParserError::InvalidOpcode(opcode_byte).to_string())?;
     let amount = read_u8(stream)? as usize;
     let mut args = [0, 0, 0, 0, 0, 0];
 
@@ -337,9 +660,15 @@ fn read_compiled_code(
     let locals = read_u16(stream)?;
     let registers = read_u16(stream)?;
     let captures = read_bool(stream)?;
-    let instructions = read_instructions(stream)?;
+    let mut instructions = read_instructions(stream)?;
     let code_objects = read_code_vector(state, stream, literals)?;
-    let catch_table = read_catch_table(stream)?;
+    let mut catch_table = read_catch_table(stream)?;
+
+    if state.config.optimise_bytecode {
+        peephole::optimise(&mut instructions, &mut catch_table);
+    }
+
+    let inline_caches = inline_caches(&mut instructions);
 
     Ok(CompiledCode {
         name,
@@ -353,6 +682,9 @@ fn read_compiled_code(
         instructions,
         code_objects,
         catch_table,
+        inline_caches,
+        #[cfg(feature = "jit")]
+        jit: Jit::new(),
     })
 }
 
@@ -442,13 +774,185 @@ fn read_catch_entry(stream: &mut dyn Read) -> Result<CatchEntry, String> {
     Ok(CatchEntry::new(start, end, jump_to))
 }
 
+/// Verifies that a CompiledCode and all its child code objects only refer to
+/// data that exists.
+///
+/// The interpreter doesn't perform any bounds checking when accessing
+/// registers, local variables, literals, and instructions. This function
+/// ensures that a corrupt or malicious bytecode image can't make the VM access
+/// memory outside of these structures.
+pub fn verify_compiled_code(
+    code: &CompiledCode,
+    literals: usize,
+) -> Result<(), String> {
+    verify_code(code, literals).map_err(|err| {
+        format!(
+            "The code object {} defined in {} on line {} is invalid: {}",
+            code.name
+                .string_value()
+                .map(|s| s.as_slice())
+                .unwrap_or("?"),
+            code.file
+                .string_value()
+                .map(|s| s.as_slice())
+                .unwrap_or("?"),
+            code.line,
+            err
+        )
+    })?;
+
+    for child in &code.code_objects {
+        verify_compiled_code(child, literals)?;
+    }
+
+    Ok(())
+}
+
+/// Verifies a single CompiledCode, without verifying its child code objects.
+pub fn verify_code(code: &CompiledCode, literals: usize) -> ParserResult<()> {
+    let last = if let Some(ins) = code.instructions.last() {
+        ins
+    } else {
+        return Err(ParserError::EmptyInstructions);
+    };
+
+    for (index, ins) in code.instructions.iter().enumerate() {
+        verify_instruction(code, literals, index, ins)?;
+    }
+
+    // The interpreter doesn't check if it reached the end of the instructions,
+    // so the last instruction must never be followed by another one.
+    if !is_terminator(last.opcode) {
+        return Err(ParserError::MissingTerminator);
+    }
+
+    let len = code.instructions.len();
+
+    for (index, entry) in code.catch_table.entries.iter().enumerate() {
+        if entry.start > entry.end || entry.end > len || entry.jump_to >= len {
+            return Err(ParserError::InvalidCatchEntry { entry: index });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns true if the interpreter never runs the instruction that directly
+/// follows an instruction using the given opcode.
+fn is_terminator(opcode: Opcode) -> bool {
+    matches!(
+        opcode,
+        Opcode::Return
+            | Opcode::Throw
+            | Opcode::Goto
+            | Opcode::TailCall
+            | Opcode::Panic
+            | Opcode::Exit
+            | Opcode::ProcessTerminateCurrent
+    )
+}
+
+fn verify_instruction(
+    code: &CompiledCode,
+    literals: usize,
+    index: usize,
+    ins: &Instruction,
+) -> ParserResult<()> {
+    let registers = code.registers as usize;
+    let mut arg = 0;
+
+    for operand in ins.opcode.operands() {
+        let value = ins.arg(arg);
+
+        match operand {
+            Operand::Register => {
+                if value as usize >= registers {
+                    return Err(ParserError::InvalidRegister {
+                        instruction: index,
+                        register: value as usize,
+                    });
+                }
+            }
+            Operand::RegisterRange => {
+                let end = value as usize + ins.arg(arg + 1) as usize;
+
+                if end > registers {
+                    return Err(ParserError::InvalidRegister {
+                        instruction: index,
+                        register: end - 1,
+                    });
+                }
+
+                // Tail calls copy the registers into the local variables of
+                // the current binding.
+                if ins.opcode == Opcode::TailCall
+                    && ins.arg(arg + 1) > code.locals
+                {
+                    return Err(ParserError::InvalidLocal {
+                        instruction: index,
+                        local: ins.arg(arg + 1) - 1,
+                    });
+                }
+            }
+            Operand::Local => {
+                if value >= code.locals {
+                    return Err(ParserError::InvalidLocal {
+                        instruction: index,
+                        local: value,
+                    });
+                }
+            }
+            Operand::Literal => {
+                if value as usize >= literals {
+                    return Err(ParserError::InvalidLiteralIndex {
+                        instruction: index,
+                        literal: value as usize,
+                    });
+                }
+            }
+            Operand::WideLiteral => {
+                let literal = ((value as usize) << 16)
+                    | (ins.arg(arg + 1) as usize & 0xFFFF);
+
+                if literal >= literals {
+                    return Err(ParserError::InvalidLiteralIndex {
+                        instruction: index,
+                        literal,
+                    });
+                }
+            }
+            Operand::CodeObject => {
+                if value as usize >= code.code_objects.len() {
+                    return Err(ParserError::InvalidCodeObject {
+                        instruction: index,
+                        code_object: value,
+                    });
+                }
+            }
+            Operand::Jump => {
+                if value as usize >= code.instructions.len() {
+                    return Err(ParserError::InvalidJump {
+                        instruction: index,
+                        target: value,
+                    });
+                }
+            }
+            Operand::Global | Operand::Value => {}
+        }
+
+        arg += operand.width();
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
     use crate::vm::instruction::Opcode;
     use crate::vm::state::{RcState, State};
-    use std::mem;
+    use crate::vm::test::*;
     use std::u64;
 
     fn state() -> RcState {
@@ -470,58 +974,6 @@ mod tests {
         };
     }
 
-    macro_rules! pack_u8 {
-        ($num:expr, $buffer:expr) => {{
-            let num = u8::to_le($num);
-            let bytes = [num];
-
-            $buffer.extend_from_slice(&bytes);
-        }};
-    }
-
-    macro_rules! pack_u16 {
-        ($num:expr, $buffer:expr) => {{
-            let num = u16::to_le($num);
-            let bytes: [u8; 2] = unsafe { mem::transmute(num) };
-
-            $buffer.extend_from_slice(&bytes);
-        }};
-    }
-
-    macro_rules! pack_u32 {
-        ($num:expr, $buffer:expr) => {{
-            let num = u32::to_le($num);
-            let bytes: [u8; 4] = unsafe { mem::transmute(num) };
-
-            $buffer.extend_from_slice(&bytes);
-        }};
-    }
-
-    macro_rules! pack_u64 {
-        ($num:expr, $buffer:expr) => {{
-            let num = u64::to_le($num);
-            let bytes: [u8; 8] = unsafe { mem::transmute(num) };
-
-            $buffer.extend_from_slice(&bytes);
-        }};
-    }
-
-    macro_rules! pack_f64 {
-        ($num:expr, $buffer:expr) => {{
-            let int: u64 = unsafe { mem::transmute($num) };
-
-            pack_u64!(int, $buffer);
-        }};
-    }
-
-    macro_rules! pack_string {
-        ($string:expr, $buffer:expr) => {{
-            pack_u64!($string.len() as u64, $buffer);
-
-            $buffer.extend_from_slice(&$string.as_bytes());
-        }};
-    }
-
     #[test]
     fn test_parse_empty() {
         let buffer = Vec::new();
@@ -536,7 +988,7 @@ mod tests {
         let mut buffer = Vec::new();
         let state = state();
 
-        pack_string!("cats", buffer);
+        pack_string("cats", &mut buffer);
 
         let output = parse(&state, &mut BufReader::new(buffer.as_slice()));
 
@@ -560,10 +1012,69 @@ mod tests {
         assert!(output.is_err());
     }
 
-    #[test]
-    fn test_parse() {
+    fn module_body() -> Vec<u8> {
+        module_body_with_registers(1)
+    }
+
+    fn module_body_with_registers(registers: u16) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        pack_u64(2, &mut buffer); // literals
+
+        pack_u8(LITERAL_STRING, &mut buffer);
+        pack_string("main", &mut buffer);
+
+        pack_u8(LITERAL_STRING, &mut buffer);
+        pack_string("test.inko", &mut buffer);
+
+        pack_u32(0, &mut buffer); // name
+        pack_u32(1, &mut buffer); // file
+        pack_u16(4, &mut buffer); // line
+        pack_u64(0, &mut buffer); // arguments
+        pack_u8(0, &mut buffer); // required arguments
+        pack_u16(0, &mut buffer); // locals
+        pack_u16(registers, &mut buffer); // registers
+        pack_u8(0, &mut buffer); // captures
+
+        pack_u64(1, &mut buffer); // instructions
+
+        pack_u8(Opcode::Return as u8, &mut buffer);
+        pack_u8(1, &mut buffer); // args count
+        pack_u16(6, &mut buffer); // arg 1
+        pack_u16(2, &mut buffer); // line number
+
+        pack_u64(0, &mut buffer); // code objects
+        pack_u64(0, &mut buffer); // catch table entries
+
+        buffer
+    }
+
+    fn module_payload() -> Vec<u8> {
+        let body = module_body();
+        let mut buffer = Vec::new();
+
+        pack_u64(1, &mut buffer); // 1 module
+        pack_u64(body.len() as u64, &mut buffer);
+
+        buffer.extend_from_slice(&body);
+        buffer
+    }
+
+    fn indexed_module_payload(name: &str) -> Vec<u8> {
+        let body = module_body();
+        let mut buffer = Vec::new();
+
+        pack_u64(1, &mut buffer); // 1 module
+        pack_string(name, &mut buffer);
+        pack_u64(0, &mut buffer); // offset
+        pack_u64(body.len() as u64, &mut buffer);
+
+        buffer.extend_from_slice(&body);
+        buffer
+    }
+
+    fn image_header(indexed: bool, checksum: u32) -> Vec<u8> {
         let mut buffer = Vec::new();
-        let state = state();
 
         buffer.push(105);
         buffer.push(110);
@@ -572,42 +1083,37 @@ mod tests {
 
         buffer.push(VERSION);
 
-        pack_string!("main", buffer); // entry point
-
-        pack_u64!(1, buffer); // 1 module
-        pack_u64!(93, buffer); // number of bytes beyond this point
-        pack_u64!(2, buffer); // literals
-
-        pack_u8!(LITERAL_STRING, buffer);
-        pack_string!("main", buffer);
+        pack_string("0.9.0", &mut buffer); // compiler version
 
-        pack_u8!(LITERAL_STRING, buffer);
-        pack_string!("test.inko", buffer);
+        pack_u64(1, &mut buffer); // metadata entries
+        pack_string("source_root", &mut buffer);
+        pack_string("/tmp", &mut buffer);
 
-        pack_u32!(0, buffer); // name
-        pack_u32!(1, buffer); // file
-        pack_u16!(4, buffer); // line
-        pack_u64!(0, buffer); // arguments
-        pack_u8!(0, buffer); // required arguments
-        pack_u16!(0, buffer); // locals
-        pack_u16!(0, buffer); // registers
-        pack_u8!(0, buffer); // captures
+        pack_string("main", &mut buffer); // entry point
+        pack_u8(indexed as u8, &mut buffer);
+        pack_u32(checksum, &mut buffer);
 
-        pack_u64!(1, buffer); // instructions
+        buffer
+    }
 
-        pack_u8!(Opcode::Return as u8, buffer);
-        pack_u8!(1, buffer); // args count
-        pack_u16!(6, buffer); // arg 1
-        pack_u16!(2, buffer); // line number
+    #[test]
+    fn test_parse() {
+        let payload = module_payload();
+        let mut buffer = image_header(false, crc32fast::hash(&payload));
+        let state = state();
 
-        pack_u64!(0, buffer); // code objects
-        pack_u64!(0, buffer); // catch table entries
+        buffer.extend_from_slice(&payload);
 
         let image =
             unwrap!(parse(&state, &mut BufReader::new(buffer.as_slice())));
 
         let module = &image.modules[0];
 
+        assert_eq!(&image.compiler_version, "0.9.0");
+        assert_eq!(
+            image.metadata,
+            vec![("source_root".to_string(), "/tmp".to_string())]
+        );
         assert_eq!(&image.entry_point, "main");
         assert_eq!(module.name().string_value().unwrap().as_slice(), "main");
         assert_eq!(
@@ -616,11 +1122,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_module_invalid_code() {
+        let body = module_body_with_registers(0);
+        let state = state();
+
+        assert!(read_module(&state, &mut &body[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_module_without_verification() {
+        let body = module_body_with_registers(0);
+        let mut config = Config::new();
+
+        config.verify_bytecode = false;
+
+        let state = State::with_rc(config, &[]);
+
+        assert!(read_module(&state, &mut &body[..]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_invalid_checksum() {
+        let payload = module_payload();
+        let mut buffer =
+            image_header(false, crc32fast::hash(&payload).wrapping_add(1));
+        let state = state();
+
+        buffer.extend_from_slice(&payload);
+
+        let output = parse(&state, &mut BufReader::new(buffer.as_slice()));
+
+        assert!(output.is_err());
+        assert!(output.err().unwrap().contains("checksum"));
+    }
+
+    #[test]
+    fn test_parse_with_module_index() {
+        let payload = indexed_module_payload("main");
+        let mut buffer = image_header(true, crc32fast::hash(&payload));
+        let state = state();
+
+        buffer.extend_from_slice(&payload);
+
+        let image =
+            unwrap!(parse(&state, &mut BufReader::new(buffer.as_slice())));
+
+        assert!(image.modules.is_empty());
+        assert_eq!(image.lazy_modules.len(), 1);
+        assert_eq!(&image.lazy_modules[0].name, "main");
+
+        let module = unwrap!(image.lazy_modules[0].parse(&state));
+
+        assert_eq!(module.name().string_value().unwrap().as_slice(), "main");
+    }
+
+    #[test]
+    fn test_parse_with_invalid_module_index_name() {
+        let payload = indexed_module_payload("foo");
+        let mut buffer = image_header(true, crc32fast::hash(&payload));
+        let state = state();
+
+        buffer.extend_from_slice(&payload);
+
+        let image =
+            unwrap!(parse(&state, &mut BufReader::new(buffer.as_slice())));
+
+        assert!(image.lazy_modules[0].parse(&state).is_err());
+    }
+
+    #[test]
+    fn test_parse_with_invalid_module_index_offset() {
+        let mut payload = Vec::new();
+        let state = state();
+
+        pack_u64(1, &mut payload); // 1 module
+        pack_string("main", &mut payload);
+        pack_u64(4, &mut payload); // offset
+        pack_u64(8, &mut payload); // size
+        pack_u64(0, &mut payload);
+
+        let mut buffer = image_header(true, crc32fast::hash(&payload));
+
+        buffer.extend_from_slice(&payload);
+
+        let output = parse(&state, &mut BufReader::new(buffer.as_slice()));
+
+        assert!(output.is_err());
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let mut buffer = Vec::new();
+
+        pack_u64(2, &mut buffer);
+        pack_string("a", &mut buffer);
+        pack_string("1", &mut buffer);
+        pack_string("b", &mut buffer);
+        pack_string("2", &mut buffer);
+
+        let output = unwrap!(read!(read_metadata, buffer));
+
+        assert_eq!(
+            output,
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_metadata_too_many_entries() {
+        let mut buffer = Vec::new();
+
+        pack_u64(MAX_METADATA_ENTRIES + 1, &mut buffer);
+
+        let output = read!(read_metadata, buffer);
+
+        assert!(output.is_err());
+    }
+
     #[test]
     fn test_read_string() {
         let mut buffer = Vec::new();
 
-        pack_string!("inko", buffer);
+        pack_string("inko", &mut buffer);
 
         let output = unwrap!(read!(read_string, buffer));
 
@@ -631,7 +1258,7 @@ mod tests {
     fn test_read_string_too_large() {
         let mut buffer = Vec::new();
 
-        pack_u64!(u64::MAX, buffer);
+        pack_u64(u64::MAX, &mut buffer);
 
         let output = read_string(&mut BufReader::new(buffer.as_slice()));
 
@@ -642,7 +1269,7 @@ mod tests {
     fn test_read_byte_array() {
         let mut buffer = Vec::new();
 
-        pack_string!("inko", buffer);
+        pack_string("inko", &mut buffer);
 
         let output = unwrap!(read!(read_byte_array, buffer));
 
@@ -653,7 +1280,7 @@ mod tests {
     fn test_read_byte_array_too_large() {
         let mut buffer = Vec::new();
 
-        pack_u64!(u64::MAX, buffer);
+        pack_u64(u64::MAX, &mut buffer);
 
         let output = read_byte_array(&mut BufReader::new(buffer.as_slice()));
 
@@ -664,7 +1291,7 @@ mod tests {
     fn test_read_string_longer_than_size() {
         let mut buffer = Vec::new();
 
-        pack_u64!(2, buffer);
+        pack_u64(2, &mut buffer);
 
         buffer.extend_from_slice(&"inko".as_bytes());
 
@@ -678,7 +1305,7 @@ mod tests {
         let mut buffer = Vec::new();
         let bytes: [u8; 4] = [0, 159, 146, 150];
 
-        pack_u64!(4, buffer);
+        pack_u64(4, &mut buffer);
 
         buffer.extend_from_slice(&bytes);
 
@@ -698,7 +1325,7 @@ mod tests {
     fn test_read_u8() {
         let mut buffer = Vec::new();
 
-        pack_u8!(2, buffer);
+        pack_u8(2, &mut buffer);
 
         let output = unwrap!(read!(read_u8, buffer));
 
@@ -716,7 +1343,7 @@ mod tests {
     fn test_read_u16() {
         let mut buffer = Vec::new();
 
-        pack_u16!(2, buffer);
+        pack_u16(2, &mut buffer);
 
         let output = unwrap!(read!(read_u16, buffer));
 
@@ -734,7 +1361,7 @@ mod tests {
     fn test_read_i64() {
         let mut buffer = Vec::new();
 
-        pack_u64!(2, buffer);
+        pack_u64(2, &mut buffer);
 
         let output = unwrap!(read!(read_i64, buffer));
 
@@ -752,7 +1379,7 @@ mod tests {
     fn test_read_u64_with_limit() {
         let mut buffer = Vec::new();
 
-        pack_u64!(2, buffer);
+        pack_u64(2, &mut buffer);
 
         let output =
             read_u64_with_limit(&mut BufReader::new(buffer.as_slice()), 2);
@@ -765,7 +1392,7 @@ mod tests {
     fn test_read_u64_with_limit_exceeded() {
         let mut buffer = Vec::new();
 
-        pack_u64!(2, buffer);
+        pack_u64(2, &mut buffer);
 
         let output =
             read_u64_with_limit(&mut BufReader::new(buffer.as_slice()), 1);
@@ -777,7 +1404,7 @@ mod tests {
     fn test_read_f64() {
         let mut buffer = Vec::new();
 
-        pack_f64!(2.123456, buffer);
+        pack_f64(2.123456, &mut buffer);
 
         let output = unwrap!(read!(read_f64, buffer));
 
@@ -795,10 +1422,10 @@ mod tests {
     fn test_read_instruction() {
         let mut buffer = Vec::new();
 
-        pack_u8!(0, buffer); // type
-        pack_u8!(1, buffer); // args
-        pack_u16!(6, buffer);
-        pack_u16!(2, buffer); // line
+        pack_u8(0, &mut buffer); // type
+        pack_u8(1, &mut buffer); // args
+        pack_u16(6, &mut buffer);
+        pack_u16(2, &mut buffer); // line
 
         let ins =
             unwrap!(read_instruction(&mut BufReader::new(buffer.as_slice())));
@@ -808,15 +1435,28 @@ mod tests {
         assert_eq!(ins.line, 2);
     }
 
+    #[test]
+    fn test_read_instruction_invalid_opcode() {
+        let mut buffer = Vec::new();
+
+        pack_u8(Opcode::Throw as u8 + 1, &mut buffer); // type
+        pack_u8(0, &mut buffer); // args
+        pack_u16(2, &mut buffer); // line
+
+        let output = read_instruction(&mut BufReader::new(buffer.as_slice()));
+
+        assert!(output.is_err());
+    }
+
     #[test]
     fn test_read_instructions() {
         let mut buffer = Vec::new();
 
-        pack_u64!(1, buffer);
-        pack_u8!(0, buffer); // type
-        pack_u8!(1, buffer); // args
-        pack_u16!(6, buffer);
-        pack_u16!(2, buffer); // line
+        pack_u64(1, &mut buffer);
+        pack_u8(0, &mut buffer); // type
+        pack_u8(1, &mut buffer); // args
+        pack_u16(6, &mut buffer);
+        pack_u16(2, &mut buffer); // line
 
         let instructions =
             unwrap!(read_instructions(&mut BufReader::new(buffer.as_slice())));
@@ -842,41 +1482,41 @@ mod tests {
             state.intern_string("baz".to_string()),
         ];
 
-        pack_u32!(0, buffer); // name
-        pack_u32!(1, buffer); // file
-        pack_u16!(4, buffer); // line
+        pack_u32(0, &mut buffer); // name
+        pack_u32(1, &mut buffer); // file
+        pack_u16(4, &mut buffer); // line
 
-        pack_u64!(3, buffer); // arguments
-        pack_u32!(2, buffer); // foo
-        pack_u32!(3, buffer); // bar
-        pack_u32!(4, buffer); // baz
+        pack_u64(3, &mut buffer); // arguments
+        pack_u32(2, &mut buffer); // foo
+        pack_u32(3, &mut buffer); // bar
+        pack_u32(4, &mut buffer); // baz
 
-        pack_u8!(2, buffer); // required args
-        pack_u16!(1, buffer); // locals
-        pack_u16!(2, buffer); // registers
-        pack_u8!(1, buffer); // captures
+        pack_u8(2, &mut buffer); // required args
+        pack_u16(1, &mut buffer); // locals
+        pack_u16(2, &mut buffer); // registers
+        pack_u8(1, &mut buffer); // captures
 
         // instructions
-        pack_u64!(2, buffer);
-        pack_u8!(Opcode::SetLiteral as u8, buffer); // type
-        pack_u8!(1, buffer); // args count
-        pack_u16!(6, buffer); // arg 1
-        pack_u16!(2, buffer); // line number
+        pack_u64(2, &mut buffer);
+        pack_u8(Opcode::SetLiteral as u8, &mut buffer); // type
+        pack_u8(1, &mut buffer); // args count
+        pack_u16(6, &mut buffer); // arg 1
+        pack_u16(2, &mut buffer); // line number
 
-        pack_u8!(Opcode::Return as u8, buffer); // type
-        pack_u8!(1, buffer); // args count
-        pack_u16!(6, buffer); // arg 1
-        pack_u16!(2, buffer); // line number
+        pack_u8(Opcode::Return as u8, &mut buffer); // type
+        pack_u8(1, &mut buffer); // args count
+        pack_u16(6, &mut buffer); // arg 1
+        pack_u16(2, &mut buffer); // line number
 
         // code objects
-        pack_u64!(0, buffer);
+        pack_u64(0, &mut buffer);
 
         // catch table entries
-        pack_u64!(1, buffer);
-        pack_u16!(4, buffer); // start
-        pack_u16!(6, buffer); // end
-        pack_u16!(8, buffer); // jump-to
-        pack_u16!(10, buffer); // register
+        pack_u64(1, &mut buffer);
+        pack_u16(4, &mut buffer); // start
+        pack_u16(6, &mut buffer); // end
+        pack_u16(8, &mut buffer); // jump-to
+        pack_u16(10, &mut buffer); // register
 
         let object = unwrap!(read_compiled_code(
             &state,
@@ -909,4 +1549,202 @@ mod tests {
         assert_eq!(entry.end, 6);
         assert_eq!(entry.jump_to, 8);
     }
+
+    fn verify_code_with(
+        state: &RcState,
+        instructions: Vec<Instruction>,
+    ) -> ParserResult<()> {
+        let mut code = CompiledCode::new(
+            state.intern_string("foo".to_string()),
+            state.intern_string("foo.inko".to_string()),
+            1,
+            instructions,
+        );
+
+        code.locals = 1;
+        code.registers = 2;
+
+        verify_code(&code, 1)
+    }
+
+    fn ins(opcode: Opcode, arguments: [u16; 6]) -> Instruction {
+        Instruction::new(opcode, arguments, 1)
+    }
+
+    #[test]
+    fn test_verify_code_valid() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![
+                ins(Opcode::SetLiteral, [0, 0, 0, 0, 0, 0]),
+                ins(Opcode::SetLocal, [0, 0, 0, 0, 0, 0]),
+                ins(Opcode::GotoIfTrue, [3, 0, 0, 0, 0, 0]),
+                ins(Opcode::TailCall, [0, 1, 0, 0, 0, 0]),
+                ins(Opcode::Return, [0, 1, 0, 0, 0, 0]),
+            ],
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_verify_code_empty() {
+        let state = state();
+        let result = verify_code_with(&state, Vec::new());
+
+        assert!(matches!(result, Err(ParserError::EmptyInstructions)));
+    }
+
+    #[test]
+    fn test_verify_code_missing_terminator() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![
+                ins(Opcode::Return, [0, 1, 0, 0, 0, 0]),
+                ins(Opcode::SetLiteral, [0, 0, 0, 0, 0, 0]),
+            ],
+        );
+
+        assert!(matches!(result, Err(ParserError::MissingTerminator)));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_register() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::Return, [0, 2, 0, 0, 0, 0])],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParserError::InvalidRegister {
+                instruction: 0,
+                register: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_register_range() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::StringConcat, [0, 1, 2, 0, 0, 0])],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParserError::InvalidRegister { register: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_tail_call() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::TailCall, [0, 2, 0, 0, 0, 0])],
+        );
+
+        assert!(matches!(result, Err(ParserError::InvalidLocal { .. })));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_local() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::GetLocal, [0, 1, 0, 0, 0, 0])],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParserError::InvalidLocal {
+                instruction: 0,
+                local: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_literal() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::SetLiteralWide, [0, 1, 0, 0, 0, 0])],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParserError::InvalidLiteralIndex { literal: 65536, .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_code_object() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::SetBlock, [0, 0, 1, 0, 0, 0])],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParserError::InvalidCodeObject { code_object: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_code_invalid_jump() {
+        let state = state();
+        let result = verify_code_with(
+            &state,
+            vec![ins(Opcode::Goto, [1, 0, 0, 0, 0, 0])],
+        );
+
+        assert!(matches!(
+            result,
+            Err(ParserError::InvalidJump { target: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_compiled_code_invalid_catch_entry() {
+        let state = state();
+        let mut code = CompiledCode::new(
+            state.intern_string("foo".to_string()),
+            state.intern_string("foo.inko".to_string()),
+            1,
+            vec![ins(Opcode::ProcessTerminateCurrent, [0, 0, 0, 0, 0, 0])],
+        );
+
+        code.catch_table.entries.push(CatchEntry::new(0, 1, 1));
+
+        let result = verify_compiled_code(&code, 0);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_compiled_code_invalid_child() {
+        let state = state();
+        let name = state.intern_string("foo".to_string());
+        let file = state.intern_string("foo.inko".to_string());
+        let mut code = CompiledCode::new(
+            name,
+            file,
+            1,
+            vec![ins(Opcode::ProcessTerminateCurrent, [0, 0, 0, 0, 0, 0])],
+        );
+
+        code.code_objects
+            .push(CompiledCode::new(name, file, 2, Vec::new()));
+
+        let result = verify_compiled_code(&code, 0);
+
+        assert!(result.is_err());
+    }
 }
@@ -62,6 +62,13 @@ impl<T> Chunk<T> {
         }
     }
 
+    /// Returns a raw pointer to the first value of the chunk.
+    ///
+    /// This pointer is NULL if the chunk is empty.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+
     pub fn slice(&self, start: usize, length: usize) -> &[T] {
         unsafe { slice::from_raw_parts(self.ptr.add(start), length) }
     }
@@ -1,6 +1,9 @@
 //! Sequences of bytecode instructions with associated literal values.
 use crate::catch_table::CatchTable;
 use crate::deref_pointer::DerefPointer;
+use crate::inline_cache::{inline_caches, InlineCache};
+#[cfg(feature = "jit")]
+use crate::jit::Jit;
 use crate::object_pointer::ObjectPointer;
 use crate::vm::instruction::Instruction;
 
@@ -42,6 +45,13 @@ pub struct CompiledCode {
 
     /// The table to use for catching values.
     pub catch_table: CatchTable,
+
+    /// The inline caches of the instructions that look up attributes.
+    pub inline_caches: Vec<InlineCache>,
+
+    /// The state of the JIT compiler for this code.
+    #[cfg(feature = "jit")]
+    pub jit: Jit,
 }
 
 impl CompiledCode {
@@ -50,8 +60,10 @@ impl CompiledCode {
         name: ObjectPointer,
         file: ObjectPointer,
         line: u16,
-        instructions: Vec<Instruction>,
+        mut instructions: Vec<Instruction>,
     ) -> CompiledCode {
+        let inline_caches = inline_caches(&mut instructions);
+
         CompiledCode {
             name,
             file,
@@ -64,6 +76,9 @@ impl CompiledCode {
             instructions,
             code_objects: Vec::new(),
             catch_table: CatchTable::new(),
+            inline_caches,
+            #[cfg(feature = "jit")]
+            jit: Jit::new(),
         }
     }
 
@@ -88,6 +103,12 @@ impl CompiledCode {
         DerefPointer::new(&self.instructions.get_unchecked(index))
     }
 
+    /// Returns the inline cache with the given index, if any.
+    #[inline(always)]
+    pub fn inline_cache(&self, index: u16) -> Option<&InlineCache> {
+        self.inline_caches.get(index as usize)
+    }
+
     #[inline(always)]
     pub fn arguments_count(&self) -> usize {
         self.arguments.len()
@@ -105,7 +126,6 @@ mod tests {
     use crate::config::Config;
     use crate::vm::instruction::{Instruction, Opcode};
     use crate::vm::state::{RcState, State};
-    use std::mem;
 
     fn state() -> RcState {
         State::with_rc(Config::new(), &[])
@@ -156,7 +176,24 @@ mod tests {
     }
 
     #[test]
+    fn test_inline_cache() {
+        let state = state();
+        let ins = Instruction::new(Opcode::GetAttribute, [0; 6], 1);
+        let code = CompiledCode::new(
+            state.intern_string("foo".to_string()),
+            state.intern_string("bar.inko".to_string()),
+            1,
+            vec![ins],
+        );
+
+        assert_eq!(code.inline_caches.len(), 1);
+        assert!(code.inline_cache(0).is_some());
+        assert!(code.inline_cache(1).is_none());
+    }
+
+    #[test]
+    #[cfg(not(feature = "jit"))]
     fn test_compiled_code_size() {
-        assert_eq!(mem::size_of::<CompiledCode>(), 120);
+        assert_eq!(std::mem::size_of::<CompiledCode>(), 144);
     }
 }
@@ -5,6 +5,8 @@
 use crate::immix::block::BLOCK_SIZE;
 use std::cmp::min;
 use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 /// Sets a configuration field based on an environment variable.
 macro_rules! set_from_env {
@@ -22,8 +24,61 @@ const DEFAULT_MATURE_THRESHOLD: u32 = (4 * 1024 * 1024) / (BLOCK_SIZE as u32);
 const DEFAULT_GROWTH_FACTOR: f64 = 1.5;
 const DEFAULT_GROWTH_THRESHOLD: f64 = 0.9;
 const DEFAULT_REDUCTIONS: usize = 1000;
+const DEFAULT_JIT_THRESHOLD: usize = 1000;
+const DEFAULT_PROFILE_INTERVAL: u64 = 1000;
+
+/// What to do when a process panics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PanicPolicy {
+    /// Terminate the entire program.
+    Exit,
+
+    /// Only terminate the process that panicked, unless it's the main process.
+    Terminate,
+}
+
+impl FromStr for PanicPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "exit" => Ok(PanicPolicy::Exit),
+            "terminate" => Ok(PanicPolicy::Terminate),
+            _ => Err(format!("The panic policy {:?} is invalid", value)),
+        }
+    }
+}
+
+/// What to do when all processes are blocked, and none of them can make
+/// progress.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeadlockPolicy {
+    /// Don't detect deadlocks, leaving the program hanging.
+    Ignore,
+
+    /// Print the stack traces of all blocked processes, but keep running.
+    Report,
+
+    /// Print the stack traces of all blocked processes, then terminate the
+    /// program.
+    Terminate,
+}
+
+impl FromStr for DeadlockPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ignore" => Ok(DeadlockPolicy::Ignore),
+            "report" => Ok(DeadlockPolicy::Report),
+            "terminate" => Ok(DeadlockPolicy::Terminate),
+            _ => Err(format!("The deadlock policy {:?} is invalid", value)),
+        }
+    }
+}
 
 /// Structure containing the configuration settings for the virtual machine.
+#[derive(Clone)]
 pub struct Config {
     /// The number of primary process threads to run.
     ///
@@ -35,6 +90,11 @@ pub struct Config {
     /// This defaults to the number of CPU cores.
     pub blocking_threads: usize,
 
+    /// The number of threads to use for performing file operations.
+    ///
+    /// This defaults to the number of CPU cores.
+    pub io_threads: usize,
+
     /// The number of garbage collector threads to run.
     ///
     /// This defaults to half the number of CPU cores, with a minimum of two.
@@ -77,6 +137,12 @@ pub struct Config {
     /// mature collection.
     pub mature_threshold: u32,
 
+    /// The maximum number of bytes the heap of a single process can use.
+    ///
+    /// A process exceeding this limit is panicked. This defaults to 0, meaning
+    /// there is no limit.
+    pub heap_limit: usize,
+
     /// The block allocation growth factor for the heap.
     pub heap_growth_factor: f64,
 
@@ -86,6 +152,60 @@ pub struct Config {
 
     /// When enabled, GC timings will be printed to STDERR.
     pub print_gc_timings: bool,
+
+    /// The path of a file to write the statistics of every garbage collection
+    /// to, using one JSON object per line.
+    pub gc_log: Option<PathBuf>,
+
+    /// The directory to write heap snapshots to when receiving SIGUSR1.
+    ///
+    /// Heap snapshots can't be triggered using a signal if this is not set.
+    pub heap_snapshot_directory: Option<PathBuf>,
+
+    /// When enabled, bytecode is verified after parsing it.
+    ///
+    /// This defaults to true, and is only meant to be disabled by tools that
+    /// inspect bytecode without running it, as running unverified bytecode may
+    /// crash the VM.
+    pub verify_bytecode: bool,
+
+    /// When enabled, common instruction sequences are combined into
+    /// superinstructions when loading bytecode.
+    ///
+    /// This defaults to true.
+    pub optimise_bytecode: bool,
+
+    /// When enabled, the number of times every pair of opcodes is executed is
+    /// recorded, and printed to STDERR when the VM terminates.
+    pub opcode_statistics: bool,
+
+    /// The number of times a CompiledCode must be called before it's compiled
+    /// to machine code. A value of 0 disables the JIT compiler.
+    ///
+    /// This setting is only used when the VM is built with the "jit" feature.
+    pub jit_threshold: usize,
+
+    /// The path of a file to write a CPU profile to when the VM terminates.
+    ///
+    /// Processes are only sampled when this is set.
+    pub profile: Option<PathBuf>,
+
+    /// The number of microseconds between two samples taken by the profiler.
+    /// Defaults to 1000.
+    pub profile_interval: u64,
+
+    /// The paths of the native extensions to load when starting the VM.
+    pub extensions: Vec<PathBuf>,
+
+    /// What to do when a process panics.
+    ///
+    /// This defaults to terminating the entire program.
+    pub panic_policy: PanicPolicy,
+
+    /// What to do when all processes are blocked.
+    ///
+    /// This defaults to ignoring deadlocks.
+    pub deadlock_policy: DeadlockPolicy,
 }
 
 impl Config {
@@ -95,15 +215,28 @@ impl Config {
         Config {
             primary_threads: cpu_count,
             blocking_threads: cpu_count,
+            io_threads: cpu_count,
             gc_threads: cpu_count,
             tracer_threads: cpu_count,
             bytecode_threads: min(4, cpu_count),
             reductions: DEFAULT_REDUCTIONS,
             young_threshold: DEFAULT_YOUNG_THRESHOLD,
             mature_threshold: DEFAULT_MATURE_THRESHOLD,
+            heap_limit: 0,
             heap_growth_factor: DEFAULT_GROWTH_FACTOR,
             heap_growth_threshold: DEFAULT_GROWTH_THRESHOLD,
             print_gc_timings: false,
+            gc_log: None,
+            heap_snapshot_directory: None,
+            verify_bytecode: true,
+            optimise_bytecode: true,
+            opcode_statistics: false,
+            jit_threshold: DEFAULT_JIT_THRESHOLD,
+            profile: None,
+            profile_interval: DEFAULT_PROFILE_INTERVAL,
+            extensions: Vec::new(),
+            panic_policy: PanicPolicy::Exit,
+            deadlock_policy: DeadlockPolicy::Ignore,
         }
     }
 
@@ -115,6 +248,7 @@ impl Config {
     pub fn populate_from_env(&mut self) {
         set_from_env!(self, primary_threads, "PRIMARY_THREADS", usize);
         set_from_env!(self, blocking_threads, "BLOCKING_THREADS", usize);
+        set_from_env!(self, io_threads, "IO_THREADS", usize);
         set_from_env!(self, gc_threads, "GC_THREADS", usize);
         set_from_env!(self, tracer_threads, "TRACER_THREADS", usize);
         set_from_env!(self, bytecode_threads, "BYTECODE_THREADS", usize);
@@ -123,6 +257,7 @@ impl Config {
 
         set_from_env!(self, young_threshold, "YOUNG_THRESHOLD", u32);
         set_from_env!(self, mature_threshold, "MATURE_THRESHOLD", u32);
+        set_from_env!(self, heap_limit, "HEAP_LIMIT", usize);
         set_from_env!(self, heap_growth_factor, "HEAP_GROWTH_FACTOR", f64);
 
         set_from_env!(
@@ -133,6 +268,28 @@ impl Config {
         );
 
         set_from_env!(self, print_gc_timings, "PRINT_GC_TIMINGS", bool);
+        set_from_env!(self, optimise_bytecode, "OPTIMISE_BYTECODE", bool);
+        set_from_env!(self, opcode_statistics, "OPCODE_STATISTICS", bool);
+        set_from_env!(self, jit_threshold, "JIT_THRESHOLD", usize);
+        set_from_env!(self, profile_interval, "PROFILE_INTERVAL", u64);
+        set_from_env!(self, panic_policy, "PANIC_POLICY", PanicPolicy);
+        set_from_env!(self, deadlock_policy, "DEADLOCK_POLICY", DeadlockPolicy);
+
+        if let Some(path) = env::var_os("INKO_GC_LOG") {
+            self.gc_log = Some(PathBuf::from(path));
+        }
+
+        if let Some(path) = env::var_os("INKO_HEAP_SNAPSHOT_DIRECTORY") {
+            self.heap_snapshot_directory = Some(PathBuf::from(path));
+        }
+
+        if let Some(path) = env::var_os("INKO_PROFILE") {
+            self.profile = Some(PathBuf::from(path));
+        }
+
+        if let Some(paths) = env::var_os("INKO_EXTENSIONS") {
+            self.extensions.extend(env::split_paths(&paths));
+        }
     }
 }
 
@@ -146,23 +303,107 @@ mod tests {
         let config = Config::new();
 
         assert!(config.primary_threads >= 1);
+        assert!(config.io_threads >= 1);
         assert!(config.gc_threads >= 1);
         assert_eq!(config.reductions, 1000);
+        assert_eq!(config.panic_policy, PanicPolicy::Exit);
+        assert_eq!(config.deadlock_policy, DeadlockPolicy::Ignore);
+        assert!(config.gc_log.is_none());
+        assert!(config.heap_snapshot_directory.is_none());
+        assert_eq!(config.heap_limit, 0);
+        assert!(config.verify_bytecode);
+        assert!(config.optimise_bytecode);
+        assert!(!config.opcode_statistics);
+        assert_eq!(config.jit_threshold, 1000);
+        assert!(config.profile.is_none());
+        assert_eq!(config.profile_interval, 1000);
+    }
+
+    #[test]
+    fn test_panic_policy_from_str() {
+        assert_eq!("exit".parse::<PanicPolicy>(), Ok(PanicPolicy::Exit));
+        assert_eq!(
+            "terminate".parse::<PanicPolicy>(),
+            Ok(PanicPolicy::Terminate)
+        );
+        assert!("foo".parse::<PanicPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_deadlock_policy_from_str() {
+        assert_eq!(
+            "ignore".parse::<DeadlockPolicy>(),
+            Ok(DeadlockPolicy::Ignore)
+        );
+        assert_eq!(
+            "report".parse::<DeadlockPolicy>(),
+            Ok(DeadlockPolicy::Report)
+        );
+        assert_eq!(
+            "terminate".parse::<DeadlockPolicy>(),
+            Ok(DeadlockPolicy::Terminate)
+        );
+        assert!("foo".parse::<DeadlockPolicy>().is_err());
     }
 
     #[test]
     fn test_populate_from_env() {
         env::set_var("INKO_PRIMARY_THREADS", "42");
+        env::set_var("INKO_IO_THREADS", "3");
         env::set_var("INKO_HEAP_GROWTH_FACTOR", "4.2");
+        env::set_var("INKO_PANIC_POLICY", "terminate");
+        env::set_var("INKO_DEADLOCK_POLICY", "report");
+        env::set_var("INKO_GC_LOG", "/tmp/gc.jsonl");
+        env::set_var("INKO_HEAP_LIMIT", "1024");
+        env::set_var("INKO_HEAP_SNAPSHOT_DIRECTORY", "/tmp/snapshots");
+        env::set_var("INKO_OPTIMISE_BYTECODE", "false");
+        env::set_var("INKO_OPCODE_STATISTICS", "true");
+        env::set_var("INKO_JIT_THRESHOLD", "10");
+        env::set_var("INKO_PROFILE", "/tmp/profile.txt");
+        env::set_var("INKO_PROFILE_INTERVAL", "500");
+        env::set_var(
+            "INKO_EXTENSIONS",
+            env::join_paths(&["/tmp/a.so", "/tmp/b.so"]).unwrap(),
+        );
 
         let mut config = Config::new();
 
         config.populate_from_env();
 
         // Unset before any assertions may fail.
+        env::remove_var("INKO_IO_THREADS");
         env::remove_var("INKO_HEAP_GROWTH_FACTOR");
+        env::remove_var("INKO_EXTENSIONS");
+        env::remove_var("INKO_PANIC_POLICY");
+        env::remove_var("INKO_DEADLOCK_POLICY");
+        env::remove_var("INKO_GC_LOG");
+        env::remove_var("INKO_HEAP_LIMIT");
+        env::remove_var("INKO_HEAP_SNAPSHOT_DIRECTORY");
+        env::remove_var("INKO_OPTIMISE_BYTECODE");
+        env::remove_var("INKO_OPCODE_STATISTICS");
+        env::remove_var("INKO_JIT_THRESHOLD");
+        env::remove_var("INKO_PROFILE");
+        env::remove_var("INKO_PROFILE_INTERVAL");
 
         assert_eq!(config.primary_threads, 42);
+        assert_eq!(config.io_threads, 3);
         assert_eq!(config.heap_growth_factor, 4.2);
+        assert_eq!(config.panic_policy, PanicPolicy::Terminate);
+        assert_eq!(config.deadlock_policy, DeadlockPolicy::Report);
+        assert_eq!(config.gc_log, Some(PathBuf::from("/tmp/gc.jsonl")));
+        assert_eq!(config.heap_limit, 1024);
+        assert_eq!(
+            config.heap_snapshot_directory,
+            Some(PathBuf::from("/tmp/snapshots"))
+        );
+        assert!(!config.optimise_bytecode);
+        assert!(config.opcode_statistics);
+        assert_eq!(config.jit_threshold, 10);
+        assert_eq!(config.profile, Some(PathBuf::from("/tmp/profile.txt")));
+        assert_eq!(config.profile_interval, 500);
+        assert_eq!(
+            config.extensions,
+            vec![PathBuf::from("/tmp/a.so"), PathBuf::from("/tmp/b.so")]
+        );
     }
 }
@@ -0,0 +1,486 @@
+//! Embedding of the VM in Rust applications.
+//!
+//! This module provides an API for running Inko bytecode images inside a Rust
+//! program, without having to use the `inko` executable. A `Vm` is created
+//! using a `Config`, after which the host can register its own external
+//! functions before running an image stored in memory:
+//!
+//! ```ignore
+//! use libinko::config::Config;
+//! use libinko::embed::Vm;
+//!
+//! let mut vm = Vm::new(Config::new())?;
+//!
+//! vm.add_external_function("host_greet", host_greet)?;
+//!
+//! vm.load_image(&image)?;
+//!
+//! let result = vm.spawn(None)?;
+//! ```
+//!
+//! Running an image blocks the calling thread until the VM terminates. The
+//! value returned by the main process is converted to a `Value`, as the memory
+//! of the process is released once the value is converted.
+use crate::bytecode_parser;
+use crate::config::Config;
+use crate::external_functions::{ExternalFunction, ExternalFunctions};
+use crate::object_pointer::ObjectPointer;
+use crate::vm::machine::Machine;
+use crate::vm::state::{MainProcessResult, RcState, State};
+use num_bigint::BigInt;
+use std::fmt;
+
+/// A value returned by an Inko process.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Boolean(bool),
+    Integer(i64),
+    BigInteger(BigInt),
+    Float(f64),
+    String(String),
+
+    /// A value that can't be converted to a Rust value, such as an Array or a
+    /// custom object.
+    Object,
+}
+
+impl Value {
+    /// Converts an object pointer to a Value.
+    pub fn from_pointer(state: &State, pointer: ObjectPointer) -> Self {
+        if pointer == state.nil_object {
+            Value::Nil
+        } else if pointer == state.true_object {
+            Value::Boolean(true)
+        } else if pointer == state.false_object {
+            Value::Boolean(false)
+        } else if let Ok(integer) = pointer.integer_value() {
+            Value::Integer(integer)
+        } else if let Ok(bigint) = pointer.bigint_value() {
+            Value::BigInteger(bigint.clone())
+        } else if let Ok(float) = pointer.float_value() {
+            Value::Float(float)
+        } else if let Ok(string) = pointer.string_value() {
+            Value::String(string.to_owned_string())
+        } else {
+            Value::Object
+        }
+    }
+}
+
+/// An error produced when running an embedded VM.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The bytecode image is invalid.
+    InvalidImage(String),
+
+    /// The module to run doesn't exist.
+    UndefinedModule(String),
+
+    /// A process panicked, terminating the VM.
+    Panic(String),
+
+    /// The program terminated itself with a non-zero exit status.
+    Exit(i32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidImage(message) => {
+                write!(f, "The bytecode image is invalid: {}", message)
+            }
+            Error::UndefinedModule(message) => write!(f, "{}", message),
+            Error::Panic(message) => write!(f, "{}", message),
+            Error::Exit(status) => {
+                write!(f, "The program exited with status {}", status)
+            }
+        }
+    }
+}
+
+/// A VM embedded in a Rust application.
+///
+/// A `Vm` is set up once, after which it can run the loaded image any number
+/// of times. Each run uses its own VM state, as the process pools can't be
+/// restarted once the program terminates; thus no memory or global state is
+/// shared between runs.
+pub struct Vm {
+    config: Config,
+    arguments: Vec<String>,
+    external_functions: ExternalFunctions,
+    image: Option<Vec<u8>>,
+}
+
+impl Vm {
+    /// Creates a new VM that uses the given configuration.
+    ///
+    /// The external functions that Inko ships with are registered
+    /// automatically, as are the functions of the native extensions specified
+    /// in the configuration.
+    pub fn new(config: Config) -> Result<Self, String> {
+        let mut external_functions = ExternalFunctions::setup()?;
+
+        external_functions.load_extensions(&config.extensions)?;
+
+        Ok(Vm {
+            config,
+            arguments: Vec::new(),
+            external_functions,
+            image: None,
+        })
+    }
+
+    /// Sets the commandline arguments that are exposed to the Inko program.
+    pub fn set_arguments(&mut self, arguments: &[String]) {
+        self.arguments = arguments.to_vec();
+    }
+
+    /// Registers an external function provided by the host application.
+    ///
+    /// An error is returned if a function with the same name is already
+    /// defined.
+    pub fn add_external_function<I: Into<String>>(
+        &mut self,
+        name: I,
+        function: ExternalFunction,
+    ) -> Result<(), String> {
+        self.external_functions.add(name, function)
+    }
+
+    /// Loads a bytecode image stored in memory, replacing the image loaded
+    /// previously.
+    pub fn load_image(&mut self, image: &[u8]) -> Result<(), Error> {
+        bytecode_parser::parse(&self.state(), &mut &image[..])
+            .map_err(Error::InvalidImage)?;
+
+        self.image = Some(image.to_vec());
+        Ok(())
+    }
+
+    /// Runs a module of the loaded image in a new main process, blocking the
+    /// calling thread until the program terminates.
+    ///
+    /// If no module name is given, the entry point of the image is used.
+    pub fn spawn(&self, module: Option<&str>) -> Result<Value, Error> {
+        let bytes = self.image.as_ref().ok_or_else(|| {
+            Error::InvalidImage("No bytecode image is loaded".to_string())
+        })?;
+
+        let state = self.state();
+        let image = bytecode_parser::parse(&state, &mut &bytes[..])
+            .map_err(Error::InvalidImage)?;
+
+        let name = module
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| image.entry_point.clone());
+
+        state.modules.lock().set_image(&state, image);
+
+        Machine::new(state.clone())
+            .start_module(&name)
+            .map_err(Error::UndefinedModule)?;
+
+        let result = state.main_process_result.lock().take();
+
+        match result {
+            Some(MainProcessResult::Finished(process, pointer)) => {
+                let value = Value::from_pointer(&state, pointer);
+
+                process.terminate(&state);
+                Ok(value)
+            }
+            Some(MainProcessResult::Panicked(message)) => {
+                Err(Error::Panic(message))
+            }
+            None => match state.current_exit_status() {
+                0 => Ok(Value::Nil),
+                status => Err(Error::Exit(status)),
+            },
+        }
+    }
+
+    /// Loads a bytecode image stored in memory, then runs its entry point.
+    pub fn run(&mut self, image: &[u8]) -> Result<Value, Error> {
+        self.load_image(image)?;
+        self.spawn(None)
+    }
+
+    fn state(&self) -> RcState {
+        State::with_external_functions(
+            self.config.clone(),
+            &self.arguments,
+            self.external_functions.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PanicPolicy;
+    use crate::object_value;
+    use crate::process::RcProcess;
+    use crate::runtime_error::RuntimeError;
+    use crate::vm::instruction::Opcode;
+    use crate::vm::test::{code, module_image, Literal};
+
+    /// Builds an image with a single module that runs the given instructions.
+    ///
+    /// The literals available to the instructions are the string "main" (0),
+    /// the string "main.inko" (1), and the given literal (2).
+    fn image(literal: &str, instructions: &[(Opcode, &[u16])]) -> Vec<u8> {
+        module_image(&[Literal::String(literal)], code(instructions, &[]))
+    }
+
+    fn config() -> Config {
+        let mut config = Config::new();
+
+        config.primary_threads = 1;
+        config.blocking_threads = 1;
+        config
+    }
+
+    fn host_greeting(
+        state: &RcState,
+        process: &RcProcess,
+        _: &[ObjectPointer],
+    ) -> Result<ObjectPointer, RuntimeError> {
+        Ok(process.allocate(
+            object_value::string("hello".to_string()),
+            state.string_prototype,
+        ))
+    }
+
+    #[test]
+    fn test_run() {
+        let mut vm = Vm::new(config()).unwrap();
+        let image = image(
+            "hello",
+            &[(Opcode::SetLiteral, &[0, 2]), (Opcode::Return, &[0, 0])],
+        );
+
+        assert_eq!(vm.run(&image), Ok(Value::String("hello".to_string())));
+    }
+
+    #[test]
+    fn test_run_with_external_function() {
+        let mut vm = Vm::new(config()).unwrap();
+        let image = image(
+            "host_greeting",
+            &[
+                (Opcode::SetLiteral, &[0, 2]),
+                (Opcode::ExternalFunctionLoad, &[0, 0]),
+                (Opcode::ExternalFunctionCall, &[0, 0, 0, 0]),
+                (Opcode::Return, &[0, 0]),
+            ],
+        );
+
+        vm.add_external_function("host_greeting", host_greeting)
+            .unwrap();
+
+        assert_eq!(vm.run(&image), Ok(Value::String("hello".to_string())));
+    }
+
+    #[test]
+    fn test_run_with_panic() {
+        let mut vm = Vm::new(config()).unwrap();
+        let image = image(
+            "oops",
+            &[(Opcode::SetLiteral, &[0, 2]), (Opcode::Panic, &[0])],
+        );
+
+        match vm.run(&image) {
+            Err(Error::Panic(message)) => assert!(message.contains("oops")),
+            result => panic!("Expected a panic, found {:?}", result),
+        }
+    }
+
+    /// Builds an image where the main process spawns a child process that
+    /// panics, watches the child using the given external function, then
+    /// waits for the exit message of the child.
+    fn panicking_child_image(watch: &str) -> Vec<u8> {
+        let child =
+            code(&[(Opcode::SetLiteral, &[0, 2]), (Opcode::Panic, &[0])], &[]);
+
+        let main = code(
+            &[
+                (Opcode::SetBlock, &[0, 0, 7]),
+                (Opcode::ProcessSpawn, &[1, 0]),
+                (Opcode::SetLiteral, &[2, 3]),
+                (Opcode::ExternalFunctionLoad, &[2, 2]),
+                (Opcode::ExternalFunctionCall, &[3, 2, 1, 1]),
+                (Opcode::SetLiteral, &[4, 4]),
+                (Opcode::ProcessReceiveMessage, &[5, 4]),
+                (Opcode::Return, &[0, 5]),
+            ],
+            &[child],
+        );
+
+        module_image(
+            &[
+                Literal::String("oops"),
+                Literal::String(watch),
+                Literal::Float(0.0),
+            ],
+            main,
+        )
+    }
+
+    #[test]
+    fn test_run_with_panic_in_terminated_process() {
+        let mut config = config();
+
+        config.panic_policy = PanicPolicy::Terminate;
+
+        let mut vm = Vm::new(config).unwrap();
+        let image = panicking_child_image("process_monitor");
+
+        assert_eq!(vm.run(&image), Ok(Value::Object));
+    }
+
+    #[test]
+    fn test_run_with_panic_in_linked_process() {
+        let mut config = config();
+
+        config.panic_policy = PanicPolicy::Terminate;
+
+        let mut vm = Vm::new(config).unwrap();
+        let image = panicking_child_image("process_link");
+
+        match vm.run(&image) {
+            Err(Error::Panic(message)) => {
+                assert!(message.contains("The linked process"))
+            }
+            result => panic!("Expected a panic, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_run_with_selective_receive() {
+        let mut vm = Vm::new(config()).unwrap();
+
+        // The main process sends itself "a" and ["b", "c"], then receives the
+        // message tagged "b", followed by the oldest remaining message.
+        let main = code(
+            &[
+                (Opcode::ProcessCurrent, &[0]),
+                (Opcode::SetLiteral, &[1, 2]),
+                (Opcode::ProcessSendMessage, &[2, 0, 1]),
+                (Opcode::SetLiteral, &[3, 3]),
+                (Opcode::SetLiteral, &[4, 4]),
+                (Opcode::ArrayAllocate, &[5, 3, 2]),
+                (Opcode::ProcessSendMessage, &[2, 0, 5]),
+                (Opcode::SetLiteral, &[6, 5]),
+                (Opcode::ProcessReceiveMatching, &[7, 3, 6]),
+                (Opcode::ProcessReceiveMessage, &[7, 6]),
+                (Opcode::Return, &[0, 7]),
+            ],
+            &[],
+        );
+
+        let image = module_image(
+            &[
+                Literal::String("a"),
+                Literal::String("b"),
+                Literal::String("c"),
+                Literal::Float(0.0),
+            ],
+            main,
+        );
+
+        assert_eq!(vm.run(&image), Ok(Value::String("a".to_string())));
+    }
+
+    #[test]
+    fn test_run_with_selective_receive_timeout() {
+        let mut vm = Vm::new(config()).unwrap();
+        let main = code(
+            &[
+                (Opcode::ProcessCurrent, &[0]),
+                (Opcode::SetLiteral, &[1, 2]),
+                (Opcode::ProcessSendMessage, &[2, 0, 1]),
+                (Opcode::SetLiteral, &[3, 3]),
+                (Opcode::SetLiteral, &[4, 4]),
+                (Opcode::ProcessReceiveMatching, &[5, 3, 4]),
+                (Opcode::Return, &[0, 5]),
+            ],
+            &[],
+        );
+
+        let image = module_image(
+            &[
+                Literal::String("a"),
+                Literal::String("b"),
+                Literal::Float(0.01),
+            ],
+            main,
+        );
+
+        match vm.run(&image) {
+            Err(Error::Panic(_)) => {}
+            result => panic!("Expected a panic, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_run_with_invalid_image() {
+        let mut vm = Vm::new(config()).unwrap();
+
+        match vm.run(&[1, 2, 3]) {
+            Err(Error::InvalidImage(_)) => {}
+            result => panic!("Expected an invalid image, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_spawn_with_undefined_module() {
+        let mut vm = Vm::new(config()).unwrap();
+        let image = image("hello", &[(Opcode::Return, &[0, 0])]);
+
+        vm.load_image(&image).unwrap();
+
+        match vm.spawn(Some("foo")) {
+            Err(Error::UndefinedModule(_)) => {}
+            result => {
+                panic!("Expected an undefined module, found {:?}", result)
+            }
+        }
+    }
+
+    #[test]
+    fn test_spawn_multiple_times() {
+        let mut vm = Vm::new(config()).unwrap();
+        let image = image(
+            "hello",
+            &[(Opcode::SetLiteral, &[0, 2]), (Opcode::Return, &[0, 0])],
+        );
+
+        vm.load_image(&image).unwrap();
+
+        assert_eq!(vm.spawn(None), Ok(Value::String("hello".to_string())));
+        assert_eq!(vm.spawn(None), Ok(Value::String("hello".to_string())));
+    }
+
+    #[test]
+    fn test_spawn_without_image() {
+        let vm = Vm::new(config()).unwrap();
+
+        match vm.spawn(None) {
+            Err(Error::InvalidImage(_)) => {}
+            result => panic!("Expected an invalid image, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_add_external_function_twice() {
+        let mut vm = Vm::new(config()).unwrap();
+
+        assert!(vm
+            .add_external_function("host_greeting", host_greeting)
+            .is_ok());
+        assert!(vm
+            .add_external_function("host_greeting", host_greeting)
+            .is_err());
+    }
+}
@@ -4,7 +4,12 @@ use crate::process::RcProcess;
 use crate::runtime_error::RuntimeError;
 use crate::vm::state::RcState;
 use ahash::AHashMap;
-use std::io::Read;
+use libloading::Library;
+use std::ffi::CStr;
+use std::io::{self, Read};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Defines a setup() function that registers all the given external functions.
 macro_rules! register {
@@ -28,12 +33,14 @@ mod env;
 mod ffi;
 mod float;
 mod fs;
+mod gc;
 mod hasher;
 mod integer;
 mod modules;
 mod object;
 mod process;
 mod random;
+mod scheduler;
 mod socket;
 mod stdio;
 mod string;
@@ -46,12 +53,48 @@ pub type ExternalFunction = fn(
     &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError>;
 
+/// The name of the function a native extension must export.
+pub const EXTENSION_REGISTER_FUNCTION: &[u8] = b"inko_extension_register";
+
+/// The name of the function a native extension must export to report the
+/// version it was compiled with.
+pub const EXTENSION_VERSION_FUNCTION: &[u8] = b"inko_extension_version";
+
+/// The version of libinko and Rust used for compiling the VM, as a
+/// NULL-terminated string.
+///
+/// Extensions return a pointer to this string from their
+/// `inko_extension_version` function.
+pub const EXTENSION_VERSION: &str = concat!(
+    "libinko ",
+    env!("CARGO_PKG_VERSION"),
+    ", ",
+    env!("INKO_RUSTC_VERSION"),
+    "\0"
+);
+
+/// The function a native extension uses for registering its external
+/// functions.
+///
+/// Extensions export this function using `#[no_mangle]`. Since this function
+/// uses the Rust ABI, extensions must be compiled using the same version of
+/// Rust and libinko as the VM that loads them. This is verified using the
+/// `ExtensionVersionFunction` before this function is called.
+pub type ExtensionRegisterFunction =
+    fn(&mut ExternalFunctions) -> Result<(), String>;
+
+/// The function a native extension uses for reporting the version of libinko
+/// and Rust it was compiled with.
+///
+/// This function must return a pointer to `EXTENSION_VERSION`.
+pub type ExtensionVersionFunction = unsafe extern "C" fn() -> *const c_char;
+
 /// Reads a number of bytes from a buffer into a Vec.
 pub fn read_into<T: Read>(
     stream: &mut T,
     output: &mut Vec<u8>,
     size: Option<u64>,
-) -> Result<usize, RuntimeError> {
+) -> io::Result<usize> {
     let read = if size > Some(0) {
         stream.take(size.unwrap()).read_to_end(output)?
     } else {
@@ -62,8 +105,15 @@ pub fn read_into<T: Read>(
 }
 
 /// A collection of external functions.
+#[derive(Clone)]
 pub struct ExternalFunctions {
     mapping: AHashMap<String, ExternalFunction>,
+
+    /// The native extensions that have been loaded.
+    ///
+    /// These libraries must remain loaded for as long as their functions may
+    /// be used.
+    extensions: Vec<Arc<Library>>,
 }
 
 impl ExternalFunctions {
@@ -90,6 +140,8 @@ impl ExternalFunctions {
         integer::setup(&mut instance)?;
         string::setup(&mut instance)?;
         child_process::setup(&mut instance)?;
+        scheduler::setup(&mut instance)?;
+        gc::setup(&mut instance)?;
 
         Ok(instance)
     }
@@ -98,6 +150,7 @@ impl ExternalFunctions {
     pub fn new() -> Self {
         Self {
             mapping: AHashMap::default(),
+            extensions: Vec::new(),
         }
     }
 
@@ -120,6 +173,97 @@ impl ExternalFunctions {
         Ok(())
     }
 
+    /// Loads a native extension from a dynamic library, and registers the
+    /// external functions it provides.
+    ///
+    /// If the extension fails to register its functions, or registers a
+    /// function that is already defined, none of its functions are added.
+    pub fn load_extension(&mut self, path: &Path) -> Result<(), String> {
+        let library = Library::new(path).map_err(|err| {
+            format!("The extension {} can't be loaded: {}", path.display(), err)
+        })?;
+
+        let version = unsafe {
+            let function = *library
+                .get::<ExtensionVersionFunction>(EXTENSION_VERSION_FUNCTION)
+                .map_err(|err| {
+                    Self::missing_symbol(path, EXTENSION_VERSION_FUNCTION, err)
+                })?;
+
+            CStr::from_ptr(function()).to_string_lossy().into_owned()
+        };
+
+        let expected = &EXTENSION_VERSION[..EXTENSION_VERSION.len() - 1];
+
+        if version != expected {
+            return Err(format!(
+                "The extension {} is compiled for {}, but {} is required",
+                path.display(),
+                version,
+                expected
+            ));
+        }
+
+        let register = unsafe {
+            *library
+                .get::<ExtensionRegisterFunction>(EXTENSION_REGISTER_FUNCTION)
+                .map_err(|err| {
+                    Self::missing_symbol(path, EXTENSION_REGISTER_FUNCTION, err)
+                })?
+        };
+
+        // The functions are registered separately first, so we don't end up
+        // with pointers to functions of a library that failed to load.
+        let mut functions = Self::new();
+
+        register(&mut functions).map_err(|err| {
+            format!(
+                "The extension {} failed to register its functions: {}",
+                path.display(),
+                err
+            )
+        })?;
+
+        if let Some(name) = functions
+            .mapping
+            .keys()
+            .find(|name| self.mapping.contains_key(*name))
+        {
+            return Err(format!(
+                "The extension {} failed to register its functions: \
+                The external function {} is already defined",
+                path.display(),
+                name
+            ));
+        }
+
+        self.mapping.extend(functions.mapping);
+        self.extensions.push(Arc::new(library));
+        Ok(())
+    }
+
+    fn missing_symbol(
+        path: &Path,
+        symbol: &[u8],
+        error: libloading::Error,
+    ) -> String {
+        format!(
+            "The extension {} doesn't export the function {}: {}",
+            path.display(),
+            String::from_utf8_lossy(symbol),
+            error
+        )
+    }
+
+    /// Loads multiple native extensions.
+    pub fn load_extensions(&mut self, paths: &[PathBuf]) -> Result<(), String> {
+        for path in paths {
+            self.load_extension(path)?;
+        }
+
+        Ok(())
+    }
+
     /// Looks up a external function by its name.
     pub fn get(&self, name: &str) -> Result<ExternalFunction, String> {
         self.mapping.get(name).cloned().ok_or_else(|| {
@@ -52,8 +52,48 @@ pub fn byte_array_drain_to_string(
     ))
 }
 
+/// Freezes a ByteArray, preventing any further modifications.
+///
+/// Frozen byte arrays are shared between processes when sent as a message,
+/// instead of being copied.
+///
+/// This function requires a single argument: the ByteArray to freeze.
+pub fn byte_array_freeze(
+    _: &RcState,
+    _: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let bytes = arguments[0];
+
+    bytes.byte_array_value()?;
+    bytes.get_mut().value.freeze_byte_array()?;
+
+    Ok(bytes)
+}
+
+/// Returns `true` if a ByteArray is frozen.
+///
+/// This function requires a single argument: the ByteArray to check.
+pub fn byte_array_frozen(
+    state: &RcState,
+    _: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let bytes = arguments[0];
+
+    bytes.byte_array_value()?;
+
+    Ok(if bytes.get().value.is_frozen_byte_array() {
+        state.true_object
+    } else {
+        state.false_object
+    })
+}
+
 register!(
     byte_array_clear,
     byte_array_to_string,
-    byte_array_drain_to_string
+    byte_array_drain_to_string,
+    byte_array_freeze,
+    byte_array_frozen
 );
@@ -1,7 +1,12 @@
 //! Functions for working with the file system.
+//!
+//! Operations that read or write the contents of files are performed on the
+//! I/O pool, suspending the process until the operation finishes. See the
+//! `io_pool` module for more information.
 use crate::date_time::DateTime;
 use crate::external_functions::read_into;
-use crate::file::File;
+use crate::file::{self, File};
+use crate::io_pool::{IoOutput, IoResult};
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
 use crate::process::RcProcess;
@@ -12,6 +17,66 @@ use num_traits::ToPrimitive;
 use std::fs;
 use std::io::{Seek, SeekFrom, Write};
 
+/// Schedules a file operation on the I/O pool, suspending the process.
+///
+/// When the operation finishes the process is rescheduled, and the function
+/// that scheduled the operation is called again. That function must then use
+/// `RcProcess::take_io_result()` to obtain the result, instead of scheduling a
+/// new operation.
+fn schedule<F>(
+    state: &RcState,
+    process: &RcProcess,
+    operation: F,
+) -> Result<ObjectPointer, RuntimeError>
+where
+    F: FnOnce() -> IoResult + Send + 'static,
+{
+    state.io_pool.schedule(process, operation);
+    Err(RuntimeError::WouldBlock)
+}
+
+/// Opens a file on the I/O pool.
+fn open(
+    state: &RcState,
+    process: &RcProcess,
+    path: ObjectPointer,
+    mode: i64,
+    prototype: ObjectPointer,
+) -> Result<ObjectPointer, RuntimeError> {
+    if let Some(result) = process.take_io_result() {
+        let file = File::new(result?.into_file()?, path);
+
+        return Ok(process.allocate(object_value::file(file), prototype));
+    }
+
+    let path = path.string_value()?.to_owned_string();
+
+    schedule(state, process, move || {
+        Ok(IoOutput::File(file::open_options(mode).open(path)?))
+    })
+}
+
+/// Writes bytes to a file on the I/O pool.
+fn write(
+    state: &RcState,
+    process: &RcProcess,
+    file: ObjectPointer,
+    input: &[u8],
+) -> Result<ObjectPointer, RuntimeError> {
+    if let Some(result) = process.take_io_result() {
+        let size = result?.into_integer()?;
+
+        return Ok(process.allocate_u64(size, state.integer_prototype));
+    }
+
+    let input = input.to_vec();
+    let mut file = file.file_value_mut()?.get_mut().try_clone()?;
+
+    schedule(state, process, move || {
+        Ok(IoOutput::Integer(file.write(&input)? as u64))
+    })
+}
+
 /// Returns the path of a file.
 ///
 /// This function requires one argument: the file to get the path of.
@@ -83,11 +148,9 @@ pub fn file_write_string(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = arguments[0].file_value_mut()?;
     let input = arguments[1].string_value()?.as_bytes();
-    let size = file.get_mut().write(&input)?;
 
-    Ok(process.allocate_usize(size, state.integer_prototype))
+    write(state, process, arguments[0], input)
 }
 
 /// Writes a ByteArray to a file.
@@ -101,11 +164,9 @@ pub fn file_write_bytes(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = arguments[0].file_value_mut()?;
     let input = arguments[1].byte_array_value()?;
-    let size = file.get_mut().write(&input)?;
 
-    Ok(process.allocate_usize(size, state.integer_prototype))
+    write(state, process, arguments[0], input)
 }
 
 /// Copies a file from one location to another.
@@ -119,11 +180,18 @@ pub fn file_copy(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let src = arguments[0].string_value()?;
-    let dst = arguments[1].string_value()?;
-    let bytes_copied = fs::copy(src, dst)?;
+    if let Some(result) = process.take_io_result() {
+        let bytes_copied = result?.into_integer()?;
+
+        return Ok(process.allocate_u64(bytes_copied, state.integer_prototype));
+    }
+
+    let src = arguments[0].string_value()?.to_owned_string();
+    let dst = arguments[1].string_value()?.to_owned_string();
 
-    Ok(process.allocate_u64(bytes_copied, state.integer_prototype))
+    schedule(state, process, move || {
+        Ok(IoOutput::Integer(fs::copy(src, dst)?))
+    })
 }
 
 /// Returns the size of a file in bytes.
@@ -135,10 +203,17 @@ pub fn file_size(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let path = arguments[0].string_value()?;
-    let meta = fs::metadata(path)?;
+    if let Some(result) = process.take_io_result() {
+        let size = result?.into_integer()?;
 
-    Ok(process.allocate_u64(meta.len(), state.integer_prototype))
+        return Ok(process.allocate_u64(size, state.integer_prototype));
+    }
+
+    let path = arguments[0].string_value()?.to_owned_string();
+
+    schedule(state, process, move || {
+        Ok(IoOutput::Integer(fs::metadata(path)?.len()))
+    })
 }
 
 /// Removes a file.
@@ -146,11 +221,20 @@ pub fn file_size(
 /// This function requires a single argument: the path to the file to remove.
 pub fn file_remove(
     state: &RcState,
-    _: &RcProcess,
+    process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    fs::remove_file(arguments[0].string_value()?)?;
-    Ok(state.nil_object)
+    if let Some(result) = process.take_io_result() {
+        result?;
+        return Ok(state.nil_object);
+    }
+
+    let path = arguments[0].string_value()?.to_owned_string();
+
+    schedule(state, process, move || {
+        fs::remove_file(path)?;
+        Ok(IoOutput::Nil)
+    })
 }
 
 /// Returns the creation time of a path.
@@ -266,10 +350,9 @@ pub fn file_open_read_only(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = File::read_only(arguments[0])?;
     let proto = state.read_only_file_prototype;
 
-    Ok(process.allocate(object_value::file(file), proto))
+    open(state, process, arguments[0], file::READ, proto)
 }
 
 /// Opens a file in write-only mode.
@@ -280,10 +363,9 @@ pub fn file_open_write_only(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = File::write_only(arguments[0])?;
     let proto = state.write_only_file_prototype;
 
-    Ok(process.allocate(object_value::file(file), proto))
+    open(state, process, arguments[0], file::WRITE, proto)
 }
 
 /// Opens a file in append-only mode.
@@ -294,10 +376,9 @@ pub fn file_open_append_only(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = File::append_only(arguments[0])?;
     let proto = state.write_only_file_prototype;
 
-    Ok(process.allocate(object_value::file(file), proto))
+    open(state, process, arguments[0], file::APPEND, proto)
 }
 
 /// Opens a file for both reading and writing.
@@ -308,10 +389,9 @@ pub fn file_open_read_write(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = File::read_write(arguments[0])?;
     let proto = state.read_write_file_prototype;
 
-    Ok(process.allocate(object_value::file(file), proto))
+    open(state, process, arguments[0], file::READ_WRITE, proto)
 }
 
 /// Opens a file for both reading and appending.
@@ -322,10 +402,9 @@ pub fn file_open_read_append(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = File::read_append(arguments[0])?;
     let proto = state.read_write_file_prototype;
 
-    Ok(process.allocate(object_value::file(file), proto))
+    open(state, process, arguments[0], file::READ_APPEND, proto)
 }
 
 /// Reads bytes from a file into a ByteArray.
@@ -340,13 +419,28 @@ pub fn file_read(
     process: &RcProcess,
     arguments: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let file = arguments[0].file_value_mut()?;
-    let buff = arguments[1].byte_array_value_mut()?;
+    if let Some(result) = process.take_io_result() {
+        let mut bytes = result?.into_bytes()?;
+        let read = bytes.len();
+
+        arguments[1].byte_array_value_mut()?.append(&mut bytes);
+
+        return Ok(process.allocate_usize(read, state.integer_prototype));
+    }
+
+    // The ByteArray is only written to once the read finishes, so we validate
+    // it upfront instead of discarding the data read.
+    arguments[1].byte_array_value_mut()?;
+
+    let mut file = arguments[0].file_value_mut()?.get_mut().try_clone()?;
     let size = arguments[2].u64_value().ok();
-    let stream = file.get_mut();
-    let result = read_into(stream, buff, size)?;
 
-    Ok(process.allocate_usize(result, state.integer_prototype))
+    schedule(state, process, move || {
+        let mut bytes = Vec::new();
+
+        read_into(&mut file, &mut bytes, size)?;
+        Ok(IoOutput::Bytes(bytes))
+    })
 }
 
 fn allocate_time(
@@ -0,0 +1,77 @@
+//! Functions for inspecting the garbage collector.
+use crate::duration;
+use crate::gc::statistics::GcStatistics;
+use crate::object_pointer::ObjectPointer;
+use crate::object_value;
+use crate::process::RcProcess;
+use crate::runtime_error::RuntimeError;
+use crate::vm::state::RcState;
+
+/// Returns the statistics of all garbage collections performed by the VM.
+///
+/// The statistics are returned as an Array, using the same layout as
+/// `process_gc_stats`.
+///
+/// This function doesn't take any arguments.
+pub fn vm_gc_stats(
+    state: &RcState,
+    process: &RcProcess,
+    _: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let stats = state.gc_statistics.lock().clone();
+
+    Ok(statistics_array(state, process, &stats))
+}
+
+/// Returns the statistics of all garbage collections of a process.
+///
+/// The statistics are returned as an Array containing the following values:
+///
+/// 1. The number of collections of only the young generation.
+/// 2. The number of collections of the mature generation.
+/// 3. The total time spent garbage collecting, in seconds.
+/// 4. The longest time spent on a single collection, in seconds.
+/// 5. The total number of marked objects.
+/// 6. The total number of promoted objects.
+/// 7. The total number of evacuated objects.
+///
+/// This function requires a single argument: the process to get the
+/// statistics of.
+pub fn process_gc_stats(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let stats = arguments[0].process_value()?.gc_statistics();
+
+    Ok(statistics_array(state, process, &stats))
+}
+
+fn statistics_array(
+    state: &RcState,
+    process: &RcProcess,
+    stats: &GcStatistics,
+) -> ObjectPointer {
+    let integer =
+        |value| process.allocate_usize(value, state.integer_prototype);
+    let float = |value| {
+        process.allocate(
+            object_value::float(duration::to_f64(Some(value))),
+            state.float_prototype,
+        )
+    };
+
+    let values = vec![
+        integer(stats.young_collections),
+        integer(stats.mature_collections),
+        float(stats.total_pause),
+        float(stats.max_pause),
+        integer(stats.marked),
+        integer(stats.promoted),
+        integer(stats.evacuated),
+    ];
+
+    process.allocate(object_value::array(values), state.array_prototype)
+}
+
+register!(vm_gc_stats, process_gc_stats);
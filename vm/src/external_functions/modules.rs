@@ -1,4 +1,5 @@
 //! Functions for working with Inko modules.
+use crate::modules::Modules;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
 use crate::process::RcProcess;
@@ -11,7 +12,7 @@ pub fn module_list(
     process: &RcProcess,
     _: &[ObjectPointer],
 ) -> Result<ObjectPointer, RuntimeError> {
-    let modules = state.modules.lock().list();
+    let modules = state.modules.lock().list(state)?;
 
     Ok(process.allocate(object_value::array(modules), state.array_prototype))
 }
@@ -38,4 +39,105 @@ pub fn module_source_path(
     Ok(arguments[0].module_value()?.source_path())
 }
 
-register!(module_list, module_name, module_source_path);
+/// Returns the version of the compiler that produced the bytecode image.
+pub fn image_compiler_version(
+    state: &RcState,
+    process: &RcProcess,
+    _: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let version = state
+        .modules
+        .lock()
+        .compiler_version()
+        .cloned()
+        .unwrap_or_default();
+
+    Ok(process.allocate(object_value::string(version), state.string_prototype))
+}
+
+/// Returns the value of a metadata entry of the bytecode image.
+///
+/// This function requires a single argument: the key of the entry to get.
+pub fn image_metadata_get(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let key = arguments[0].string_value()?;
+
+    if let Some(value) = state.modules.lock().metadata(key.as_slice()) {
+        Ok(process.allocate(
+            object_value::string(value.clone()),
+            state.string_prototype,
+        ))
+    } else {
+        Err(RuntimeError::ErrorMessage(format!(
+            "The image metadata entry {:?} isn't set",
+            key
+        )))
+    }
+}
+
+/// Returns an Array containing the keys of all metadata entries of the
+/// bytecode image.
+///
+/// This function doesn't take any arguments.
+pub fn image_metadata_keys(
+    state: &RcState,
+    process: &RcProcess,
+    _: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let keys = state
+        .modules
+        .lock()
+        .metadata_keys()
+        .into_iter()
+        .map(|key| {
+            process.allocate(
+                object_value::string(key.clone()),
+                state.string_prototype,
+            )
+        })
+        .collect();
+
+    Ok(process.allocate(object_value::array(keys), state.array_prototype))
+}
+
+/// Parses a bytecode image and adds its modules to the running program.
+///
+/// This function requires a single argument: the path of the image to load.
+/// The return value is the name of the entry point of the image. The modules of
+/// the image aren't executed until they are first imported.
+pub fn module_load_image(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let path = arguments[0].string_value()?;
+
+    // Images may be large, so we parse them without holding on to the lock of
+    // the modules.
+    let image = Modules::parse_file(state, path.as_slice())
+        .map_err(RuntimeError::ErrorMessage)?;
+
+    let entry_point = image.entry_point.clone();
+
+    state
+        .modules
+        .lock()
+        .add_image(state, image)
+        .map_err(RuntimeError::ErrorMessage)?;
+
+    Ok(process
+        .allocate(object_value::string(entry_point), state.string_prototype))
+}
+
+register!(
+    module_list,
+    module_name,
+    module_source_path,
+    module_load_image,
+    image_compiler_version,
+    image_metadata_get,
+    image_metadata_keys
+);
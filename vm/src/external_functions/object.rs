@@ -1,4 +1,5 @@
 //! Functions for working with Inko objects.
+use crate::frozen;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
 use crate::process::RcProcess;
@@ -19,4 +20,41 @@ pub fn object_attribute_names(
     Ok(process.allocate(object_value::array(names), state.array_prototype))
 }
 
-register!(object_attribute_names);
+/// Freezes an object and every object it refers to.
+///
+/// Frozen objects can't be modified, and are shared between processes when
+/// sent as a message, instead of being copied.
+///
+/// This function requires one argument: the object to freeze.
+pub fn object_freeze(
+    _: &RcState,
+    _: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let object = arguments[0];
+
+    frozen::freeze(object)?;
+
+    Ok(object)
+}
+
+/// Returns `true` if an object is frozen.
+///
+/// This function requires one argument: the object to check.
+pub fn object_frozen(
+    state: &RcState,
+    _: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let object = arguments[0];
+
+    Ok(
+        if !object.is_tagged_integer() && object.get().value.is_frozen() {
+            state.true_object
+        } else {
+            state.false_object
+        },
+    )
+}
+
+register!(object_attribute_names, object_freeze, object_frozen);
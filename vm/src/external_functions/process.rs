@@ -1,10 +1,14 @@
 //! Functions for Inko processes.
 use crate::execution_context::ExecutionContext;
+use crate::immix::local_allocator::HeapLimitPolicy;
+use crate::mailbox::OverflowPolicy;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
-use crate::process::RcProcess;
+use crate::process::{ExitReason, Priority, RcProcess};
 use crate::runtime_error::RuntimeError;
+use crate::vm::instructions::process::process_exit_message;
 use crate::vm::state::RcState;
+use std::fs::File;
 
 /// Returns a stacktrace for the current process.
 ///
@@ -53,4 +57,307 @@ pub fn process_stacktrace(
     Ok(process.allocate(object_value::array(trace), state.array_prototype))
 }
 
-register!(process_stacktrace);
+/// Starts monitoring a process.
+///
+/// When the monitored process terminates, the current process is sent a
+/// message containing the process and the reason it terminated. If the process
+/// already terminated, this message is sent right away.
+///
+/// This function requires a single argument: the process to monitor.
+pub fn process_monitor(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let target = arguments[0].process_value()?;
+
+    if let Some(reason) = process.monitor(target) {
+        send_exit_message(state, process, target, &reason);
+    }
+
+    Ok(state.nil_object)
+}
+
+/// Stops monitoring a process.
+///
+/// This function requires a single argument: the process to stop monitoring.
+pub fn process_demonitor(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    process.demonitor(arguments[0].process_value()?);
+
+    Ok(state.nil_object)
+}
+
+/// Links the current process to another process.
+///
+/// When either process terminates, the other process is sent an exit message.
+/// If either process panics, the other process panics as well, unless it traps
+/// exits. If the process to link to already terminated, this is applied right
+/// away.
+///
+/// This function requires a single argument: the process to link to.
+pub fn process_link(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let target = arguments[0].process_value()?;
+
+    if let Some(reason) = process.link(target) {
+        if let ExitReason::Panic(_) = reason {
+            if !process.traps_exits() {
+                return Err(format!(
+                    "The linked process {:#x} panicked",
+                    target.identifier()
+                )
+                .into());
+            }
+        }
+
+        send_exit_message(state, process, target, &reason);
+    }
+
+    Ok(state.nil_object)
+}
+
+/// Removes a link between the current process and another process.
+///
+/// This function requires a single argument: the process to unlink.
+pub fn process_unlink(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    process.unlink(arguments[0].process_value()?);
+
+    Ok(state.nil_object)
+}
+
+/// Sets whether the current process traps the exits of linked processes.
+///
+/// When trapping exits, a panic in a linked process only results in an exit
+/// message being sent to the current process, instead of also terminating the
+/// current process.
+///
+/// This function requires a single argument: a boolean indicating if exits
+/// should be trapped.
+pub fn process_set_trap_exits(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    process.set_trap_exits(arguments[0] == state.true_object);
+
+    Ok(state.nil_object)
+}
+
+/// Returns the number of messages in the mailbox of a process.
+///
+/// This function requires a single argument: the process to get the mailbox
+/// length of.
+pub fn process_mailbox_length(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let length = arguments[0].process_value()?.mailbox_length();
+
+    Ok(process.allocate_usize(length, state.integer_prototype))
+}
+
+/// Limits the number of messages the mailbox of the current process can store.
+///
+/// This function requires two arguments:
+///
+/// 1. The maximum number of messages, or 0 to remove the limit.
+/// 2. The overflow policy to use when the mailbox is full.
+pub fn process_set_mailbox_capacity(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let capacity = arguments[0].usize_value()?;
+    let policy = OverflowPolicy::from_u8(arguments[1].u8_value()?)
+        .ok_or_else(|| "The overflow policy is invalid".to_string())?;
+
+    let capacity = if capacity > 0 { Some(capacity) } else { None };
+
+    process.set_mailbox_capacity(capacity, policy);
+    Ok(state.nil_object)
+}
+
+/// Requests a garbage collection of the current process.
+///
+/// The collection is performed the next time the process reaches a GC
+/// safepoint, such as when calling a method.
+///
+/// This function requires a single argument: a boolean indicating if the
+/// mature generation should be collected as well.
+pub fn process_collect_garbage(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    process.request_collection(arguments[0] == state.true_object);
+    Ok(state.nil_object)
+}
+
+/// Limits the size of the heap of the current process.
+///
+/// This function requires two arguments:
+///
+/// 1. The maximum number of bytes, or 0 to remove the limit.
+/// 2. The policy to use when the limit is exceeded.
+pub fn process_set_heap_limit(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let bytes = arguments[0].usize_value()?;
+    let policy = HeapLimitPolicy::from_u8(arguments[1].u8_value()?)
+        .ok_or_else(|| "The heap limit policy is invalid".to_string())?;
+
+    process.set_heap_limit(bytes, policy);
+    Ok(state.nil_object)
+}
+
+/// Requests a heap snapshot of the current process.
+///
+/// The file is created right away, but the snapshot is written after the full
+/// collection performed at the next GC safepoint.
+///
+/// This function requires a single argument: the path to write the snapshot
+/// to.
+pub fn process_heap_snapshot(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let file = File::create(arguments[0].string_value()?.as_slice())?;
+
+    process.request_heap_snapshot(file);
+    Ok(state.nil_object)
+}
+
+/// Returns the priority of a process.
+///
+/// This function requires a single argument: the process to get the priority
+/// of.
+pub fn process_priority(
+    _: &RcState,
+    _: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let priority = arguments[0].process_value()?.priority();
+
+    Ok(ObjectPointer::integer(i64::from(priority.to_u8())))
+}
+
+/// Changes the priority of a process.
+///
+/// The new priority takes effect the next time the process is scheduled.
+///
+/// This function requires two arguments:
+///
+/// 1. The process to change the priority of.
+/// 2. The new priority.
+pub fn process_set_priority(
+    state: &RcState,
+    _: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let target = arguments[0].process_value()?;
+    let priority = Priority::from_u8(arguments[1].u8_value()?)
+        .ok_or_else(|| "The process priority is invalid".to_string())?;
+
+    target.set_priority(priority);
+    Ok(state.nil_object)
+}
+
+/// Returns statistics about a process.
+///
+/// The statistics are returned as an Array containing the following values:
+///
+/// 1. The number of memory blocks used by the heap of the process.
+/// 2. The number of messages in the mailbox of the process.
+/// 3. The number of reductions performed by the process.
+/// 4. A boolean indicating if the process is pinned to a thread.
+/// 5. A boolean indicating if the process is running in the blocking pool.
+/// 6. The number of memory blocks used by the young generation.
+/// 7. The number of memory blocks used by the mature generation.
+/// 8. The number of blocks the young generation can allocate before it's
+///    garbage collected.
+/// 9. The number of blocks the mature generation can allocate before it's
+///    garbage collected.
+///
+/// This function requires a single argument: the process to get the
+/// statistics of.
+pub fn process_stats(
+    state: &RcState,
+    process: &RcProcess,
+    arguments: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let target = arguments[0].process_value()?;
+    let heap = target.heap_statistics();
+    let boolean = |value| {
+        if value {
+            state.true_object
+        } else {
+            state.false_object
+        }
+    };
+
+    let stats = vec![
+        process.allocate_usize(target.heap_blocks(), state.integer_prototype),
+        process
+            .allocate_usize(target.mailbox_length(), state.integer_prototype),
+        process.allocate_usize(target.reductions(), state.integer_prototype),
+        boolean(target.is_pinned()),
+        boolean(target.is_blocking()),
+        process.allocate_usize(target.young_blocks(), state.integer_prototype),
+        process.allocate_usize(target.mature_blocks(), state.integer_prototype),
+        process.allocate_u64(
+            u64::from(heap.young_threshold()),
+            state.integer_prototype,
+        ),
+        process.allocate_u64(
+            u64::from(heap.mature_threshold()),
+            state.integer_prototype,
+        ),
+    ];
+
+    Ok(process.allocate(object_value::array(stats), state.array_prototype))
+}
+
+/// Sends the current process the exit message of a process that already
+/// terminated.
+fn send_exit_message(
+    state: &RcState,
+    process: &RcProcess,
+    target: &RcProcess,
+    reason: &ExitReason,
+) {
+    let message = process_exit_message(state, process, target, reason);
+
+    process.send_message_from_self(message);
+}
+
+register!(
+    process_stacktrace,
+    process_monitor,
+    process_demonitor,
+    process_link,
+    process_unlink,
+    process_set_trap_exits,
+    process_mailbox_length,
+    process_set_mailbox_capacity,
+    process_collect_garbage,
+    process_set_heap_limit,
+    process_heap_snapshot,
+    process_priority,
+    process_set_priority,
+    process_stats
+);
@@ -0,0 +1,76 @@
+//! Functions for inspecting the process scheduler.
+use crate::object_pointer::ObjectPointer;
+use crate::object_value;
+use crate::process::RcProcess;
+use crate::runtime_error::RuntimeError;
+use crate::scheduler::process_pool::ProcessPool;
+use crate::vm::state::RcState;
+
+/// Returns a snapshot of the state of the process scheduler.
+///
+/// The snapshot is an Array containing the following values:
+///
+/// 1. The number of processes that are alive.
+/// 2. The number of processes suspended with a timeout.
+/// 3. The number of processes waiting for a socket to become ready.
+/// 4. The state of the primary pool.
+/// 5. The state of the blocking pool.
+///
+/// The state of a pool is an Array containing the number of processes in the
+/// global queue, and an Array containing the number of processes in the queue
+/// of every thread.
+///
+/// This function doesn't take any arguments.
+pub fn vm_scheduler_stats(
+    state: &RcState,
+    process: &RcProcess,
+    _: &[ObjectPointer],
+) -> Result<ObjectPointer, RuntimeError> {
+    let scheduler = &state.scheduler;
+    let stats = vec![
+        process.allocate_usize(
+            scheduler.live_processes(),
+            state.integer_prototype,
+        ),
+        process.allocate_usize(
+            state.timeout_worker.suspended_processes(),
+            state.integer_prototype,
+        ),
+        process.allocate_usize(
+            state.network_poller.waiting_processes(),
+            state.integer_prototype,
+        ),
+        pool_stats(state, process, &scheduler.primary_pool),
+        pool_stats(state, process, &scheduler.blocking_pool),
+    ];
+
+    Ok(process.allocate(object_value::array(stats), state.array_prototype))
+}
+
+fn pool_stats(
+    state: &RcState,
+    process: &RcProcess,
+    pool: &ProcessPool,
+) -> ObjectPointer {
+    let queues = pool
+        .state
+        .queues
+        .iter()
+        .map(|queue| {
+            process.allocate_usize(
+                queue.local_jobs() + queue.pending_external(),
+                state.integer_prototype,
+            )
+        })
+        .collect();
+
+    let stats = vec![
+        process
+            .allocate_usize(pool.state.global_jobs(), state.integer_prototype),
+        process.allocate(object_value::array(queues), state.array_prototype),
+    ];
+
+    process.allocate(object_value::array(stats), state.array_prototype)
+}
+
+register!(vm_scheduler_stats);
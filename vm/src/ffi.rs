@@ -339,6 +339,14 @@ impl Argument {
                         ObjectValue::ByteArray(ref bytes) => {
                             Argument::Pointer(bytes.as_ptr() as RawPointer)
                         }
+                        ObjectValue::FrozenByteArray(_) => {
+                            // The bytes may be shared with other processes,
+                            // and C code could modify them.
+                            return Err(
+                                "frozen byte arrays can not be passed as a pointer"
+                                    .to_string(),
+                            );
+                        }
                         ObjectValue::Pointer(ptr) => {
                             Argument::Pointer(ptr.as_c_pointer())
                         }
@@ -906,6 +914,7 @@ mod tests {
 #[cfg(test)]
 mod tests_for_all_platforms {
     use super::*;
+    use crate::vm::test::setup;
 
     #[test]
     fn test_library_new_invalid() {
@@ -922,6 +931,19 @@ mod tests_for_all_platforms {
         assert_eq!(ptr.unwrap().address(), 0);
     }
 
+    #[test]
+    fn test_argument_wrap_frozen_byte_array() {
+        let (_machine, _, process) = setup();
+        let mut value = object_value::byte_array(vec![1, 2]);
+
+        value.freeze_byte_array().unwrap();
+
+        let bytes = process.allocate_without_prototype(value);
+        let argument = unsafe { Argument::wrap(ffi_type!(pointer), bytes) };
+
+        assert!(argument.is_err());
+    }
+
     #[test]
     fn test_pointer_from_address_invalid() {
         let ptr = unsafe { Pointer::from_address(ObjectPointer::integer(-1)) };
@@ -29,43 +29,58 @@ pub struct File {
     path: ObjectPointer,
 }
 
+/// Returns the options to use for opening a file in the given mode.
+///
+/// Unknown modes are treated as `READ`.
+pub fn open_options(mode: i64) -> OpenOptions {
+    let mut options = OpenOptions::new();
+
+    match mode {
+        WRITE => options.write(true).truncate(true).create(true),
+        APPEND => options.append(true).create(true),
+        READ_WRITE => options.read(true).write(true).create(true),
+        READ_APPEND => options.read(true).append(true).create(true),
+        _ => options.read(true),
+    };
+
+    options
+}
+
 impl File {
+    pub fn new(file: fs::File, path: ObjectPointer) -> Self {
+        File {
+            inner: ClosableFile::new(file),
+            path,
+        }
+    }
+
     pub fn read_only(path: ObjectPointer) -> Result<Self, RuntimeError> {
-        Self::open(path, OpenOptions::new().read(true))
+        Self::open(path, &open_options(READ))
     }
 
     pub fn write_only(path: ObjectPointer) -> Result<Self, RuntimeError> {
-        Self::open(
-            path,
-            OpenOptions::new().write(true).truncate(true).create(true),
-        )
+        Self::open(path, &open_options(WRITE))
     }
 
     pub fn append_only(path: ObjectPointer) -> Result<Self, RuntimeError> {
-        Self::open(path, OpenOptions::new().append(true).create(true))
+        Self::open(path, &open_options(APPEND))
     }
 
     pub fn read_write(path: ObjectPointer) -> Result<Self, RuntimeError> {
-        Self::open(path, OpenOptions::new().read(true).write(true).create(true))
+        Self::open(path, &open_options(READ_WRITE))
     }
 
     pub fn read_append(path: ObjectPointer) -> Result<Self, RuntimeError> {
-        Self::open(
-            path,
-            OpenOptions::new().read(true).append(true).create(true),
-        )
+        Self::open(path, &open_options(READ_APPEND))
     }
 
     pub fn open(
         path: ObjectPointer,
-        options: &mut OpenOptions,
+        options: &OpenOptions,
     ) -> Result<Self, RuntimeError> {
         let file = options.open(path.string_value()?)?;
 
-        Ok(File {
-            inner: ClosableFile::new(file),
-            path,
-        })
+        Ok(Self::new(file, path))
     }
 
     pub fn path(&self) -> &ObjectPointer {
@@ -0,0 +1,414 @@
+//! Deeply immutable objects that can be shared between processes.
+//!
+//! Freezing an object makes it and every object it refers to immutable. The
+//! data of a frozen object is stored in a reference counted `FrozenObject`,
+//! which is shared with every process the object is sent to. Receiving a frozen
+//! object only allocates a new object header (and the headers of its
+//! attributes), instead of copying the data of every object involved.
+//!
+//! Only objects that don't refer to process-local state can be frozen. This
+//! means the prototype and attribute names of a frozen object must be
+//! permanent objects, and values such as files, blocks, and processes can't be
+//! frozen.
+use crate::arc_without_weak::ArcWithoutWeak;
+use crate::immix::copy_object::CopyObject;
+use crate::object::Object;
+use crate::object_pointer::ObjectPointer;
+use crate::object_value::ObjectValue;
+use crate::shape::Attributes;
+use ahash::AHashMap;
+use std::mem;
+
+/// A value stored in a frozen Array or in an attribute of a frozen object.
+#[derive(Clone)]
+pub enum FrozenValue {
+    /// A tagged integer or permanent object, which is shared as-is.
+    Pointer(ObjectPointer),
+
+    /// A frozen object, allocated separately for every process that uses it.
+    Object(ArcWithoutWeak<FrozenObject>),
+}
+
+impl FrozenValue {
+    /// Allocates the object of this value, if necessary.
+    pub fn allocate<A: CopyObject>(&self, allocator: &mut A) -> ObjectPointer {
+        match self {
+            FrozenValue::Pointer(pointer) => *pointer,
+            FrozenValue::Object(object) => {
+                FrozenObject::allocate(object, allocator)
+            }
+        }
+    }
+}
+
+/// The shared data of a frozen object.
+pub struct FrozenObject {
+    /// The prototype of the object, which is always a permanent object.
+    prototype: Option<ObjectPointer>,
+
+    /// The value of the object.
+    ///
+    /// This is `ObjectValue::None` for regular objects. For other objects this
+    /// is a value that can be shared without copying its data, such as a
+    /// String or a frozen Array.
+    value: ObjectValue,
+
+    /// The names and values of the object's attributes. The names are always
+    /// permanent objects.
+    attributes: Vec<(ObjectPointer, FrozenValue)>,
+}
+
+impl FrozenObject {
+    /// Returns the value of the object.
+    pub fn value(&self) -> &ObjectValue {
+        &self.value
+    }
+
+    /// Allocates a new object using the data of a frozen object.
+    pub fn allocate<A: CopyObject>(
+        object: &ArcWithoutWeak<FrozenObject>,
+        allocator: &mut A,
+    ) -> ObjectPointer {
+        let value = if object.value.is_none() {
+            ObjectValue::FrozenObject(object.clone())
+        } else {
+            share_value(&object.value)
+        };
+
+        let mut new_object = if let Some(prototype) = object.prototype {
+            Object::with_prototype(value, prototype)
+        } else {
+            Object::new(value)
+        };
+
+        if !object.attributes.is_empty() {
+            let mut attributes = Attributes::new();
+
+            for (name, value) in &object.attributes {
+                attributes.insert(*name, value.allocate(allocator));
+            }
+
+            new_object.set_attributes(attributes);
+        }
+
+        allocator.allocate_copy(new_object)
+    }
+}
+
+/// Freezes an object and every object it refers to.
+///
+/// If any of these objects can't be frozen, an error is returned and none of
+/// the objects are modified.
+pub fn freeze(pointer: ObjectPointer) -> Result<(), String> {
+    check(pointer, &mut Vec::new())?;
+    freeze_checked(pointer, &mut AHashMap::new());
+
+    Ok(())
+}
+
+/// Checks if an object and the objects it refers to can be frozen.
+///
+/// The `parents` argument contains the objects currently being checked, and is
+/// used to detect objects that (indirectly) refer to themselves.
+fn check(
+    pointer: ObjectPointer,
+    parents: &mut Vec<ObjectPointer>,
+) -> Result<(), String> {
+    if pointer.is_permanent() {
+        return Ok(());
+    }
+
+    if parents.contains(&pointer) {
+        return Err("Objects that refer to themselves can't be frozen".into());
+    }
+
+    let object = pointer.get();
+
+    match object.value {
+        ObjectValue::FrozenArray(_) | ObjectValue::FrozenObject(_) => {
+            return Ok(());
+        }
+        ObjectValue::None
+        | ObjectValue::Float(_)
+        | ObjectValue::Integer(_)
+        | ObjectValue::BigInt(_)
+        | ObjectValue::String(_)
+        | ObjectValue::InternedString(_)
+        | ObjectValue::ByteArray(_)
+        | ObjectValue::FrozenByteArray(_)
+        | ObjectValue::Array(_) => {}
+        ref value => {
+            return Err(format!("{} objects can't be frozen", value.name()));
+        }
+    }
+
+    if let Some(prototype) = object.prototype() {
+        if !prototype.is_permanent() {
+            return Err(
+                "Objects with a prototype that isn't permanent can't be frozen"
+                    .into(),
+            );
+        }
+    }
+
+    parents.push(pointer);
+
+    if let ObjectValue::Array(ref values) = object.value {
+        for value in values.iter() {
+            check(*value, parents)?;
+        }
+    }
+
+    if let Some(attributes) = object.attributes_ref() {
+        for (name, value) in attributes.iter() {
+            if !name.is_permanent() {
+                return Err(
+                    "Objects with attribute names that aren't permanent can't \
+                     be frozen"
+                        .into(),
+                );
+            }
+
+            check(value, parents)?;
+        }
+    }
+
+    parents.pop();
+
+    Ok(())
+}
+
+/// Freezes an object that is known to be freezable.
+///
+/// The `frozen` argument maps objects frozen so far to their frozen values,
+/// ensuring objects referred to multiple times are only frozen once.
+fn freeze_checked(
+    pointer: ObjectPointer,
+    frozen: &mut AHashMap<ObjectPointer, FrozenValue>,
+) -> FrozenValue {
+    if pointer.is_permanent() {
+        return FrozenValue::Pointer(pointer);
+    }
+
+    if let Some(value) = frozen.get(&pointer) {
+        return value.clone();
+    }
+
+    let object = pointer.get_mut();
+
+    if let ObjectValue::FrozenObject(ref shared) = object.value {
+        return FrozenValue::Object(shared.clone());
+    }
+
+    let mut attributes = Vec::new();
+
+    if let Some(object_attributes) = object.attributes_ref() {
+        let pairs: Vec<_> = object_attributes.iter().collect();
+
+        for (name, value) in pairs {
+            attributes.push((name, freeze_checked(value, frozen)));
+        }
+    }
+
+    let value = match mem::replace(&mut object.value, ObjectValue::None) {
+        ObjectValue::None => ObjectValue::None,
+        ObjectValue::Array(values) => {
+            let values: Vec<_> = values
+                .iter()
+                .map(|value| freeze_checked(*value, frozen))
+                .collect();
+
+            let shared = ArcWithoutWeak::new(values);
+
+            object.value = ObjectValue::FrozenArray(shared.clone());
+
+            ObjectValue::FrozenArray(shared)
+        }
+        ObjectValue::ByteArray(bytes) => {
+            let shared = ArcWithoutWeak::new(*bytes);
+
+            object.value = ObjectValue::FrozenByteArray(shared.clone());
+
+            ObjectValue::FrozenByteArray(shared)
+        }
+        value => {
+            let shared = share_value(&value);
+
+            object.value = value;
+
+            shared
+        }
+    };
+
+    let shared = ArcWithoutWeak::new(FrozenObject {
+        prototype: object.prototype(),
+        value,
+        attributes,
+    });
+
+    if object.value.is_none() {
+        object.value = ObjectValue::FrozenObject(shared.clone());
+    }
+
+    let value = FrozenValue::Object(shared);
+
+    frozen.insert(pointer, value.clone());
+
+    value
+}
+
+/// Returns a value that shares its data with the given value.
+///
+/// This function only supports values that can be stored in a `FrozenObject`.
+fn share_value(value: &ObjectValue) -> ObjectValue {
+    match value {
+        ObjectValue::Float(value) => ObjectValue::Float(*value),
+        ObjectValue::Integer(value) => ObjectValue::Integer(*value),
+        ObjectValue::BigInt(value) => ObjectValue::BigInt(value.clone()),
+        ObjectValue::String(value) | ObjectValue::InternedString(value) => {
+            ObjectValue::String(value.clone())
+        }
+        ObjectValue::FrozenByteArray(value) => {
+            ObjectValue::FrozenByteArray(value.clone())
+        }
+        ObjectValue::FrozenArray(value) => {
+            ObjectValue::FrozenArray(value.clone())
+        }
+        _ => unreachable!("{} objects can't be shared", value.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_value;
+    use crate::vm::test::setup;
+
+    #[test]
+    fn test_freeze_array() {
+        let (_machine, _block, process) = setup();
+        let value = process.allocate_empty();
+        let array = process
+            .allocate_without_prototype(object_value::array(vec![value]));
+
+        freeze(array).unwrap();
+
+        assert!(array.get().value.is_frozen());
+        assert!(value.get().value.is_frozen());
+        assert!(array.get_mut().value.as_array_mut().is_err());
+    }
+
+    #[test]
+    fn test_freeze_object_with_attributes() {
+        let (machine, _block, process) = setup();
+        let name = machine.state.intern_string("bytes".to_string());
+        let bytes = process
+            .allocate_without_prototype(object_value::byte_array(vec![1]));
+        let object = process.allocate_empty();
+
+        object.get_mut().add_attribute(name, bytes);
+
+        freeze(object).unwrap();
+
+        assert!(object.get().value.is_frozen());
+        assert!(bytes.get().value.is_frozen_byte_array());
+        assert_eq!(object.get().lookup_attribute_in_self(name), Some(bytes));
+    }
+
+    #[test]
+    fn test_freeze_shared_object() {
+        let (_machine, _block, process) = setup();
+        let value = process.allocate_empty();
+        let array =
+            process.allocate_without_prototype(object_value::array(vec![
+                value, value,
+            ]));
+
+        freeze(array).unwrap();
+
+        let values = array.get().value.as_frozen_array().unwrap();
+
+        match (&values[0], &values[1]) {
+            (FrozenValue::Object(a), FrozenValue::Object(b)) => {
+                assert_eq!(a.as_ptr(), b.as_ptr());
+            }
+            _ => panic!("expected the values to be frozen objects"),
+        }
+    }
+
+    #[test]
+    fn test_freeze_unsupported_value() {
+        let (_machine, block, process) = setup();
+        let block = process
+            .allocate_without_prototype(object_value::block(block.clone()));
+        let bytes = process
+            .allocate_without_prototype(object_value::byte_array(vec![1]));
+        let array =
+            process.allocate_without_prototype(object_value::array(vec![
+                bytes, block,
+            ]));
+
+        assert!(freeze(array).is_err());
+        assert!(array.get().value.is_array());
+        assert!(!bytes.get().value.is_frozen_byte_array());
+    }
+
+    #[test]
+    fn test_freeze_object_with_process_local_prototype() {
+        let (_machine, _block, process) = setup();
+        let prototype = process.allocate_empty();
+        let object = process.allocate_empty();
+
+        object.get_mut().set_prototype(prototype);
+
+        assert!(freeze(object).is_err());
+        assert!(!object.get().value.is_frozen());
+    }
+
+    #[test]
+    fn test_freeze_object_with_process_local_attribute_name() {
+        let (_machine, _block, process) = setup();
+        let name = process.allocate_empty();
+        let object = process.allocate_empty();
+
+        object.get_mut().add_attribute(name, name);
+
+        assert!(freeze(object).is_err());
+    }
+
+    #[test]
+    fn test_freeze_cyclic_object() {
+        let (machine, _block, process) = setup();
+        let name = machine.state.intern_string("self".to_string());
+        let object = process.allocate_empty();
+
+        object.get_mut().add_attribute(name, object);
+
+        assert!(freeze(object).is_err());
+        assert!(!object.get().value.is_frozen());
+    }
+
+    #[test]
+    fn test_allocate_frozen_value() {
+        let (machine, _block, process) = setup();
+        let name = machine.state.intern_string("number".to_string());
+        let number =
+            process.allocate_without_prototype(object_value::integer(42));
+        let object = process.allocate_empty();
+
+        object
+            .get_mut()
+            .set_prototype(machine.state.array_prototype);
+        object.get_mut().add_attribute(name, number);
+
+        let value = freeze_checked(object, &mut AHashMap::new());
+        let copy = process.allocate_frozen(&value);
+        let copied_number = copy.get().lookup_attribute_in_self(name).unwrap();
+
+        assert!(copy != object);
+        assert!(copy.get().value.is_frozen());
+        assert_eq!(copy.get().prototype(), Some(machine.state.array_prototype));
+        assert!(copied_number != number);
+        assert_eq!(copied_number.integer_value().unwrap(), 42);
+    }
+}
@@ -32,9 +32,17 @@ pub fn collect(
 
     let stats = CollectionStatistics {
         duration: start_time.elapsed(),
+        mature: collect_mature,
         trace: trace_stats,
     };
 
+    process.record_gc_statistics(&stats);
+    vm_state.gc_statistics.lock().record(&stats);
+
+    if let Some(log) = vm_state.gc_log.as_ref() {
+        log.write(vm_state.start_time.elapsed(), process, &stats);
+    }
+
     if vm_state.config.print_gc_timings {
         eprintln!(
                 "[{:#x}] GC (mature: {}) in {:?}, {} marked, {} promoted, {} evacuated",
@@ -105,6 +113,27 @@ mod tests {
         assert!(pointer.is_marked());
     }
 
+    #[test]
+    fn test_collect_records_statistics() {
+        let (_machine, _block, process) = setup();
+        let state = State::with_rc(Config::new(), &[]);
+
+        process
+            .context_mut()
+            .set_register(0, process.allocate_empty());
+
+        collect(&state, &process, &Pool::new(1));
+
+        let process_stats = process.gc_statistics();
+        let vm_stats = state.gc_statistics.lock();
+
+        assert_eq!(process_stats.young_collections, 1);
+        assert_eq!(process_stats.mature_collections, 0);
+        assert_eq!(process_stats.marked, 1);
+        assert_eq!(vm_stats.young_collections, 1);
+        assert_eq!(vm_stats.marked, 1);
+    }
+
     #[test]
     fn test_trace_trace_without_moving_without_mature() {
         let (_machine, _block, process) = setup();
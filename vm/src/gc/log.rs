@@ -0,0 +1,149 @@
+//! Structured logging of garbage collections.
+//!
+//! Every collection is written to the log as a single line containing a JSON
+//! object, making it easy to process the log using other tools.
+use crate::gc::statistics::CollectionStatistics;
+use crate::process::RcProcess;
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// A file to write garbage collection statistics to.
+pub struct GcLog {
+    file: Mutex<File>,
+}
+
+impl GcLog {
+    /// Opens the log at the given path, appending to the file if it already
+    /// exists.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| {
+                format!("Failed to open the GC log {}: {}", path.display(), err)
+            })?;
+
+        Ok(GcLog {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Writes the statistics of a single collection to the log.
+    ///
+    /// The `time` argument is the time elapsed since the VM started.
+    pub fn write(
+        &self,
+        time: Duration,
+        process: &RcProcess,
+        stats: &CollectionStatistics,
+    ) {
+        let mut line = entry(time, process, stats);
+
+        line.push('\n');
+
+        // Failing to write to the log shouldn't affect the running program, so
+        // any errors are ignored.
+        let _ = self.file.lock().write_all(line.as_bytes());
+    }
+}
+
+/// Returns a JSON object describing a single collection.
+fn entry(
+    time: Duration,
+    process: &RcProcess,
+    stats: &CollectionStatistics,
+) -> String {
+    let allocator = &process.local_data().allocator;
+
+    format!(
+        "{{\"time_us\":{},\"process\":{},\"mature\":{},\"duration_us\":{},\
+         \"marked\":{},\"promoted\":{},\"evacuated\":{},\"young_blocks\":{},\
+         \"mature_blocks\":{},\"young_threshold\":{},\"mature_threshold\":{}}}",
+        time.as_micros(),
+        process.identifier(),
+        stats.mature,
+        stats.duration.as_micros(),
+        stats.trace.marked,
+        stats.trace.promoted,
+        stats.trace.evacuated,
+        process.young_blocks(),
+        process.mature_blocks(),
+        allocator.young_config.threshold,
+        allocator.mature_config.threshold
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gc::statistics::TraceStatistics;
+    use crate::vm::test::setup;
+    use std::env;
+    use std::fs;
+
+    fn statistics() -> CollectionStatistics {
+        let mut trace = TraceStatistics::new();
+
+        trace.marked = 3;
+        trace.promoted = 2;
+        trace.evacuated = 1;
+
+        CollectionStatistics {
+            duration: Duration::from_micros(15),
+            mature: true,
+            trace,
+        }
+    }
+
+    #[test]
+    fn test_entry() {
+        let (_machine, _block, process) = setup();
+        let allocator = &process.local_data().allocator;
+        let line = entry(Duration::from_micros(42), &process, &statistics());
+
+        assert_eq!(
+            line,
+            format!(
+                "{{\"time_us\":42,\"process\":{},\"mature\":true,\
+                 \"duration_us\":15,\"marked\":3,\"promoted\":2,\
+                 \"evacuated\":1,\"young_blocks\":0,\"mature_blocks\":0,\
+                 \"young_threshold\":{},\"mature_threshold\":{}}}",
+                process.identifier(),
+                allocator.young_config.threshold,
+                allocator.mature_config.threshold
+            )
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let (_machine, _block, process) = setup();
+        let path = env::temp_dir().join("inko_test_gc_log_write.jsonl");
+        let _ = fs::remove_file(&path);
+        let log = GcLog::open(&path).unwrap();
+
+        log.write(Duration::from_secs(1), &process, &statistics());
+        log.write(Duration::from_secs(2), &process, &statistics());
+
+        let contents = fs::read_to_string(&path).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        let lines = contents.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"time_us\":1000000,"));
+        assert!(lines[1].starts_with("{\"time_us\":2000000,"));
+    }
+
+    #[test]
+    fn test_open_invalid_path() {
+        let path = env::temp_dir().join("inko_missing_directory").join("log");
+
+        assert!(GcLog::open(&path).is_err());
+    }
+}
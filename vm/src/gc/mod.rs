@@ -1,4 +1,6 @@
 pub mod collection;
+pub mod log;
 pub mod remembered_set;
+pub mod snapshot;
 pub mod statistics;
 pub mod tracer;
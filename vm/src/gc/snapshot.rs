@@ -0,0 +1,828 @@
+//! Heap snapshots of processes.
+//!
+//! A heap snapshot describes every live object of a single process, allowing
+//! one to find out which objects are retained and why. Snapshots are captured
+//! right after a full collection, at which point the mark bytemaps of the
+//! process' blocks tell us exactly which objects are live.
+//!
+//! A snapshot is a text file containing one entry per line, with fields
+//! separated by a single space. The first line is the header, and is always
+//! `inko-heap-snapshot` followed by the version of the format. The header is
+//! followed by these entries:
+//!
+//! * `process ID`: the identifier of the process, in hexadecimal.
+//! * `root KIND ADDRESS`: an object that is directly reachable by the process.
+//!   The kind is one of `stack`, `mailbox` or `result`.
+//! * `object ADDRESS GENERATION TYPE PROTOTYPE [NAME]`: a live object. The
+//!   generation is either `young` or `mature`, and the type is the type of the
+//!   object's value (e.g. `Array`). The prototype is the address of the
+//!   object's prototype, or `-` if it has none. The name is the name of the
+//!   prototype, if it has one.
+//! * `attribute NAME`: the name of an attribute of the last object.
+//! * `reference ADDRESS`: an object the last object refers to.
+//!
+//! Addresses are written in hexadecimal. Names span until the end of the line,
+//! with backslashes and newlines escaped as `\\` and `\n`. Roots and references
+//! only include objects stored on the heap of the process, meaning permanent
+//! objects and integers are left out.
+use crate::immix::block::{OBJECTS_PER_BLOCK, OBJECT_START_SLOT};
+use crate::immix::bytemap::Bytemap;
+use crate::object_pointer::{ObjectPointer, ObjectPointerPointer};
+use crate::process::RcProcess;
+use crate::vm::state::State;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The version of the snapshot format.
+pub const VERSION: u8 = 1;
+
+/// The first word of every snapshot.
+const HEADER: &str = "inko-heap-snapshot";
+
+/// The attribute containing the name of an object.
+const OBJECT_NAME_ATTRIBUTE: &str = "@_object_name";
+
+/// The number of snapshot signals received by this OS process.
+static SIGNALS: AtomicUsize = AtomicUsize::new(0);
+
+/// An object directly reachable by a process.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Root {
+    pub kind: String,
+    pub address: usize,
+}
+
+/// A single live object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotObject {
+    pub address: usize,
+    pub mature: bool,
+
+    /// The type of the object's value.
+    pub kind: String,
+
+    pub prototype: Option<usize>,
+
+    /// The name of the object's prototype.
+    pub name: Option<String>,
+
+    pub attributes: Vec<String>,
+
+    /// The addresses of the objects this object refers to.
+    pub references: Vec<usize>,
+}
+
+/// A snapshot of the heap of a single process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapSnapshot {
+    pub process: usize,
+    pub roots: Vec<Root>,
+    pub objects: Vec<SnapshotObject>,
+}
+
+impl SnapshotObject {
+    fn from_pointer(
+        pointer: ObjectPointer,
+        name_attribute: ObjectPointer,
+    ) -> Self {
+        let object = pointer.get();
+        let prototype = object.prototype();
+        let name = prototype.and_then(|proto| {
+            proto
+                .get()
+                .lookup_attribute_in_self(name_attribute)
+                .and_then(string)
+        });
+
+        let mut attributes = object
+            .attribute_names()
+            .into_iter()
+            .filter_map(string)
+            .collect::<Vec<_>>();
+
+        // Attributes are stored in a hash map, so we sort them to produce the
+        // same output for the same objects.
+        attributes.sort();
+
+        let mut references = Vec::new();
+
+        object.each_pointer(|child| {
+            if let Some(address) = heap_address(child) {
+                references.push(address);
+            }
+        });
+
+        SnapshotObject {
+            address: pointer.raw.raw as usize,
+            mature: pointer.is_mature(),
+            kind: object.value.name().to_string(),
+            prototype: prototype.map(|proto| proto.raw.raw as usize),
+            name,
+            attributes,
+            references,
+        }
+    }
+
+    /// Returns a human readable description of the type of this object.
+    pub fn description(&self) -> String {
+        match self.name {
+            Some(ref name) if *name != self.kind => {
+                format!("{} ({})", name, self.kind)
+            }
+            Some(ref name) => name.clone(),
+            None => self.kind.clone(),
+        }
+    }
+}
+
+impl HeapSnapshot {
+    /// Captures a snapshot of the heap of a process.
+    ///
+    /// This method must be called right after a full collection of the
+    /// process, as only objects marked by this collection are included.
+    pub fn capture(state: &State, process: &RcProcess) -> Self {
+        let name_attribute =
+            state.intern_string(OBJECT_NAME_ATTRIBUTE.to_string());
+        let mut roots = Vec::new();
+
+        process.each_global_pointer(|ptr| add_root(&mut roots, "result", ptr));
+
+        process
+            .local_data()
+            .mailbox
+            .lock()
+            .each_pointer(|ptr| add_root(&mut roots, "mailbox", ptr));
+
+        for context in process.contexts() {
+            context.each_pointer(|ptr| add_root(&mut roots, "stack", ptr));
+        }
+
+        // Registers and bindings may refer to the same object many times, so
+        // we only include every root once.
+        roots.sort();
+        roots.dedup();
+
+        let allocator = &process.local_data().allocator;
+        let buckets = allocator
+            .young_generation
+            .iter()
+            .chain(iter::once(&allocator.mature_generation));
+
+        let mut objects = Vec::new();
+
+        for bucket in buckets {
+            let head = bucket.blocks.head();
+
+            if head.is_null() {
+                continue;
+            }
+
+            for block in head.iter() {
+                for index in OBJECT_START_SLOT..OBJECTS_PER_BLOCK {
+                    if !block.marked_objects_bytemap.is_set(index) {
+                        continue;
+                    }
+
+                    let pointer =
+                        ObjectPointer::new(unsafe { block.lines.add(index) });
+
+                    objects.push(SnapshotObject::from_pointer(
+                        pointer,
+                        name_attribute,
+                    ));
+                }
+            }
+        }
+
+        HeapSnapshot {
+            process: process.identifier(),
+            roots,
+            objects,
+        }
+    }
+
+    /// Reads a snapshot from a file.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| {
+            format!("Failed to open {}: {}", path.display(), err)
+        })?;
+
+        Self::parse(BufReader::new(file))
+    }
+
+    /// Parses a snapshot.
+    pub fn parse<R: BufRead>(input: R) -> Result<Self, String> {
+        let mut lines = input.lines().enumerate();
+        let mut snapshot = HeapSnapshot {
+            process: 0,
+            roots: Vec::new(),
+            objects: Vec::new(),
+        };
+
+        match lines.next() {
+            Some((_, Ok(line))) => {
+                if line != format!("{} {}", HEADER, VERSION) {
+                    return Err("The file is not a heap snapshot, or uses an \
+                                unsupported version"
+                        .to_string());
+                }
+            }
+            Some((_, Err(err))) => return Err(err.to_string()),
+            None => return Err("The heap snapshot is empty".to_string()),
+        }
+
+        for (index, line) in lines {
+            let line = line.map_err(|err| err.to_string())?;
+
+            snapshot
+                .parse_entry(&line)
+                .map_err(|err| format!("Line {}: {}", index + 1, err))?;
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Writes the snapshot to a file.
+    pub fn write_file(&self, file: File) -> io::Result<()> {
+        let mut output = BufWriter::new(file);
+
+        self.write(&mut output)?;
+        output.flush()
+    }
+
+    /// Writes the snapshot to the given output.
+    pub fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(output, "{} {}", HEADER, VERSION)?;
+        writeln!(output, "process {:#x}", self.process)?;
+
+        for root in &self.roots {
+            writeln!(output, "root {} {:#x}", root.kind, root.address)?;
+        }
+
+        for object in &self.objects {
+            write!(
+                output,
+                "object {:#x} {} {}",
+                object.address,
+                if object.mature { "mature" } else { "young" },
+                object.kind
+            )?;
+
+            if let Some(prototype) = object.prototype {
+                write!(output, " {:#x}", prototype)?;
+            } else {
+                write!(output, " -")?;
+            }
+
+            if let Some(ref name) = object.name {
+                write!(output, " {}", escape(name))?;
+            }
+
+            writeln!(output)?;
+
+            for name in &object.attributes {
+                writeln!(output, "attribute {}", escape(name))?;
+            }
+
+            for address in &object.references {
+                writeln!(output, "reference {:#x}", address)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of objects per type, sorted from the most common
+    /// type to the least common type.
+    pub fn type_counts(&self) -> Vec<(String, usize)> {
+        let mut counts = HashMap::new();
+
+        for object in &self.objects {
+            *counts.entry(object.description()).or_insert(0) += 1;
+        }
+
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Returns the shortest path from a root to the object at the given
+    /// address.
+    ///
+    /// The path starts with the root, and ends with the object itself.
+    pub fn path_to(&self, address: usize) -> Option<(&Root, Vec<usize>)> {
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| (object.address, object))
+            .collect::<HashMap<_, _>>();
+
+        let mut parents = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        for (index, root) in self.roots.iter().enumerate() {
+            if visited.insert(root.address) {
+                queue.push_back((root.address, index));
+            }
+        }
+
+        while let Some((current, root)) = queue.pop_front() {
+            if current == address {
+                let mut path = vec![current];
+
+                while let Some(parent) = parents.get(path.last().unwrap()) {
+                    path.push(*parent);
+                }
+
+                path.reverse();
+
+                return Some((&self.roots[root], path));
+            }
+
+            if let Some(object) = objects.get(&current) {
+                for &child in &object.references {
+                    if visited.insert(child) {
+                        parents.insert(child, current);
+                        queue.push_back((child, root));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn parse_entry(&mut self, line: &str) -> Result<(), String> {
+        let mut fields = line.splitn(2, ' ');
+        let kind = fields.next().unwrap_or("");
+        let rest = fields.next().unwrap_or("");
+
+        match kind {
+            "process" => {
+                self.process = parse_address(rest)?;
+            }
+            "root" => {
+                let mut fields = rest.splitn(2, ' ');
+                let kind = fields.next().unwrap_or("").to_string();
+                let address = parse_address(fields.next().unwrap_or(""))?;
+
+                self.roots.push(Root { kind, address });
+            }
+            "object" => {
+                let mut fields = rest.splitn(5, ' ');
+                let address = parse_address(fields.next().unwrap_or(""))?;
+                let mature = match fields.next() {
+                    Some("young") => false,
+                    Some("mature") => true,
+                    _ => return Err("The generation is invalid".to_string()),
+                };
+
+                let kind = match fields.next() {
+                    Some(kind) if !kind.is_empty() => kind.to_string(),
+                    _ => return Err("The object type is missing".to_string()),
+                };
+
+                let prototype = match fields.next() {
+                    Some("-") => None,
+                    Some(value) => Some(parse_address(value)?),
+                    None => return Err("The prototype is missing".to_string()),
+                };
+
+                self.objects.push(SnapshotObject {
+                    address,
+                    mature,
+                    kind,
+                    prototype,
+                    name: fields
+                        .next()
+                        .filter(|name| !name.is_empty())
+                        .map(unescape),
+                    attributes: Vec::new(),
+                    references: Vec::new(),
+                });
+            }
+            "attribute" => {
+                self.last_object()?.attributes.push(unescape(rest));
+            }
+            "reference" => {
+                let address = parse_address(rest)?;
+
+                self.last_object()?.references.push(address);
+            }
+            _ => return Err(format!("The entry {:?} is invalid", kind)),
+        }
+
+        Ok(())
+    }
+
+    fn last_object(&mut self) -> Result<&mut SnapshotObject, String> {
+        self.objects
+            .last_mut()
+            .ok_or_else(|| "The entry must follow an object".to_string())
+    }
+}
+
+/// Records a snapshot signal sent to this OS process.
+pub fn signal_received() {
+    SIGNALS.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Returns the number of snapshot signals received so far.
+pub fn signals() -> usize {
+    SIGNALS.load(Ordering::Acquire)
+}
+
+/// Installs a handler that records a snapshot signal whenever the OS process
+/// receives SIGUSR1.
+#[cfg(unix)]
+pub fn install_signal_handler() -> Result<(), String> {
+    use nix::sys::signal::{
+        sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal,
+    };
+    use std::os::raw::c_int;
+
+    extern "C" fn handle(_: c_int) {
+        signal_received();
+    }
+
+    let action = SigAction::new(
+        SigHandler::Handler(handle),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+
+    unsafe { sigaction(Signal::SIGUSR1, &action) }
+        .map(|_| ())
+        .map_err(|err| {
+            format!("Failed to install the heap snapshot handler: {}", err)
+        })
+}
+
+#[cfg(not(unix))]
+pub fn install_signal_handler() -> Result<(), String> {
+    Err("Heap snapshot signals aren't supported on this platform".to_string())
+}
+
+/// Requests a snapshot of a process if a snapshot signal was received since the
+/// process last checked for signals.
+///
+/// The snapshot is written to a new file in the given directory.
+pub fn check_signals(directory: &Path, process: &RcProcess) {
+    let received = signals();
+
+    if !process.acknowledge_heap_snapshot_signals(received) {
+        return;
+    }
+
+    let path = signal_snapshot_path(directory, process, received);
+
+    match File::create(&path) {
+        Ok(file) => process.request_heap_snapshot(file),
+        Err(err) => eprintln!(
+            "Failed to create the heap snapshot {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+fn signal_snapshot_path(
+    directory: &Path,
+    process: &RcProcess,
+    signal: usize,
+) -> PathBuf {
+    directory.join(format!(
+        "heap-{}-{:#x}-{}.snapshot",
+        std::process::id(),
+        process.identifier(),
+        signal
+    ))
+}
+
+fn add_root(roots: &mut Vec<Root>, kind: &str, pointer: ObjectPointerPointer) {
+    if let Some(address) = heap_address(pointer) {
+        roots.push(Root {
+            kind: kind.to_string(),
+            address,
+        });
+    }
+}
+
+/// Returns the address of the object a pointer points to, if the object is
+/// stored on the heap of a process.
+fn heap_address(pointer: ObjectPointerPointer) -> Option<usize> {
+    let pointer = pointer.get();
+
+    if pointer.is_null() || pointer.is_permanent() {
+        None
+    } else {
+        Some(pointer.raw.raw as usize)
+    }
+}
+
+fn string(pointer: ObjectPointer) -> Option<String> {
+    pointer
+        .string_value()
+        .ok()
+        .map(|value| value.as_slice().to_string())
+}
+
+fn parse_address(value: &str) -> Result<usize, String> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+
+    usize::from_str_radix(digits, 16)
+        .map_err(|_| format!("The address {:?} is invalid", value))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(current) = chars.next() {
+        if current != '\\' {
+            result.push(current);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gc::collection::collect;
+    use crate::gc::tracer::Pool;
+    use crate::object_value;
+    use crate::vm::test::setup;
+    use std::env;
+    use std::fs;
+
+    fn object(address: usize, references: Vec<usize>) -> SnapshotObject {
+        SnapshotObject {
+            address,
+            mature: false,
+            kind: "Object".to_string(),
+            prototype: None,
+            name: None,
+            attributes: Vec::new(),
+            references,
+        }
+    }
+
+    fn root(address: usize) -> Root {
+        Root {
+            kind: "stack".to_string(),
+            address,
+        }
+    }
+
+    #[test]
+    fn test_capture() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let proto = process.allocate_empty();
+        let instance = process.allocate(object_value::none(), proto);
+        let child = process.allocate_empty();
+        let array = process
+            .allocate(object_value::array(vec![child]), state.array_prototype);
+
+        process.allocate_empty();
+
+        proto.add_attribute(
+            &process,
+            state.intern_string(OBJECT_NAME_ATTRIBUTE.to_string()),
+            state.intern_string("Foo".to_string()),
+        );
+
+        instance.add_attribute(
+            &process,
+            state.intern_string("@values".to_string()),
+            array,
+        );
+
+        process.context_mut().set_register(0, instance);
+        process.context_mut().set_register(1, instance);
+        process.request_collection(true);
+
+        collect(state, &process, &Pool::new(1));
+
+        let snapshot = HeapSnapshot::capture(state, &process);
+        let address = |pointer: ObjectPointer| pointer.raw.raw as usize;
+        let find = |pointer: ObjectPointer| {
+            snapshot
+                .objects
+                .iter()
+                .find(|object| object.address == address(pointer))
+                .unwrap()
+        };
+
+        assert_eq!(snapshot.process, process.identifier());
+        assert_eq!(snapshot.roots, vec![root(address(instance))]);
+        assert_eq!(snapshot.objects.len(), 4);
+
+        let instance_object = find(instance);
+
+        assert_eq!(instance_object.kind, "Object");
+        assert_eq!(instance_object.name, Some("Foo".to_string()));
+        assert_eq!(instance_object.prototype, Some(address(proto)));
+        assert_eq!(instance_object.attributes, vec!["@values".to_string()]);
+        assert_eq!(
+            instance_object.references,
+            vec![address(proto), address(array)]
+        );
+
+        let array_object = find(array);
+
+        assert_eq!(array_object.kind, "Array");
+        assert_eq!(array_object.references, vec![address(child)]);
+        assert_eq!(find(proto).attributes, vec!["@_object_name".to_string()]);
+        assert!(find(child).references.is_empty());
+    }
+
+    #[test]
+    fn test_write() {
+        let mut instance = object(0x10, vec![0x20]);
+        let mut output = Vec::new();
+
+        instance.prototype = Some(0x30);
+        instance.name = Some("Foo".to_string());
+        instance.attributes = vec!["@a".to_string(), "@b".to_string()];
+
+        let snapshot = HeapSnapshot {
+            process: 0x1,
+            roots: vec![root(0x10)],
+            objects: vec![instance, object(0x20, Vec::new())],
+        };
+
+        snapshot.write(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "inko-heap-snapshot 1\n\
+             process 0x1\n\
+             root stack 0x10\n\
+             object 0x10 young Object 0x30 Foo\n\
+             attribute @a\n\
+             attribute @b\n\
+             reference 0x20\n\
+             object 0x20 young Object -\n"
+        );
+    }
+
+    #[test]
+    fn test_write_and_parse() {
+        let mut instance = object(0x10, vec![0x20, 0x30]);
+        let mut output = Vec::new();
+
+        instance.mature = true;
+        instance.name = Some("A name\nwith \\ characters".to_string());
+        instance.attributes = vec!["@with space".to_string()];
+
+        let snapshot = HeapSnapshot {
+            process: 0x1,
+            roots: vec![root(0x10)],
+            objects: vec![instance, object(0x20, Vec::new())],
+        };
+
+        snapshot.write(&mut output).unwrap();
+
+        assert_eq!(HeapSnapshot::parse(output.as_slice()), Ok(snapshot));
+    }
+
+    #[test]
+    fn test_parse_invalid_snapshots() {
+        let parse = |input: &str| HeapSnapshot::parse(input.as_bytes());
+
+        assert!(parse("").is_err());
+        assert!(parse("inko-heap-snapshot 2\n").is_err());
+        assert!(parse("inko-heap-snapshot 1\nfoo 0x1\n").is_err());
+        assert!(parse("inko-heap-snapshot 1\nattribute @a\n").is_err());
+        assert!(parse("inko-heap-snapshot 1\nobject 0x1 old Object -").is_err());
+        assert_eq!(
+            parse("inko-heap-snapshot 1\nroot stack 0xz\n"),
+            Err("Line 2: The address \"0xz\" is invalid".to_string())
+        );
+    }
+
+    #[test]
+    fn test_read() {
+        let path = env::temp_dir().join("inko_test_heap_snapshot_read");
+        let snapshot = HeapSnapshot {
+            process: 0x1,
+            roots: vec![root(0x10)],
+            objects: vec![object(0x10, Vec::new())],
+        };
+
+        snapshot.write_file(File::create(&path).unwrap()).unwrap();
+
+        let result = HeapSnapshot::read(&path);
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Ok(snapshot));
+    }
+
+    #[test]
+    fn test_type_counts() {
+        let mut array = object(0x30, Vec::new());
+        let mut instance = object(0x40, Vec::new());
+
+        array.kind = "Array".to_string();
+        array.name = Some("Array".to_string());
+        instance.name = Some("Foo".to_string());
+
+        let snapshot = HeapSnapshot {
+            process: 0x1,
+            roots: Vec::new(),
+            objects: vec![
+                object(0x10, Vec::new()),
+                object(0x20, Vec::new()),
+                array,
+                instance,
+            ],
+        };
+
+        assert_eq!(
+            snapshot.type_counts(),
+            vec![
+                ("Object".to_string(), 2),
+                ("Array".to_string(), 1),
+                ("Foo (Object)".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_to() {
+        let snapshot = HeapSnapshot {
+            process: 0x1,
+            roots: vec![root(0x10), root(0x40)],
+            objects: vec![
+                object(0x10, vec![0x20]),
+                object(0x20, vec![0x30]),
+                object(0x30, Vec::new()),
+                object(0x40, vec![0x30]),
+                object(0x50, Vec::new()),
+            ],
+        };
+
+        let (path_root, path) = snapshot.path_to(0x30).unwrap();
+
+        assert_eq!(path_root.address, 0x40);
+        assert_eq!(path, vec![0x40, 0x30]);
+        assert_eq!(snapshot.path_to(0x10).unwrap().1, vec![0x10]);
+        assert!(snapshot.path_to(0x50).is_none());
+    }
+
+    #[test]
+    fn test_check_signals() {
+        let (_machine, _block, process) = setup();
+        let directory = env::temp_dir();
+
+        check_signals(&directory, &process);
+
+        assert!(process.take_heap_snapshot_request().is_none());
+
+        signal_received();
+        check_signals(&directory, &process);
+
+        let path = signal_snapshot_path(&directory, &process, signals());
+
+        assert!(process.take_heap_snapshot_request().is_some());
+        assert!(path.is_file());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_signal_snapshot_path() {
+        let (_machine, _block, process) = setup();
+        let path = signal_snapshot_path(Path::new("/tmp"), &process, 2);
+
+        assert_eq!(
+            path,
+            PathBuf::from(format!(
+                "/tmp/heap-{}-{:#x}-2.snapshot",
+                std::process::id(),
+                process.identifier()
+            ))
+        );
+    }
+}
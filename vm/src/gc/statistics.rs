@@ -1,5 +1,6 @@
 //! Types for storing garbage collection statistics.
 use std::ops::{Add, AddAssign};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Duration;
 
 /// Statistics produced by a single thread tracing objects.
@@ -51,10 +52,147 @@ pub struct CollectionStatistics {
     /// The total time spent garbage collecting.
     pub duration: Duration,
 
+    /// A boolean indicating if the mature generation was collected.
+    pub mature: bool,
+
     /// The statistics produced by tracing objects.
     pub trace: TraceStatistics,
 }
 
+/// Statistics accumulated over many garbage collections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GcStatistics {
+    /// The number of collections of only the young generation.
+    pub young_collections: usize,
+
+    /// The number of collections of both the young and mature generation.
+    pub mature_collections: usize,
+
+    /// The total time spent garbage collecting.
+    pub total_pause: Duration,
+
+    /// The longest time spent on a single collection.
+    pub max_pause: Duration,
+
+    /// The total number of marked objects.
+    pub marked: usize,
+
+    /// The total number of promoted objects.
+    pub promoted: usize,
+
+    /// The total number of evacuated objects.
+    pub evacuated: usize,
+}
+
+impl GcStatistics {
+    pub fn new() -> Self {
+        GcStatistics {
+            young_collections: 0,
+            mature_collections: 0,
+            total_pause: Duration::from_secs(0),
+            max_pause: Duration::from_secs(0),
+            marked: 0,
+            promoted: 0,
+            evacuated: 0,
+        }
+    }
+
+    /// Adds the statistics of a single collection.
+    pub fn record(&mut self, stats: &CollectionStatistics) {
+        if stats.mature {
+            self.mature_collections += 1;
+        } else {
+            self.young_collections += 1;
+        }
+
+        self.total_pause += stats.duration;
+
+        if stats.duration > self.max_pause {
+            self.max_pause = stats.duration;
+        }
+
+        self.marked += stats.trace.marked;
+        self.promoted += stats.trace.promoted;
+        self.evacuated += stats.trace.evacuated;
+    }
+}
+
+/// Statistics accumulated over many garbage collections of a single process.
+///
+/// The statistics are only updated by the thread collecting the process, but
+/// may be read by other processes at any time. To allow this, the statistics
+/// are stored using atomics.
+pub struct AtomicGcStatistics {
+    young_collections: AtomicUsize,
+    mature_collections: AtomicUsize,
+
+    /// The total time spent garbage collecting, in nanoseconds.
+    total_pause: AtomicU64,
+
+    /// The longest time spent on a single collection, in nanoseconds.
+    max_pause: AtomicU64,
+
+    marked: AtomicUsize,
+    promoted: AtomicUsize,
+    evacuated: AtomicUsize,
+}
+
+impl AtomicGcStatistics {
+    pub fn new() -> Self {
+        AtomicGcStatistics {
+            young_collections: AtomicUsize::new(0),
+            mature_collections: AtomicUsize::new(0),
+            total_pause: AtomicU64::new(0),
+            max_pause: AtomicU64::new(0),
+            marked: AtomicUsize::new(0),
+            promoted: AtomicUsize::new(0),
+            evacuated: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds the statistics of a single collection.
+    ///
+    /// This method must only be called by the thread collecting the process.
+    pub fn record(&self, stats: &CollectionStatistics) {
+        let duration = stats.duration.as_nanos() as u64;
+
+        if stats.mature {
+            self.mature_collections.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.young_collections.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.total_pause.fetch_add(duration, Ordering::Relaxed);
+
+        if duration > self.max_pause.load(Ordering::Relaxed) {
+            self.max_pause.store(duration, Ordering::Relaxed);
+        }
+
+        self.marked.fetch_add(stats.trace.marked, Ordering::Relaxed);
+        self.promoted
+            .fetch_add(stats.trace.promoted, Ordering::Relaxed);
+        self.evacuated
+            .fetch_add(stats.trace.evacuated, Ordering::Relaxed);
+    }
+
+    /// Returns a copy of the current statistics.
+    pub fn load(&self) -> GcStatistics {
+        GcStatistics {
+            young_collections: self.young_collections.load(Ordering::Relaxed),
+            mature_collections: self.mature_collections.load(Ordering::Relaxed),
+            total_pause: Duration::from_nanos(
+                self.total_pause.load(Ordering::Relaxed),
+            ),
+            max_pause: Duration::from_nanos(
+                self.max_pause.load(Ordering::Relaxed),
+            ),
+            marked: self.marked.load(Ordering::Relaxed),
+            promoted: self.promoted.load(Ordering::Relaxed),
+            evacuated: self.evacuated.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,4 +236,68 @@ mod tests {
         assert_eq!(stat1.promoted, 2);
         assert_eq!(stat1.evacuated, 2);
     }
+
+    #[test]
+    fn test_gc_statistics_record() {
+        let mut stats = GcStatistics::new();
+        let mut young = CollectionStatistics {
+            duration: Duration::from_millis(2),
+            mature: false,
+            trace: TraceStatistics::new(),
+        };
+
+        young.trace.marked = 3;
+        young.trace.promoted = 2;
+        young.trace.evacuated = 1;
+
+        let mature = CollectionStatistics {
+            duration: Duration::from_millis(5),
+            mature: true,
+            trace: TraceStatistics::new(),
+        };
+
+        stats.record(&young);
+        stats.record(&mature);
+        stats.record(&young);
+
+        assert_eq!(stats.young_collections, 2);
+        assert_eq!(stats.mature_collections, 1);
+        assert_eq!(stats.total_pause, Duration::from_millis(9));
+        assert_eq!(stats.max_pause, Duration::from_millis(5));
+        assert_eq!(stats.marked, 6);
+        assert_eq!(stats.promoted, 4);
+        assert_eq!(stats.evacuated, 2);
+    }
+
+    #[test]
+    fn test_atomic_gc_statistics_record() {
+        let stats = AtomicGcStatistics::new();
+        let mut young = CollectionStatistics {
+            duration: Duration::from_millis(2),
+            mature: false,
+            trace: TraceStatistics::new(),
+        };
+
+        young.trace.marked = 3;
+        young.trace.promoted = 2;
+        young.trace.evacuated = 1;
+
+        let mature = CollectionStatistics {
+            duration: Duration::from_millis(5),
+            mature: true,
+            trace: TraceStatistics::new(),
+        };
+
+        stats.record(&young);
+        stats.record(&mature);
+        stats.record(&young);
+
+        let mut expected = GcStatistics::new();
+
+        expected.record(&young);
+        expected.record(&mature);
+        expected.record(&young);
+
+        assert_eq!(stats.load(), expected);
+    }
 }
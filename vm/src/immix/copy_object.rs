@@ -3,11 +3,13 @@
 //! The CopyObject trait can be implemented by allocators to support copying of
 //! objects into a heap.
 use crate::block::Block;
-use crate::object::{AttributesMap, Object};
+use crate::frozen::FrozenObject;
+use crate::object::Object;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
 use crate::object_value::ObjectValue;
 use crate::runtime_error::RuntimeError;
+use crate::shape::Attributes;
 
 pub trait CopyObject: Sized {
     /// Allocates a copied object.
@@ -79,6 +81,15 @@ pub trait CopyObject: Sized {
             ObjectValue::ByteArray(ref byte_array) => {
                 ObjectValue::ByteArray(byte_array.clone())
             }
+            ObjectValue::FrozenByteArray(ref byte_array) => {
+                ObjectValue::FrozenByteArray(byte_array.clone())
+            }
+            ObjectValue::FrozenArray(ref values) => {
+                ObjectValue::FrozenArray(values.clone())
+            }
+            ObjectValue::FrozenObject(ref object) => {
+                return Ok(FrozenObject::allocate(object, self));
+            }
             ObjectValue::Library(ref val) => ObjectValue::Library(val.clone()),
             ObjectValue::Function(ref val) => {
                 ObjectValue::Function(val.clone())
@@ -114,17 +125,17 @@ pub trait CopyObject: Sized {
             Object::new(value_copy)
         };
 
-        if let Some(map) = to_copy.attributes_map() {
-            let mut map_copy = AttributesMap::default();
+        if let Some(attributes) = to_copy.attributes_ref() {
+            let mut attributes_copy = Attributes::new();
 
-            for (key, val) in map.iter() {
-                let key_copy = self.copy_object(*key)?;
-                let val_copy = self.copy_object(*val)?;
+            for (key, val) in attributes.iter() {
+                let key_copy = self.copy_object(key)?;
+                let val_copy = self.copy_object(val)?;
 
-                map_copy.insert(key_copy, val_copy);
+                attributes_copy.insert(key_copy, val_copy);
             }
 
-            copy.set_attributes_map(map_copy);
+            copy.set_attributes(attributes_copy);
         }
 
         Ok(self.allocate_copy(copy))
@@ -137,6 +148,7 @@ mod tests {
     use crate::binding::Binding;
     use crate::compiled_code::CompiledCode;
     use crate::config::Config;
+    use crate::frozen;
     use crate::immix::global_allocator::GlobalAllocator;
     use crate::immix::local_allocator::LocalAllocator;
     use crate::module::Module;
@@ -203,7 +215,7 @@ mod tests {
         let copy = dummy.copy_object(ptr1).unwrap();
 
         assert!(copy.is_finalizable());
-        assert!(copy.get().attributes_map().is_some());
+        assert!(copy.get().attributes_ref().is_some());
     }
 
     #[test]
@@ -245,6 +257,23 @@ mod tests {
         assert_eq!(copy.string_value().unwrap().as_slice(), "a");
     }
 
+    #[test]
+    fn test_copy_frozen_byte_array() {
+        let mut dummy = DummyAllocator::new();
+        let mut value = object_value::byte_array(vec![1, 2]);
+
+        value.freeze_byte_array().unwrap();
+
+        let pointer = dummy.allocator.allocate_without_prototype(value);
+        let copy = dummy.copy_object(pointer).unwrap();
+
+        assert!(copy.get().value.is_frozen_byte_array());
+        assert_eq!(
+            copy.byte_array_value().unwrap().as_ptr(),
+            pointer.byte_array_value().unwrap().as_ptr()
+        );
+    }
+
     #[test]
     fn test_copy_array() {
         let mut dummy = DummyAllocator::new();
@@ -260,6 +289,49 @@ mod tests {
         assert_eq!(copy.get().value.as_array().unwrap().len(), 2);
     }
 
+    #[test]
+    fn test_copy_frozen_array() {
+        let mut dummy = DummyAllocator::new();
+        let value = dummy.allocator.allocate_empty();
+        let array = dummy
+            .allocator
+            .allocate_without_prototype(object_value::array(vec![value]));
+
+        frozen::freeze(array).unwrap();
+
+        let copy = dummy.copy_object(array).unwrap();
+
+        assert!(copy.get().value.is_frozen());
+        assert_eq!(
+            copy.frozen_array_value().unwrap().as_ptr(),
+            array.frozen_array_value().unwrap().as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_copy_frozen_object() {
+        let mut dummy = DummyAllocator::new();
+        let state = state();
+        let name = state.intern_string("bytes".to_string());
+        let bytes = dummy
+            .allocator
+            .allocate_without_prototype(object_value::byte_array(vec![1]));
+        let object = dummy.allocator.allocate_empty();
+
+        object.get_mut().add_attribute(name, bytes);
+        frozen::freeze(object).unwrap();
+
+        let copy = dummy.copy_object(object).unwrap();
+        let bytes_copy = copy.get().lookup_attribute_in_self(name).unwrap();
+
+        assert!(copy.get().value.is_frozen());
+        assert!(bytes_copy != bytes);
+        assert_eq!(
+            bytes_copy.byte_array_value().unwrap().as_ptr(),
+            bytes.byte_array_value().unwrap().as_ptr()
+        );
+    }
+
     #[test]
     fn test_copy_block() {
         let mut dummy = DummyAllocator::new();
@@ -4,6 +4,7 @@
 //! process heap.
 use crate::config::Config;
 use crate::gc::remembered_set::RememberedSet;
+use crate::immix::block::BLOCK_SIZE;
 use crate::immix::bucket::{Bucket, MATURE};
 use crate::immix::copy_object::CopyObject;
 use crate::immix::generation_config::GenerationConfig;
@@ -14,10 +15,88 @@ use crate::object_pointer::{ObjectPointer, ObjectPointerPointer};
 use crate::object_value;
 use crate::object_value::ObjectValue;
 use crate::vm::state::State;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 
 /// The maximum age of a bucket in the young generation.
 pub const YOUNG_MAX_AGE: i8 = 2;
 
+/// What to do when a process exceeds its heap limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeapLimitPolicy {
+    /// Panic the process.
+    Panic,
+
+    /// Throw an error in the process, allowing it to handle the error.
+    Error,
+}
+
+impl HeapLimitPolicy {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(HeapLimitPolicy::Panic),
+            1 => Some(HeapLimitPolicy::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the number of blocks needed to store the given number of bytes.
+pub fn blocks_for_bytes(bytes: usize) -> usize {
+    let blocks = bytes / BLOCK_SIZE;
+
+    if blocks * BLOCK_SIZE < bytes {
+        blocks + 1
+    } else {
+        blocks
+    }
+}
+
+/// Statistics about the heap of a process.
+///
+/// Other processes may request these statistics while the process is running.
+/// Reading the heap directly would race with the thread running the process, so
+/// the statistics are stored using atomics, updated whenever blocks are
+/// requested or reclaimed.
+pub struct HeapStatistics {
+    young_blocks: AtomicUsize,
+    mature_blocks: AtomicUsize,
+    young_threshold: AtomicU32,
+    mature_threshold: AtomicU32,
+}
+
+impl HeapStatistics {
+    fn new(young_threshold: u32, mature_threshold: u32) -> Self {
+        HeapStatistics {
+            young_blocks: AtomicUsize::new(0),
+            mature_blocks: AtomicUsize::new(0),
+            young_threshold: AtomicU32::new(young_threshold),
+            mature_threshold: AtomicU32::new(mature_threshold),
+        }
+    }
+
+    /// Returns the number of blocks used by the young generation.
+    pub fn young_blocks(&self) -> usize {
+        self.young_blocks.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of blocks used by the mature generation.
+    pub fn mature_blocks(&self) -> usize {
+        self.mature_blocks.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of blocks the young generation can allocate before
+    /// it's garbage collected.
+    pub fn young_threshold(&self) -> u32 {
+        self.young_threshold.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of blocks the mature generation can allocate before
+    /// it's garbage collected.
+    pub fn mature_threshold(&self) -> u32 {
+        self.mature_threshold.load(Ordering::Relaxed)
+    }
+}
+
 /// Structure containing the state of a process-local allocator.
 pub struct LocalAllocator {
     /// The global allocated from which to request blocks of memory and return
@@ -58,6 +137,28 @@ pub struct LocalAllocator {
     /// A boolean indicating if we should evacuate objects in the mature
     /// generation.
     evacuate_mature: bool,
+
+    /// A boolean indicating if a collection of the young generation was
+    /// requested explicitly.
+    young_collection_requested: bool,
+
+    /// A boolean indicating if a collection of the mature generation was
+    /// requested explicitly.
+    mature_collection_requested: bool,
+
+    /// What to do when the heap limit is exceeded.
+    pub heap_limit_policy: HeapLimitPolicy,
+
+    /// The maximum number of blocks the heap can use, or 0 if there is no
+    /// limit.
+    pub heap_limit: usize,
+
+    /// A boolean indicating if the heap exceeded its limit when requesting a
+    /// new block.
+    heap_limit_exceeded: bool,
+
+    /// Statistics about the heap that other processes can read.
+    pub statistics: HeapStatistics,
 }
 
 impl LocalAllocator {
@@ -81,6 +182,15 @@ impl LocalAllocator {
             remembered_set: RememberedSet::new(),
             evacuate_young: false,
             evacuate_mature: false,
+            young_collection_requested: false,
+            mature_collection_requested: false,
+            heap_limit_policy: HeapLimitPolicy::Panic,
+            heap_limit: blocks_for_bytes(config.heap_limit),
+            heap_limit_exceeded: false,
+            statistics: HeapStatistics::new(
+                config.young_threshold,
+                config.mature_threshold,
+            ),
         }
     }
 
@@ -97,11 +207,77 @@ impl LocalAllocator {
     }
 
     pub fn should_collect_young(&self) -> bool {
-        self.young_config.allocation_threshold_exceeded()
+        self.young_collection_requested
+            || self.should_collect_mature()
+            || self.young_config.allocation_threshold_exceeded()
     }
 
     pub fn should_collect_mature(&self) -> bool {
-        self.mature_config.allocation_threshold_exceeded()
+        // When exceeding the heap limit we perform a full collection, as this
+        // reclaims as much memory as possible.
+        self.mature_collection_requested
+            || self.heap_limit_exceeded
+            || self.mature_config.allocation_threshold_exceeded()
+    }
+
+    /// Requests a collection, regardless of the number of allocated blocks.
+    ///
+    /// If `mature` is true, the mature generation is collected as well.
+    pub fn request_collection(&mut self, mature: bool) {
+        self.young_collection_requested = true;
+
+        if mature {
+            self.mature_collection_requested = true;
+        }
+    }
+
+    /// Returns the number of blocks used by the young generation.
+    pub fn young_blocks(&self) -> usize {
+        self.young_generation
+            .iter()
+            .map(|bucket| bucket.blocks.len())
+            .sum()
+    }
+
+    /// Returns the number of blocks used by the mature generation.
+    pub fn mature_blocks(&self) -> usize {
+        self.mature_generation.blocks.len()
+    }
+
+    /// Limits the heap to the given number of bytes, or removes the limit if
+    /// the number of bytes is zero.
+    pub fn set_heap_limit(&mut self, bytes: usize, policy: HeapLimitPolicy) {
+        self.heap_limit = blocks_for_bytes(bytes);
+        self.heap_limit_policy = policy;
+        self.heap_limit_exceeded = self.heap_exceeds_limit();
+    }
+
+    /// Returns true if the heap exceeded its limit since the last collection.
+    pub fn heap_limit_exceeded(&self) -> bool {
+        self.heap_limit_exceeded
+    }
+
+    /// Returns true if the heap still exceeds its limit after a collection.
+    ///
+    /// The heap limit is only checked again when requesting a new block. This
+    /// way a process that keeps exceeding its limit doesn't trigger a full
+    /// collection every time it reaches a safepoint.
+    pub fn heap_limit_exceeded_after_collection(&mut self) -> bool {
+        self.heap_limit_exceeded = false;
+        self.heap_exceeds_limit()
+    }
+
+    /// Returns true if the heap uses more blocks than allowed.
+    fn heap_exceeds_limit(&self) -> bool {
+        self.heap_limit > 0
+            && self.young_blocks() + self.mature_blocks() > self.heap_limit
+    }
+
+    /// Checks if the heap exceeds its limit after requesting a new block.
+    fn check_heap_limit(&mut self) {
+        if self.heap_exceeds_limit() {
+            self.heap_limit_exceeded = true;
+        }
     }
 
     /// Prepares for a garbage collection.
@@ -110,6 +286,12 @@ impl LocalAllocator {
     pub fn prepare_for_collection(&mut self, mature: bool) -> bool {
         let mut move_objects = self.evacuate_young;
 
+        self.young_collection_requested = false;
+
+        if mature {
+            self.mature_collection_requested = false;
+        }
+
         for bucket in &mut self.young_generation {
             bucket.prepare_for_collection(
                 &mut self.young_histograms,
@@ -158,6 +340,8 @@ impl LocalAllocator {
         self.evacuate_young = self
             .young_config
             .update_after_collection(&state.config, blocks);
+
+        self.update_young_statistics();
     }
 
     fn reclaim_mature_blocks(&mut self, state: &State) {
@@ -170,6 +354,28 @@ impl LocalAllocator {
         self.evacuate_mature = self
             .mature_config
             .update_after_collection(&state.config, blocks);
+
+        self.update_mature_statistics();
+    }
+
+    fn update_young_statistics(&self) {
+        self.statistics
+            .young_blocks
+            .store(self.young_blocks(), Ordering::Relaxed);
+
+        self.statistics
+            .young_threshold
+            .store(self.young_config.threshold, Ordering::Relaxed);
+    }
+
+    fn update_mature_statistics(&self) {
+        self.statistics
+            .mature_blocks
+            .store(self.mature_blocks(), Ordering::Relaxed);
+
+        self.statistics
+            .mature_threshold
+            .store(self.mature_config.threshold, Ordering::Relaxed);
     }
 
     pub fn allocate_with_prototype(
@@ -203,6 +409,8 @@ impl LocalAllocator {
 
         if new_block {
             self.young_config.increment_allocations();
+            self.update_young_statistics();
+            self.check_heap_limit();
         }
 
         pointer
@@ -215,6 +423,8 @@ impl LocalAllocator {
 
         if new_block {
             self.mature_config.increment_allocations();
+            self.update_mature_statistics();
+            self.check_heap_limit();
         }
 
         pointer
@@ -331,6 +541,131 @@ mod tests {
         assert!(alloc.prepare_for_collection(true));
     }
 
+    #[test]
+    fn test_request_collection() {
+        let (_, mut alloc) = local_allocator();
+
+        assert_eq!(alloc.should_collect_young(), false);
+
+        alloc.request_collection(false);
+
+        assert!(alloc.should_collect_young());
+        assert_eq!(alloc.should_collect_mature(), false);
+
+        alloc.prepare_for_collection(false);
+
+        assert_eq!(alloc.should_collect_young(), false);
+    }
+
+    #[test]
+    fn test_request_mature_collection() {
+        let (_, mut alloc) = local_allocator();
+
+        alloc.request_collection(true);
+
+        assert!(alloc.should_collect_young());
+        assert!(alloc.should_collect_mature());
+
+        alloc.prepare_for_collection(true);
+
+        assert_eq!(alloc.should_collect_young(), false);
+        assert_eq!(alloc.should_collect_mature(), false);
+    }
+
+    #[test]
+    fn test_heap_limit_exceeded() {
+        let (_, mut alloc) = local_allocator();
+
+        alloc.allocate_empty();
+        alloc.allocate_mature(Object::new(object_value::none()));
+
+        assert_eq!(alloc.heap_limit_exceeded(), false);
+
+        alloc.set_heap_limit(BLOCK_SIZE, HeapLimitPolicy::Error);
+
+        assert!(alloc.heap_limit_exceeded());
+        assert_eq!(alloc.heap_limit_policy, HeapLimitPolicy::Error);
+
+        alloc.set_heap_limit(BLOCK_SIZE * 2, HeapLimitPolicy::Panic);
+
+        assert_eq!(alloc.heap_limit_exceeded(), false);
+    }
+
+    #[test]
+    fn test_heap_limit_exceeded_when_requesting_blocks() {
+        let (_, mut alloc) = local_allocator();
+
+        alloc.set_heap_limit(BLOCK_SIZE, HeapLimitPolicy::Panic);
+        alloc.allocate_empty();
+
+        assert_eq!(alloc.heap_limit_exceeded(), false);
+        assert_eq!(alloc.should_collect_mature(), false);
+
+        alloc.allocate_mature(Object::new(object_value::none()));
+
+        assert!(alloc.heap_limit_exceeded());
+        assert!(alloc.should_collect_mature());
+    }
+
+    #[test]
+    fn test_heap_limit_exceeded_after_collection() {
+        let (_, mut alloc) = local_allocator();
+
+        alloc.set_heap_limit(BLOCK_SIZE, HeapLimitPolicy::Panic);
+        alloc.allocate_empty();
+        alloc.allocate_mature(Object::new(object_value::none()));
+
+        assert!(alloc.heap_limit_exceeded_after_collection());
+
+        // The limit is only checked again when the heap grows.
+        assert_eq!(alloc.heap_limit_exceeded(), false);
+        assert_eq!(alloc.should_collect_mature(), false);
+
+        alloc.set_heap_limit(BLOCK_SIZE * 2, HeapLimitPolicy::Panic);
+
+        assert_eq!(alloc.heap_limit_exceeded_after_collection(), false);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let (state, mut alloc) = local_allocator();
+
+        alloc.allocate_empty();
+        alloc.allocate_mature(Object::new(object_value::none()));
+
+        assert_eq!(alloc.statistics.young_blocks(), 1);
+        assert_eq!(alloc.statistics.mature_blocks(), 1);
+        assert_eq!(
+            alloc.statistics.young_threshold(),
+            state.config.young_threshold
+        );
+        assert_eq!(
+            alloc.statistics.mature_threshold(),
+            state.config.mature_threshold
+        );
+
+        alloc.prepare_for_collection(true);
+        alloc.reclaim_blocks(&state, true);
+
+        assert_eq!(alloc.statistics.young_blocks(), 0);
+        assert_eq!(alloc.statistics.mature_blocks(), 0);
+    }
+
+    #[test]
+    fn test_blocks_for_bytes() {
+        assert_eq!(blocks_for_bytes(0), 0);
+        assert_eq!(blocks_for_bytes(1), 1);
+        assert_eq!(blocks_for_bytes(BLOCK_SIZE), 1);
+        assert_eq!(blocks_for_bytes(BLOCK_SIZE + 1), 2);
+    }
+
+    #[test]
+    fn test_heap_limit_policy_from_u8() {
+        assert_eq!(HeapLimitPolicy::from_u8(0), Some(HeapLimitPolicy::Panic));
+        assert_eq!(HeapLimitPolicy::from_u8(1), Some(HeapLimitPolicy::Error));
+        assert_eq!(HeapLimitPolicy::from_u8(2), None);
+    }
+
     #[test]
     fn test_prepare_for_collection_with_promotion() {
         let (_, mut alloc) = local_allocator();
@@ -534,6 +869,6 @@ mod tests {
     fn test_type_size() {
         // This test is put in place to ensure that the type size doesn't change
         // unexpectedly.
-        assert_eq!(mem::size_of::<LocalAllocator>(), 280);
+        assert_eq!(mem::size_of::<LocalAllocator>(), 312);
     }
 }
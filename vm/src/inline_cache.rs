@@ -0,0 +1,300 @@
+//! Inline caches for looking up attributes.
+//!
+//! Looking up an attribute requires walking the prototype chain of an object,
+//! performing a hash map lookup for every object in the chain. Since method
+//! calls are attribute lookups, this is expensive for code that calls many
+//! methods.
+//!
+//! To speed this up, every `GetAttribute` and `GetAttributeInSelf` instruction
+//! (and every superinstruction that includes a `GetAttribute`) has its own
+//! inline cache. Such a cache maps the object a lookup starts at
+//! (and the name of the attribute) to the result of the lookup. For
+//! `GetAttribute` this object is the prototype of the receiver, while for
+//! `GetAttributeInSelf` this is the receiver itself.
+//!
+//! Only lookups that exclusively involve permanent objects are cached, as
+//! permanent objects are never moved or released. Mutating a permanent object
+//! invalidates all inline caches, by incrementing a global epoch stored in the
+//! VM state. Entries stored in an older epoch are ignored.
+//!
+//! Inline caches are polymorphic: each cache has a small number of entries,
+//! and the entry to use is determined by the object the lookup starts at.
+//!
+//! Caches are shared by all threads running the same code. Every entry is
+//! protected using a sequence lock, allowing threads to read an entry without
+//! having to acquire a lock.
+use crate::object_pointer::{ObjectPointer, RawObjectPointer};
+use crate::vm::instruction::{Instruction, Opcode};
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+/// The number of entries in every inline cache.
+const ENTRIES: usize = 4;
+
+/// The argument of an instruction used for storing the index of its inline
+/// cache.
+pub const CACHE_ARGUMENT: usize = 3;
+
+/// The cache index of instructions that don't have an inline cache.
+pub const NO_CACHE: u16 = u16::MAX;
+
+/// A single cached attribute lookup.
+struct Entry {
+    /// The sequence number of this entry. This number is odd while the entry
+    /// is being updated.
+    sequence: AtomicUsize,
+
+    /// The object the lookup started at.
+    source: AtomicUsize,
+
+    /// The name of the attribute that was looked up.
+    name: AtomicUsize,
+
+    /// The epoch in which this entry was stored.
+    epoch: AtomicUsize,
+
+    /// The attribute that was found, or NULL if the attribute doesn't exist.
+    value: AtomicUsize,
+}
+
+impl Entry {
+    fn new() -> Self {
+        Entry {
+            sequence: AtomicUsize::new(0),
+            source: AtomicUsize::new(0),
+            name: AtomicUsize::new(0),
+            epoch: AtomicUsize::new(0),
+            value: AtomicUsize::new(0),
+        }
+    }
+
+    fn lookup(
+        &self,
+        source: usize,
+        name: usize,
+        epoch: usize,
+    ) -> Option<ObjectPointer> {
+        let start = self.sequence.load(Ordering::Acquire);
+
+        if start % 2 == 1 {
+            return None;
+        }
+
+        let found = self.source.load(Ordering::Relaxed) == source
+            && self.name.load(Ordering::Relaxed) == name
+            && self.epoch.load(Ordering::Relaxed) == epoch;
+
+        let value = self.value.load(Ordering::Relaxed);
+
+        fence(Ordering::Acquire);
+
+        if found && self.sequence.load(Ordering::Relaxed) == start {
+            Some(ObjectPointer::new(value as RawObjectPointer))
+        } else {
+            None
+        }
+    }
+
+    fn store(&self, source: usize, name: usize, epoch: usize, value: usize) {
+        let start = self.sequence.load(Ordering::Relaxed);
+
+        // If another thread is already updating the entry we just don't cache
+        // the result, instead of waiting for the other thread to finish.
+        if start % 2 == 1
+            || self
+                .sequence
+                .compare_exchange(
+                    start,
+                    start.wrapping_add(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            return;
+        }
+
+        fence(Ordering::Release);
+
+        self.source.store(source, Ordering::Relaxed);
+        self.name.store(name, Ordering::Relaxed);
+        self.epoch.store(epoch, Ordering::Relaxed);
+        self.value.store(value, Ordering::Relaxed);
+        self.sequence
+            .store(start.wrapping_add(2), Ordering::Release);
+    }
+}
+
+/// The inline cache of a single instruction.
+pub struct InlineCache {
+    entries: [Entry; ENTRIES],
+}
+
+impl InlineCache {
+    pub fn new() -> Self {
+        InlineCache {
+            entries: [Entry::new(), Entry::new(), Entry::new(), Entry::new()],
+        }
+    }
+
+    /// Looks up the cached result of looking up an attribute.
+    ///
+    /// If the cached lookup didn't find the attribute, a NULL pointer is
+    /// returned.
+    pub fn lookup(
+        &self,
+        source: ObjectPointer,
+        name: ObjectPointer,
+        epoch: usize,
+    ) -> Option<ObjectPointer> {
+        let source = address(source);
+
+        self.entry(source).lookup(source, address(name), epoch)
+    }
+
+    /// Caches the result of looking up an attribute.
+    ///
+    /// The `value` argument should be a NULL pointer if the attribute doesn't
+    /// exist.
+    pub fn store(
+        &self,
+        source: ObjectPointer,
+        name: ObjectPointer,
+        epoch: usize,
+        value: ObjectPointer,
+    ) {
+        let source = address(source);
+
+        self.entry(source)
+            .store(source, address(name), epoch, address(value));
+    }
+
+    fn entry(&self, source: usize) -> &Entry {
+        // Objects are aligned to at least 16 bytes, so the lower bits are
+        // ignored when picking an entry.
+        &self.entries[(source >> 4) % ENTRIES]
+    }
+}
+
+/// Returns the inline caches for a list of instructions.
+///
+/// Instructions that support inline caching have the index of their cache
+/// stored in their `CACHE_ARGUMENT` argument. This argument is set to
+/// `NO_CACHE` if an instruction doesn't have a cache, which only happens when
+/// the number of caches exceeds the number of available indexes.
+pub fn inline_caches(instructions: &mut [Instruction]) -> Vec<InlineCache> {
+    let mut caches = Vec::new();
+
+    for instruction in instructions {
+        match instruction.opcode {
+            Opcode::GetAttribute
+            | Opcode::GetAttributeInSelf
+            | Opcode::GetLocalGetAttribute
+            | Opcode::GetAttributeRunBlockWithReceiver => {}
+            _ => continue,
+        }
+
+        if caches.len() < NO_CACHE as usize {
+            instruction.set_arg(CACHE_ARGUMENT, caches.len() as u16);
+            caches.push(InlineCache::new());
+        } else {
+            instruction.set_arg(CACHE_ARGUMENT, NO_CACHE);
+        }
+    }
+
+    caches
+}
+
+fn address(pointer: ObjectPointer) -> usize {
+    pointer.raw.raw as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::test::setup;
+
+    #[test]
+    fn test_lookup_empty_cache() {
+        let (machine, _block, _process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+
+        assert!(cache
+            .lookup(state.integer_prototype, state.nil_object, 0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_store_and_lookup() {
+        let (machine, _block, _process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+
+        cache.store(state.integer_prototype, name, 1, state.true_object);
+
+        assert!(
+            cache.lookup(state.integer_prototype, name, 1).unwrap()
+                == state.true_object
+        );
+        assert!(cache.lookup(state.integer_prototype, name, 2).is_none());
+        assert!(cache
+            .lookup(state.integer_prototype, state.nil_object, 1)
+            .is_none());
+        assert!(cache.lookup(state.float_prototype, name, 1).is_none());
+    }
+
+    #[test]
+    fn test_store_missing_attribute() {
+        let (machine, _block, _process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+
+        cache.store(state.integer_prototype, name, 0, ObjectPointer::null());
+
+        assert!(cache
+            .lookup(state.integer_prototype, name, 0)
+            .unwrap()
+            .is_null());
+    }
+
+    #[test]
+    fn test_store_tagged_integer() {
+        let (machine, _block, _process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+
+        cache.store(
+            state.integer_prototype,
+            name,
+            0,
+            ObjectPointer::integer(5),
+        );
+
+        assert_eq!(
+            cache
+                .lookup(state.integer_prototype, name, 0)
+                .unwrap()
+                .integer_value(),
+            Ok(5)
+        );
+    }
+
+    #[test]
+    fn test_inline_caches() {
+        let mut instructions = vec![
+            Instruction::new(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0], 1),
+            Instruction::new(Opcode::Return, [0, 0, 0, 0, 0, 0], 1),
+            Instruction::new(Opcode::GetAttributeInSelf, [0, 1, 2, 0, 0, 0], 1),
+        ];
+
+        let caches = inline_caches(&mut instructions);
+
+        assert_eq!(caches.len(), 2);
+        assert_eq!(instructions[0].arg(CACHE_ARGUMENT), 0);
+        assert_eq!(instructions[1].arg(CACHE_ARGUMENT), 0);
+        assert_eq!(instructions[2].arg(CACHE_ARGUMENT), 1);
+    }
+}
@@ -0,0 +1,203 @@
+//! Performing blocking file operations without blocking process threads.
+//!
+//! Regular files can't be polled for readiness the way sockets can, so file
+//! operations can't use the network poller. Instead, these operations are
+//! performed by a pool of dedicated I/O threads.
+//!
+//! When a process performs a file operation, the operation is sent to the pool
+//! and the process is suspended. Once a thread finishes the operation, it
+//! stores the result in the process and reschedules it. The process then
+//! retries the instruction that started the operation, which picks up the
+//! stored result instead of starting a new operation.
+use crate::process::RcProcess;
+use crate::scheduler::process_scheduler::ProcessScheduler;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::fs;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The output of a file operation.
+#[derive(Debug)]
+pub enum IoOutput {
+    /// The operation produced an integer, such as the number of bytes written.
+    Integer(u64),
+
+    /// The operation produced the bytes that were read.
+    Bytes(Vec<u8>),
+
+    /// The operation opened a file.
+    File(fs::File),
+
+    /// The operation didn't produce a value.
+    Nil,
+}
+
+impl IoOutput {
+    pub fn into_integer(self) -> Result<u64, String> {
+        match self {
+            IoOutput::Integer(value) => Ok(value),
+            output => Err(unexpected_output("an Integer", &output)),
+        }
+    }
+
+    pub fn into_bytes(self) -> Result<Vec<u8>, String> {
+        match self {
+            IoOutput::Bytes(value) => Ok(value),
+            output => Err(unexpected_output("bytes", &output)),
+        }
+    }
+
+    pub fn into_file(self) -> Result<fs::File, String> {
+        match self {
+            IoOutput::File(value) => Ok(value),
+            output => Err(unexpected_output("a File", &output)),
+        }
+    }
+}
+
+fn unexpected_output(expected: &str, output: &IoOutput) -> String {
+    format!(
+        "The file operation produced {:?} instead of {}",
+        output, expected
+    )
+}
+
+/// The result of a file operation.
+pub type IoResult = io::Result<IoOutput>;
+
+type Operation = Box<dyn FnOnce() -> IoResult + Send>;
+
+enum Message {
+    Perform(RcProcess, Operation),
+    Terminate,
+}
+
+/// A pool of threads for performing file operations.
+pub struct IoPool {
+    /// The number of threads in this pool.
+    threads: usize,
+
+    /// The sending half of the channel used for scheduling operations.
+    sender: Sender<Message>,
+
+    /// The receiving half of the channel used for scheduling operations.
+    receiver: Receiver<Message>,
+
+    /// The number of operations that have yet to finish.
+    pending: AtomicUsize,
+}
+
+impl IoPool {
+    pub fn new(threads: usize) -> Self {
+        let (sender, receiver) = unbounded();
+
+        IoPool {
+            threads,
+            sender,
+            receiver,
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Schedules an operation to be performed on behalf of a process.
+    ///
+    /// Once scheduled, the process may be rescheduled at any time. This means
+    /// the caller must not use the process after calling this method.
+    pub fn schedule<F>(&self, process: &RcProcess, operation: F)
+    where
+        F: FnOnce() -> IoResult + Send + 'static,
+    {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+
+        self.sender
+            .send(Message::Perform(process.clone(), Box::new(operation)))
+            .expect("Failed to schedule a file operation");
+    }
+
+    /// Returns the number of operations that have yet to finish.
+    pub fn pending_operations(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    /// Terminates all threads of the pool.
+    pub fn terminate(&self) {
+        for _ in 0..self.threads {
+            let _ = self.sender.send(Message::Terminate);
+        }
+    }
+
+    /// Performs operations until the pool is terminated.
+    pub fn run(&self, scheduler: &ProcessScheduler) {
+        while let Ok(Message::Perform(process, operation)) =
+            self.receiver.recv()
+        {
+            process.set_io_result(operation());
+
+            // The counter is decremented after rescheduling the process, so
+            // the deadlock detector never observes a state in which the
+            // process is neither waiting nor scheduled.
+            scheduler.schedule(process);
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::test::setup;
+
+    #[test]
+    fn test_io_output_conversions() {
+        assert_eq!(IoOutput::Integer(42).into_integer(), Ok(42));
+        assert_eq!(IoOutput::Bytes(vec![1]).into_bytes(), Ok(vec![1]));
+        assert!(IoOutput::Nil.into_integer().is_err());
+        assert!(IoOutput::Integer(42).into_bytes().is_err());
+        assert!(IoOutput::Nil.into_file().is_err());
+    }
+
+    #[test]
+    fn test_schedule() {
+        let (_machine, _block, process) = setup();
+        let pool = IoPool::new(1);
+
+        pool.schedule(&process, || Ok(IoOutput::Nil));
+
+        assert_eq!(pool.pending_operations(), 1);
+    }
+
+    #[test]
+    fn test_run() {
+        let (machine, _block, process) = setup();
+        let scheduler = &machine.state.scheduler;
+        let pool = IoPool::new(1);
+
+        pool.schedule(&process, || Ok(IoOutput::Integer(42)));
+        pool.terminate();
+        pool.run(scheduler);
+
+        assert_eq!(pool.pending_operations(), 0);
+        assert!(scheduler.primary_pool.state.pop_global().is_some());
+
+        match process.take_io_result() {
+            Some(Ok(IoOutput::Integer(42))) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_run_after_terminating() {
+        let (machine, _block, process) = setup();
+        let pool = IoPool::new(2);
+
+        pool.terminate();
+        pool.schedule(&process, || Ok(IoOutput::Nil));
+        pool.run(&machine.state.scheduler);
+
+        assert_eq!(pool.pending_operations(), 1);
+    }
+}
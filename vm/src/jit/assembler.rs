@@ -0,0 +1,397 @@
+//! A minimal x86-64 assembler.
+//!
+//! This assembler only supports the instructions needed by the JIT compiler,
+//! operating on 64 bits registers. Jumps use labels, which are resolved when
+//! finishing the code.
+
+/// A general purpose x86-64 register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R9 = 9,
+    R10 = 10,
+    R11 = 11,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+impl Reg {
+    fn low(self) -> u8 {
+        self as u8 & 0b111
+    }
+
+    fn extended(self) -> bool {
+        self as u8 >= 8
+    }
+}
+
+/// The condition of a conditional jump or move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cond {
+    Overflow = 0x0,
+    Equal = 0x4,
+    NotEqual = 0x5,
+    Less = 0xC,
+    GreaterOrEqual = 0xD,
+    LessOrEqual = 0xE,
+    Greater = 0xF,
+}
+
+impl Cond {
+    /// Returns the condition that is true when this condition is false.
+    pub fn negate(self) -> Cond {
+        match self {
+            Cond::Overflow => Cond::Overflow,
+            Cond::Equal => Cond::NotEqual,
+            Cond::NotEqual => Cond::Equal,
+            Cond::Less => Cond::GreaterOrEqual,
+            Cond::GreaterOrEqual => Cond::Less,
+            Cond::LessOrEqual => Cond::Greater,
+            Cond::Greater => Cond::LessOrEqual,
+        }
+    }
+}
+
+/// A position in the code that can be jumped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Label(usize);
+
+/// A buffer of x86-64 machine code.
+pub struct Assembler {
+    code: Vec<u8>,
+
+    /// The offsets of all labels, or None for labels that aren't bound yet.
+    labels: Vec<Option<usize>>,
+
+    /// The offsets of all 32 bits jump displacements to patch, and the labels
+    /// they refer to.
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Assembler {
+            code: Vec::new(),
+            labels: Vec::new(),
+            fixups: Vec::new(),
+        }
+    }
+
+    /// Creates a new label that isn't bound to a position yet.
+    pub fn label(&mut self) -> Label {
+        self.labels.push(None);
+
+        Label(self.labels.len() - 1)
+    }
+
+    /// Binds a label to the current position.
+    pub fn bind(&mut self, label: Label) {
+        self.labels[label.0] = Some(self.code.len());
+    }
+
+    /// Returns the machine code, with all jumps resolved.
+    ///
+    /// An error is returned if a label used by a jump was never bound.
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        for (offset, label) in &self.fixups {
+            let target = self.labels[label.0].ok_or_else(|| {
+                format!("The label {} is used but never bound", label.0)
+            })?;
+
+            // Displacements are relative to the end of the jump instruction,
+            // which ends directly after the displacement.
+            let displacement = target as i64 - (*offset as i64 + 4);
+
+            self.code[*offset..*offset + 4]
+                .copy_from_slice(&(displacement as i32).to_le_bytes());
+        }
+
+        Ok(self.code)
+    }
+
+    pub fn push(&mut self, reg: Reg) {
+        self.rex_optional(false, Reg::Rax, reg);
+        self.code.push(0x50 + reg.low());
+    }
+
+    pub fn pop(&mut self, reg: Reg) {
+        self.rex_optional(false, Reg::Rax, reg);
+        self.code.push(0x58 + reg.low());
+    }
+
+    pub fn ret(&mut self) {
+        self.code.push(0xC3);
+    }
+
+    /// `mov dst, src`
+    pub fn mov(&mut self, dst: Reg, src: Reg) {
+        self.register_op(0x89, dst, src);
+    }
+
+    /// `mov dst, imm64`
+    pub fn mov_imm(&mut self, dst: Reg, value: u64) {
+        self.rex(Reg::Rax, dst);
+        self.code.push(0xB8 + dst.low());
+        self.code.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// `mov dst, [base + offset]`
+    pub fn load(&mut self, dst: Reg, base: Reg, offset: i32) {
+        self.rex(dst, base);
+        self.code.push(0x8B);
+        self.memory_operand(dst, base, offset);
+    }
+
+    /// `mov [base + offset], src`
+    pub fn store(&mut self, base: Reg, offset: i32, src: Reg) {
+        self.rex(src, base);
+        self.code.push(0x89);
+        self.memory_operand(src, base, offset);
+    }
+
+    /// `add dst, src`
+    pub fn add(&mut self, dst: Reg, src: Reg) {
+        self.register_op(0x01, dst, src);
+    }
+
+    /// `sub dst, src`
+    pub fn sub(&mut self, dst: Reg, src: Reg) {
+        self.register_op(0x29, dst, src);
+    }
+
+    /// `and dst, src`
+    pub fn and(&mut self, dst: Reg, src: Reg) {
+        self.register_op(0x21, dst, src);
+    }
+
+    /// `cmp left, right`
+    pub fn cmp(&mut self, left: Reg, right: Reg) {
+        self.register_op(0x39, left, right);
+    }
+
+    /// `add dst, imm8`
+    pub fn add_imm(&mut self, dst: Reg, value: i8) {
+        self.immediate_op(0, dst, value);
+    }
+
+    /// `sub dst, imm8`
+    pub fn sub_imm(&mut self, dst: Reg, value: i8) {
+        self.immediate_op(5, dst, value);
+    }
+
+    /// `test reg, imm32`
+    pub fn test_imm(&mut self, reg: Reg, value: u32) {
+        self.rex(Reg::Rax, reg);
+        self.code.push(0xF7);
+        self.code.push(0xC0 | reg.low());
+        self.code.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// `cmovcc dst, src`
+    pub fn cmov(&mut self, cond: Cond, dst: Reg, src: Reg) {
+        self.rex(dst, src);
+        self.code.push(0x0F);
+        self.code.push(0x40 + cond as u8);
+        self.code.push(0xC0 | (dst.low() << 3) | src.low());
+    }
+
+    /// `call reg`
+    pub fn call(&mut self, reg: Reg) {
+        self.rex_optional(false, Reg::Rax, reg);
+        self.code.push(0xFF);
+        self.code.push(0xD0 | reg.low());
+    }
+
+    /// `jmp label`
+    pub fn jmp(&mut self, label: Label) {
+        self.code.push(0xE9);
+        self.displacement(label);
+    }
+
+    /// `jcc label`
+    pub fn jcc(&mut self, cond: Cond, label: Label) {
+        self.code.push(0x0F);
+        self.code.push(0x80 + cond as u8);
+        self.displacement(label);
+    }
+
+    fn displacement(&mut self, label: Label) {
+        self.fixups.push((self.code.len(), label));
+        self.code.extend_from_slice(&[0, 0, 0, 0]);
+    }
+
+    /// Encodes an instruction that operates on two registers, with the
+    /// destination stored in the r/m field.
+    fn register_op(&mut self, opcode: u8, dst: Reg, src: Reg) {
+        self.rex(src, dst);
+        self.code.push(opcode);
+        self.code.push(0xC0 | (src.low() << 3) | dst.low());
+    }
+
+    fn immediate_op(&mut self, extension: u8, dst: Reg, value: i8) {
+        self.rex(Reg::Rax, dst);
+        self.code.push(0x83);
+        self.code.push(0xC0 | (extension << 3) | dst.low());
+        self.code.push(value as u8);
+    }
+
+    /// Encodes a `[base + offset]` operand, using a 32 bits offset.
+    fn memory_operand(&mut self, reg: Reg, base: Reg, offset: i32) {
+        self.code.push(0x80 | (reg.low() << 3) | base.low());
+
+        // RSP and R12 can only be used as a base using a SIB byte.
+        if base.low() == Reg::Rsp.low() {
+            self.code.push(0x24);
+        }
+
+        self.code.extend_from_slice(&offset.to_le_bytes());
+    }
+
+    /// Emits a REX prefix for a 64 bits operation.
+    fn rex(&mut self, reg: Reg, rm: Reg) {
+        self.rex_optional(true, reg, rm);
+    }
+
+    /// Emits a REX prefix, if one is needed.
+    fn rex_optional(&mut self, wide: bool, reg: Reg, rm: Reg) {
+        let mut rex = 0x40;
+
+        if wide {
+            rex |= 0x08;
+        }
+
+        if reg.extended() {
+            rex |= 0x04;
+        }
+
+        if rm.extended() {
+            rex |= 0x01;
+        }
+
+        if rex != 0x40 {
+            self.code.push(rex);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assemble<F: FnOnce(&mut Assembler)>(callback: F) -> Vec<u8> {
+        let mut asm = Assembler::new();
+
+        callback(&mut asm);
+        asm.finish().unwrap()
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        assert_eq!(assemble(|a| a.push(Reg::Rbx)), vec![0x53]);
+        assert_eq!(assemble(|a| a.push(Reg::R12)), vec![0x41, 0x54]);
+        assert_eq!(assemble(|a| a.pop(Reg::R13)), vec![0x41, 0x5D]);
+    }
+
+    #[test]
+    fn test_mov() {
+        assert_eq!(
+            assemble(|a| a.mov(Reg::Rbx, Reg::Rdi)),
+            vec![0x48, 0x89, 0xFB]
+        );
+        assert_eq!(
+            assemble(|a| a.mov(Reg::R12, Reg::Rsi)),
+            vec![0x49, 0x89, 0xF4]
+        );
+    }
+
+    #[test]
+    fn test_mov_imm() {
+        assert_eq!(
+            assemble(|a| a.mov_imm(Reg::Rcx, 1)),
+            vec![0x48, 0xB9, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_load_and_store() {
+        assert_eq!(
+            assemble(|a| a.load(Reg::Rax, Reg::Rbx, 8)),
+            vec![0x48, 0x8B, 0x83, 8, 0, 0, 0]
+        );
+        assert_eq!(
+            assemble(|a| a.load(Reg::Rax, Reg::R12, 8)),
+            vec![0x49, 0x8B, 0x84, 0x24, 8, 0, 0, 0]
+        );
+        assert_eq!(
+            assemble(|a| a.store(Reg::Rbx, 16, Reg::Rcx)),
+            vec![0x48, 0x89, 0x8B, 16, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(
+            assemble(|a| a.add(Reg::Rax, Reg::Rcx)),
+            vec![0x48, 0x01, 0xC8]
+        );
+        assert_eq!(
+            assemble(|a| a.sub(Reg::Rax, Reg::Rcx)),
+            vec![0x48, 0x29, 0xC8]
+        );
+        assert_eq!(
+            assemble(|a| a.sub_imm(Reg::Rax, 1)),
+            vec![0x48, 0x83, 0xE8, 1]
+        );
+        assert_eq!(
+            assemble(|a| a.cmp(Reg::Rax, Reg::Rcx)),
+            vec![0x48, 0x39, 0xC8]
+        );
+    }
+
+    #[test]
+    fn test_cmov() {
+        assert_eq!(
+            assemble(|a| a.cmov(Cond::Less, Reg::Rax, Reg::Rcx)),
+            vec![0x48, 0x0F, 0x4C, 0xC1]
+        );
+    }
+
+    #[test]
+    fn test_jumps() {
+        let code = assemble(|a| {
+            let label = a.label();
+
+            a.bind(label);
+            a.jmp(label);
+            a.jcc(Cond::Equal, label);
+        });
+
+        assert_eq!(
+            code,
+            vec![
+                0xE9, 0xFB, 0xFF, 0xFF, 0xFF, 0x0F, 0x84, 0xF5, 0xFF, 0xFF,
+                0xFF
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unbound_label() {
+        let mut asm = Assembler::new();
+        let label = asm.label();
+
+        asm.jmp(label);
+
+        assert!(asm.finish().is_err());
+    }
+}
@@ -0,0 +1,720 @@
+//! Compiling instructions to x86-64 machine code.
+//!
+//! Every instruction is compiled to machine code that either performs the
+//! instruction, or returns to the interpreter. Instructions operating on
+//! registers, local variables, tagged integers and jumps are compiled to
+//! native code directly. Looking up attributes is done by calling back into
+//! the functions used by the interpreter. All other instructions return to the
+//! interpreter, which then continues at that instruction.
+//!
+//! Integer instructions only support tagged integers. If an operand isn't a
+//! tagged integer or the result overflows, the machine code returns to the
+//! interpreter before modifying any registers, leaving the interpreter to
+//! perform the instruction instead.
+//!
+//! The generated code uses the following registers:
+//!
+//! * RBX: a pointer to the registers of the ExecutionContext.
+//! * R12: a pointer to the local variables of the ExecutionContext.
+//! * R13: a pointer to the VM state.
+//! * RAX: the index of the instruction to continue at in the interpreter,
+//!   once the machine code returns.
+use crate::compiled_code::CompiledCode;
+use crate::inline_cache::{InlineCache, CACHE_ARGUMENT};
+use crate::jit::assembler::{Assembler, Cond, Label, Reg};
+use crate::module::Module;
+use crate::object_pointer::{ObjectPointer, RawObjectPointer};
+use crate::vm::instruction::{Instruction, Opcode};
+use crate::vm::instructions::object;
+use crate::vm::state::RcState;
+use std::ptr;
+
+/// The size of a single register or local variable.
+const SLOT_SIZE: i32 = 8;
+
+/// The integer tag bit of a tagged integer.
+const INTEGER_TAG: u32 = 1;
+
+/// A function called by machine code to look up an attribute.
+type AttributeFunction = unsafe extern "C" fn(
+    *const RcState,
+    *const InlineCache,
+    RawObjectPointer,
+    RawObjectPointer,
+) -> RawObjectPointer;
+
+/// Looks up an attribute on behalf of machine code.
+unsafe extern "C" fn get_attribute(
+    state: *const RcState,
+    cache: *const InlineCache,
+    receiver: RawObjectPointer,
+    name: RawObjectPointer,
+) -> RawObjectPointer {
+    let state = &*state;
+    let receiver = ObjectPointer::new(receiver);
+    let name = ObjectPointer::new(name);
+    let result = if let Some(cache) = cache.as_ref() {
+        object::get_attribute_cached(state, cache, receiver, name)
+    } else {
+        object::get_attribute(state, receiver, name)
+    };
+
+    result.raw.raw
+}
+
+/// Looks up an attribute in the receiver on behalf of machine code.
+unsafe extern "C" fn get_attribute_in_self(
+    state: *const RcState,
+    cache: *const InlineCache,
+    receiver: RawObjectPointer,
+    name: RawObjectPointer,
+) -> RawObjectPointer {
+    let state = &*state;
+    let receiver = ObjectPointer::new(receiver);
+    let name = ObjectPointer::new(name);
+    let result = if let Some(cache) = cache.as_ref() {
+        object::get_attribute_in_self_cached(state, cache, receiver, name)
+    } else {
+        object::get_attribute_in_self(state, receiver, name)
+    };
+
+    result.raw.raw
+}
+
+/// Compiles the instructions of a CompiledCode to machine code.
+///
+/// An error is returned if the code isn't worth compiling, such as when its
+/// first instruction has to be performed by the interpreter.
+pub fn compile(
+    state: &RcState,
+    code: &CompiledCode,
+    module: &Module,
+) -> Result<Vec<u8>, String> {
+    let mut compiler = Compiler::new(state, code, module);
+
+    if !compiler.supported(&code.instructions[0]) {
+        return Err(format!(
+            "The first instruction ({:?}) can't be compiled",
+            code.instructions[0].opcode
+        ));
+    }
+
+    compiler.compile();
+    compiler.asm.finish()
+}
+
+struct Compiler<'a> {
+    state: &'a RcState,
+    code: &'a CompiledCode,
+    module: &'a Module,
+    asm: Assembler,
+
+    /// The labels of all instructions.
+    instructions: Vec<Label>,
+
+    /// The labels of the code that returns to the interpreter for a given
+    /// instruction, created when first needed.
+    exits: Vec<Option<Label>>,
+
+    /// The label of the code that returns to the interpreter.
+    epilogue: Label,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(
+        state: &'a RcState,
+        code: &'a CompiledCode,
+        module: &'a Module,
+    ) -> Self {
+        let mut asm = Assembler::new();
+        let instructions =
+            code.instructions.iter().map(|_| asm.label()).collect();
+        let epilogue = asm.label();
+
+        Compiler {
+            state,
+            code,
+            module,
+            asm,
+            instructions,
+            exits: vec![None; code.instructions.len()],
+            epilogue,
+        }
+    }
+
+    fn compile(&mut self) {
+        self.asm.push(Reg::Rbx);
+        self.asm.push(Reg::R12);
+        self.asm.push(Reg::R13);
+        self.asm.mov(Reg::Rbx, Reg::Rdi);
+        self.asm.mov(Reg::R12, Reg::Rsi);
+        self.asm.mov(Reg::R13, Reg::Rdx);
+
+        let code = self.code;
+
+        for (index, instruction) in code.instructions.iter().enumerate() {
+            self.asm.bind(self.instructions[index]);
+
+            if self.supported(instruction) {
+                self.instruction(index, instruction);
+            } else {
+                self.return_to_interpreter(index);
+            }
+        }
+
+        // Valid code never runs past its last instruction, but we don't want
+        // to run random memory if this does happen.
+        self.return_to_interpreter(code.instructions.len());
+
+        for index in 0..self.exits.len() {
+            if let Some(label) = self.exits[index] {
+                self.asm.bind(label);
+                self.return_to_interpreter(index);
+            }
+        }
+
+        self.asm.bind(self.epilogue);
+        self.asm.pop(Reg::R13);
+        self.asm.pop(Reg::R12);
+        self.asm.pop(Reg::Rbx);
+        self.asm.ret();
+    }
+
+    /// Returns true if an instruction can be compiled to machine code.
+    fn supported(&self, instruction: &Instruction) -> bool {
+        let len = self.code.instructions.len();
+
+        match instruction.opcode {
+            Opcode::SetLiteral => {
+                self.literal(instruction.arg(1) as usize).is_some()
+            }
+            Opcode::SetLiteralWide => {
+                self.literal(wide_literal(instruction)).is_some()
+            }
+            Opcode::Goto => (instruction.arg(0) as usize) < len,
+            Opcode::GotoIfFalse | Opcode::GotoIfTrue => {
+                (instruction.arg(0) as usize) < len
+            }
+            Opcode::IntegerCompareGotoIfFalse => {
+                (instruction.arg(3) as usize) < len
+                    && comparison(instruction.arg(4)).is_some()
+            }
+            Opcode::GetTrue
+            | Opcode::GetFalse
+            | Opcode::GetNil
+            | Opcode::CopyRegister
+            | Opcode::GetLocal
+            | Opcode::SetLocal
+            | Opcode::IntegerAdd
+            | Opcode::IntegerSub
+            | Opcode::IntegerSmaller
+            | Opcode::IntegerSmallerOrEqual
+            | Opcode::IntegerGreater
+            | Opcode::IntegerGreaterOrEqual
+            | Opcode::IntegerEquals
+            | Opcode::GetAttribute
+            | Opcode::GetAttributeInSelf
+            | Opcode::GetLocalGetAttribute => true,
+            _ => false,
+        }
+    }
+
+    fn instruction(&mut self, index: usize, instruction: &Instruction) {
+        match instruction.opcode {
+            Opcode::SetLiteral => {
+                let literal = self.literal(instruction.arg(1) as usize);
+
+                self.set_constant(instruction.arg(0), literal.unwrap());
+            }
+            Opcode::SetLiteralWide => {
+                let literal = self.literal(wide_literal(instruction));
+
+                self.set_constant(instruction.arg(0), literal.unwrap());
+            }
+            Opcode::GetTrue => {
+                self.set_constant(instruction.arg(0), self.state.true_object);
+            }
+            Opcode::GetFalse => {
+                self.set_constant(instruction.arg(0), self.state.false_object);
+            }
+            Opcode::GetNil => {
+                self.set_constant(instruction.arg(0), self.state.nil_object);
+            }
+            Opcode::CopyRegister => {
+                self.get_register(Reg::Rax, instruction.arg(1));
+                self.set_register(instruction.arg(0), Reg::Rax);
+            }
+            Opcode::GetLocal => {
+                self.get_local(Reg::Rax, instruction.arg(1));
+                self.set_register(instruction.arg(0), Reg::Rax);
+            }
+            Opcode::SetLocal => {
+                self.get_register(Reg::Rax, instruction.arg(1));
+                self.asm.store(Reg::R12, slot(instruction.arg(0)), Reg::Rax);
+            }
+            Opcode::Goto => {
+                let target = self.instructions[instruction.arg(0) as usize];
+
+                self.asm.jmp(target);
+            }
+            Opcode::GotoIfFalse => {
+                let target = self.instructions[instruction.arg(0) as usize];
+
+                self.get_register(Reg::Rax, instruction.arg(1));
+                self.jump_if_false(target);
+            }
+            Opcode::GotoIfTrue => {
+                let target = self.instructions[instruction.arg(0) as usize];
+                let skip = self.asm.label();
+
+                self.get_register(Reg::Rax, instruction.arg(1));
+                self.jump_if_false(skip);
+                self.asm.jmp(target);
+                self.asm.bind(skip);
+            }
+            Opcode::IntegerAdd => {
+                let exit = self.exit(index);
+
+                self.integer_operands(exit, instruction);
+
+                // Adding two tagged integers results in the tag bit being
+                // added twice, so we remove it from one of the operands.
+                self.asm.sub_imm(Reg::Rax, 1);
+                self.asm.add(Reg::Rax, Reg::Rcx);
+                self.asm.jcc(Cond::Overflow, exit);
+                self.set_register(instruction.arg(0), Reg::Rax);
+            }
+            Opcode::IntegerSub => {
+                let exit = self.exit(index);
+
+                self.integer_operands(exit, instruction);
+
+                // Subtracting two tagged integers removes the tag bit, so we
+                // add it back.
+                self.asm.sub(Reg::Rax, Reg::Rcx);
+                self.asm.jcc(Cond::Overflow, exit);
+                self.asm.add_imm(Reg::Rax, 1);
+                self.set_register(instruction.arg(0), Reg::Rax);
+            }
+            Opcode::IntegerSmaller
+            | Opcode::IntegerSmallerOrEqual
+            | Opcode::IntegerGreater
+            | Opcode::IntegerGreaterOrEqual
+            | Opcode::IntegerEquals => {
+                let cond = comparison(instruction.opcode as u16).unwrap();
+
+                self.integer_comparison(index, instruction, cond);
+            }
+            Opcode::IntegerCompareGotoIfFalse => {
+                let cond = comparison(instruction.arg(4)).unwrap();
+                let target = self.instructions[instruction.arg(3) as usize];
+
+                self.integer_comparison(index, instruction, cond);
+                self.asm.jcc(cond.negate(), target);
+            }
+            Opcode::GetAttribute => {
+                self.get_register(Reg::Rdx, instruction.arg(1));
+                self.call_get_attribute(instruction, get_attribute);
+            }
+            Opcode::GetAttributeInSelf => {
+                self.get_register(Reg::Rdx, instruction.arg(1));
+                self.call_get_attribute(instruction, get_attribute_in_self);
+            }
+            Opcode::GetLocalGetAttribute => {
+                self.get_local(Reg::Rdx, instruction.arg(4));
+                self.set_register(instruction.arg(1), Reg::Rdx);
+                self.call_get_attribute(instruction, get_attribute);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Compares two integers, storing the resulting boolean in a register.
+    ///
+    /// The flags set by the comparison are preserved, allowing a conditional
+    /// jump to follow.
+    fn integer_comparison(
+        &mut self,
+        index: usize,
+        instruction: &Instruction,
+        cond: Cond,
+    ) {
+        let exit = self.exit(index);
+
+        self.integer_operands(exit, instruction);
+        self.asm.mov_imm(Reg::Rdx, address(self.state.true_object));
+        self.asm.cmp(Reg::Rax, Reg::Rcx);
+
+        // Moving values doesn't change the flags set by the comparison.
+        self.asm.mov_imm(Reg::Rax, address(self.state.false_object));
+        self.asm.cmov(cond, Reg::Rax, Reg::Rdx);
+        self.set_register(instruction.arg(0), Reg::Rax);
+    }
+
+    /// Loads the operands of an integer instruction into RAX and RCX, jumping
+    /// to the exit if either operand isn't a tagged integer.
+    fn integer_operands(&mut self, exit: Label, instruction: &Instruction) {
+        self.get_register(Reg::Rax, instruction.arg(1));
+        self.get_register(Reg::Rcx, instruction.arg(2));
+        self.asm.mov(Reg::Rdx, Reg::Rax);
+        self.asm.and(Reg::Rdx, Reg::Rcx);
+        self.asm.test_imm(Reg::Rdx, INTEGER_TAG);
+        self.asm.jcc(Cond::Equal, exit);
+    }
+
+    /// Calls a function to look up an attribute, using the receiver stored in
+    /// RDX.
+    fn call_get_attribute(
+        &mut self,
+        instruction: &Instruction,
+        function: AttributeFunction,
+    ) {
+        let cache = self
+            .code
+            .inline_cache(instruction.arg(CACHE_ARGUMENT))
+            .map(|cache| cache as *const InlineCache)
+            .unwrap_or(ptr::null());
+
+        self.asm.mov(Reg::Rdi, Reg::R13);
+        self.asm.mov_imm(Reg::Rsi, cache as u64);
+        self.get_register(Reg::Rcx, instruction.arg(2));
+        self.asm.mov_imm(Reg::Rax, function as usize as u64);
+        self.asm.call(Reg::Rax);
+        self.set_register(instruction.arg(0), Reg::Rax);
+    }
+
+    /// Jumps to the label if the value in RAX is false or nil.
+    fn jump_if_false(&mut self, target: Label) {
+        self.asm.mov_imm(Reg::Rcx, address(self.state.false_object));
+        self.asm.cmp(Reg::Rax, Reg::Rcx);
+        self.asm.jcc(Cond::Equal, target);
+        self.asm.mov_imm(Reg::Rcx, address(self.state.nil_object));
+        self.asm.cmp(Reg::Rax, Reg::Rcx);
+        self.asm.jcc(Cond::Equal, target);
+    }
+
+    fn set_constant(&mut self, register: u16, value: ObjectPointer) {
+        self.asm.mov_imm(Reg::Rax, address(value));
+        self.set_register(register, Reg::Rax);
+    }
+
+    fn get_register(&mut self, dst: Reg, register: u16) {
+        self.asm.load(dst, Reg::Rbx, slot(register));
+    }
+
+    fn set_register(&mut self, register: u16, src: Reg) {
+        self.asm.store(Reg::Rbx, slot(register), src);
+    }
+
+    fn get_local(&mut self, dst: Reg, local: u16) {
+        self.asm.load(dst, Reg::R12, slot(local));
+    }
+
+    fn return_to_interpreter(&mut self, index: usize) {
+        self.asm.mov_imm(Reg::Rax, index as u64);
+        self.asm.jmp(self.epilogue);
+    }
+
+    /// Returns the label of the code that returns to the interpreter at the
+    /// given instruction.
+    fn exit(&mut self, index: usize) -> Label {
+        if let Some(label) = self.exits[index] {
+            return label;
+        }
+
+        let label = self.asm.label();
+
+        self.exits[index] = Some(label);
+        label
+    }
+
+    fn literal(&self, index: usize) -> Option<ObjectPointer> {
+        self.module.literals().get(index).cloned()
+    }
+}
+
+/// Returns the condition to use for an integer comparison opcode.
+fn comparison(opcode: u16) -> Option<Cond> {
+    let cond = match Opcode::from_u8(opcode as u8)? {
+        Opcode::IntegerSmaller => Cond::Less,
+        Opcode::IntegerSmallerOrEqual => Cond::LessOrEqual,
+        Opcode::IntegerGreater => Cond::Greater,
+        Opcode::IntegerGreaterOrEqual => Cond::GreaterOrEqual,
+        Opcode::IntegerEquals => Cond::Equal,
+        _ => return None,
+    };
+
+    Some(cond)
+}
+
+fn wide_literal(instruction: &Instruction) -> usize {
+    ((instruction.arg(1) as usize) << 16) | (instruction.arg(2) as usize)
+}
+
+fn slot(index: u16) -> i32 {
+    i32::from(index) * SLOT_SIZE
+}
+
+fn address(pointer: ObjectPointer) -> u64 {
+    pointer.raw.raw as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jit::memory::ExecutableMemory;
+    use crate::jit::NativeFunction;
+    use crate::object_pointer::MAX_INTEGER;
+    use crate::vm::test::setup;
+    use std::mem;
+
+    fn ins(opcode: Opcode, arguments: [u16; 6]) -> Instruction {
+        Instruction::new(opcode, arguments, 1)
+    }
+
+    /// Compiles and runs the instructions, returning the index the
+    /// interpreter should continue at.
+    fn run(
+        state: &RcState,
+        module: &Module,
+        instructions: Vec<Instruction>,
+        registers: &mut [ObjectPointer],
+        locals: &mut [ObjectPointer],
+    ) -> usize {
+        let name = state.intern_string("test".to_string());
+        let code = CompiledCode::new(name, name, 1, instructions);
+        let machine_code = compile(state, &code, module).unwrap();
+        let memory = ExecutableMemory::new(&machine_code).unwrap();
+        let function: NativeFunction =
+            unsafe { mem::transmute(memory.as_ptr()) };
+
+        unsafe { function(registers.as_mut_ptr(), locals.as_mut_ptr(), state) }
+    }
+
+    #[test]
+    fn test_compile_unsupported_first_instruction() {
+        let (machine, block, _process) = setup();
+        let name = machine.state.intern_string("test".to_string());
+        let code = CompiledCode::new(
+            name,
+            name,
+            1,
+            vec![ins(Opcode::Return, [0, 0, 0, 0, 0, 0])],
+        );
+
+        assert!(compile(&machine.state, &code, &block.module).is_err());
+    }
+
+    #[test]
+    fn test_integer_add() {
+        let (machine, block, _process) = setup();
+        let mut registers = [
+            ObjectPointer::integer(0),
+            ObjectPointer::integer(3),
+            ObjectPointer::integer(-5),
+        ];
+        let resume = run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::IntegerAdd, [0, 1, 2, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut [],
+        );
+
+        assert_eq!(resume, 1);
+        assert_eq!(registers[0].integer_value(), Ok(-2));
+    }
+
+    #[test]
+    fn test_integer_add_overflow() {
+        let (machine, block, _process) = setup();
+        let mut registers = [
+            ObjectPointer::integer(0),
+            ObjectPointer::integer(MAX_INTEGER),
+            ObjectPointer::integer(1),
+        ];
+        let resume = run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::GetNil, [0, 0, 0, 0, 0, 0]),
+                ins(Opcode::IntegerAdd, [0, 1, 2, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut [],
+        );
+
+        assert_eq!(resume, 1);
+        assert!(registers[0] == machine.state.nil_object);
+    }
+
+    #[test]
+    fn test_integer_add_without_integers() {
+        let (machine, block, _process) = setup();
+        let mut registers = [
+            ObjectPointer::integer(0),
+            ObjectPointer::integer(1),
+            machine.state.nil_object,
+        ];
+        let resume = run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::IntegerAdd, [0, 1, 2, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut [],
+        );
+
+        assert_eq!(resume, 0);
+        assert_eq!(registers[0].integer_value(), Ok(0));
+    }
+
+    #[test]
+    fn test_integer_sub() {
+        let (machine, block, _process) = setup();
+        let mut registers = [
+            ObjectPointer::integer(0),
+            ObjectPointer::integer(3),
+            ObjectPointer::integer(5),
+        ];
+
+        run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::IntegerSub, [0, 1, 2, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut [],
+        );
+
+        assert_eq!(registers[0].integer_value(), Ok(-2));
+    }
+
+    #[test]
+    fn test_integer_comparison() {
+        let (machine, block, _process) = setup();
+        let mut registers = [
+            ObjectPointer::integer(0),
+            ObjectPointer::integer(3),
+            ObjectPointer::integer(5),
+            ObjectPointer::integer(0),
+        ];
+
+        run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::IntegerSmaller, [0, 1, 2, 0, 0, 0]),
+                ins(Opcode::IntegerGreaterOrEqual, [3, 1, 2, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut [],
+        );
+
+        assert!(registers[0] == machine.state.true_object);
+        assert!(registers[3] == machine.state.false_object);
+    }
+
+    #[test]
+    fn test_loop() {
+        let (machine, block, _process) = setup();
+        let mut registers = [
+            ObjectPointer::integer(0),
+            ObjectPointer::integer(10),
+            ObjectPointer::integer(1),
+            ObjectPointer::null(),
+        ];
+        let mut locals = [ObjectPointer::integer(0)];
+
+        // This increments local 0 until it equals the value in register 1.
+        let resume = run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::GetLocal, [0, 0, 0, 0, 0, 0]),
+                ins(
+                    Opcode::IntegerCompareGotoIfFalse,
+                    [3, 0, 1, 5, Opcode::IntegerSmaller as u16, 0],
+                ),
+                ins(Opcode::IntegerAdd, [0, 0, 2, 0, 0, 0]),
+                ins(Opcode::SetLocal, [0, 0, 0, 0, 0, 0]),
+                ins(Opcode::Goto, [0, 0, 0, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut locals,
+        );
+
+        assert_eq!(resume, 5);
+        assert_eq!(locals[0].integer_value(), Ok(10));
+        assert!(registers[3] == machine.state.false_object);
+    }
+
+    #[test]
+    fn test_goto_if_true() {
+        let (machine, block, _process) = setup();
+        let instructions = || {
+            vec![
+                ins(Opcode::GotoIfTrue, [2, 0, 0, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ]
+        };
+
+        let mut registers = [machine.state.nil_object];
+        let resume = run(
+            &machine.state,
+            &block.module,
+            instructions(),
+            &mut registers,
+            &mut [],
+        );
+
+        assert_eq!(resume, 1);
+
+        let mut registers = [ObjectPointer::integer(1)];
+        let resume = run(
+            &machine.state,
+            &block.module,
+            instructions(),
+            &mut registers,
+            &mut [],
+        );
+
+        assert_eq!(resume, 2);
+    }
+
+    #[test]
+    fn test_get_attribute() {
+        let (machine, block, process) = setup();
+        let name = machine.state.intern_string("foo".to_string());
+        let value = ObjectPointer::integer(42);
+        let receiver = process.allocate_empty();
+
+        receiver.add_attribute(&process, name, value);
+
+        let mut registers = [ObjectPointer::null(), receiver, name];
+
+        run(
+            &machine.state,
+            &block.module,
+            vec![
+                ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+                ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ],
+            &mut registers,
+            &mut [],
+        );
+
+        assert!(registers[0] == value);
+    }
+}
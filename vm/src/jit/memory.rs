@@ -0,0 +1,87 @@
+//! Memory for storing and running machine code.
+use nix::libc::c_void;
+use nix::sys::mman::{mmap, mprotect, munmap, MapFlags, ProtFlags};
+use std::ptr;
+
+/// A region of memory containing executable machine code.
+///
+/// The memory is writable while copying the code into it, and is made
+/// executable (and read-only) afterwards.
+pub struct ExecutableMemory {
+    pointer: *mut c_void,
+    size: usize,
+}
+
+// The memory is never written to after it's made executable.
+unsafe impl Sync for ExecutableMemory {}
+unsafe impl Send for ExecutableMemory {}
+
+impl ExecutableMemory {
+    /// Allocates executable memory containing the given machine code.
+    pub fn new(code: &[u8]) -> Result<Self, String> {
+        let size = code.len().max(1);
+        let pointer = unsafe {
+            mmap(
+                ptr::null_mut(),
+                size,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                MapFlags::MAP_PRIVATE | MapFlags::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        }
+        .map_err(|err| {
+            format!("Failed to allocate executable memory: {}", err)
+        })?;
+
+        let memory = ExecutableMemory { pointer, size };
+
+        unsafe {
+            ptr::copy_nonoverlapping(
+                code.as_ptr(),
+                pointer as *mut u8,
+                code.len(),
+            );
+
+            mprotect(
+                pointer,
+                size,
+                ProtFlags::PROT_READ | ProtFlags::PROT_EXEC,
+            )
+            .map_err(|err| {
+                format!("Failed to make memory executable: {}", err)
+            })?;
+        }
+
+        Ok(memory)
+    }
+
+    /// Returns a pointer to the start of the machine code.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.pointer as *const u8
+    }
+}
+
+impl Drop for ExecutableMemory {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = munmap(self.pointer, self.size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn test_new() {
+        // mov eax, 42; ret
+        let memory = ExecutableMemory::new(&[0xB8, 42, 0, 0, 0, 0xC3]).unwrap();
+        let function: extern "C" fn() -> u32 =
+            unsafe { mem::transmute(memory.as_ptr()) };
+
+        assert_eq!(function(), 42);
+    }
+}
@@ -0,0 +1,173 @@
+//! A baseline JIT compiler for x86-64.
+//!
+//! The JIT compiler is enabled using the "jit" feature. When enabled, the VM
+//! counts how many times every CompiledCode is called. Once this number
+//! reaches a threshold, the instructions of the CompiledCode are compiled to
+//! machine code.
+//!
+//! Machine code always starts at the first instruction, and runs until it
+//! reaches an instruction it can't perform. It then returns the index of this
+//! instruction, and the interpreter continues from there. This means the
+//! interpreter is still responsible for calling methods, returning, throwing
+//! values, allocating objects, reductions and garbage collection safepoints.
+//!
+//! Machine code is never released, as CompiledCode objects are never released
+//! either.
+use crate::compiled_code::CompiledCode;
+use crate::execution_context::ExecutionContext;
+use crate::module::Module;
+use crate::object_pointer::ObjectPointer;
+use crate::vm::state::RcState;
+use parking_lot::Mutex;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(all(target_arch = "x86_64", unix)))]
+compile_error!("The JIT compiler is only supported on x86-64 Unix platforms");
+
+pub mod assembler;
+pub mod compiler;
+pub mod memory;
+
+use self::memory::ExecutableMemory;
+
+/// A function compiled to machine code.
+///
+/// The arguments are pointers to the registers, the local variables, and the
+/// VM state. The return value is the index of the instruction to continue at
+/// in the interpreter.
+pub type NativeFunction = unsafe extern "C" fn(
+    *mut ObjectPointer,
+    *mut ObjectPointer,
+    *const RcState,
+) -> usize;
+
+/// The JIT state of a single CompiledCode.
+pub struct Jit {
+    /// The number of times the code has been called, up to the JIT threshold.
+    calls: AtomicUsize,
+
+    /// The address of the compiled machine code, or 0 if the code isn't
+    /// compiled.
+    function: AtomicUsize,
+
+    /// The memory containing the machine code.
+    memory: Mutex<Option<ExecutableMemory>>,
+}
+
+impl Jit {
+    pub fn new() -> Self {
+        Jit {
+            calls: AtomicUsize::new(0),
+            function: AtomicUsize::new(0),
+            memory: Mutex::new(None),
+        }
+    }
+
+    /// Returns the machine code to run for a call, compiling it if the code
+    /// has been called often enough.
+    pub fn function(
+        &self,
+        state: &RcState,
+        code: &CompiledCode,
+        module: &Module,
+    ) -> Option<NativeFunction> {
+        let function = self.function.load(Ordering::Acquire);
+
+        if function != 0 {
+            return Some(unsafe {
+                mem::transmute::<usize, NativeFunction>(function)
+            });
+        }
+
+        let threshold = state.config.jit_threshold;
+
+        // Once the threshold is reached the code is either being compiled, or
+        // it can't be compiled. In both cases we don't need to keep counting.
+        if threshold == 0 || self.calls.load(Ordering::Relaxed) >= threshold {
+            return None;
+        }
+
+        if self.calls.fetch_add(1, Ordering::Relaxed) + 1 != threshold {
+            return None;
+        }
+
+        let memory = compiler::compile(state, code, module)
+            .and_then(|machine_code| ExecutableMemory::new(&machine_code))
+            .ok()?;
+
+        let function = memory.as_ptr() as usize;
+
+        *self.memory.lock() = Some(memory);
+        self.function.store(function, Ordering::Release);
+
+        Some(unsafe { mem::transmute::<usize, NativeFunction>(function) })
+    }
+
+    pub fn is_compiled(&self) -> bool {
+        self.function.load(Ordering::Acquire) != 0
+    }
+}
+
+/// Runs the machine code of an ExecutionContext that is about to run its first
+/// instruction.
+///
+/// The return value is the index of the instruction to continue at, or None
+/// if there is no machine code to run.
+pub fn run(state: &RcState, context: &mut ExecutionContext) -> Option<usize> {
+    let code = context.code;
+    let function = code.jit.function(state, &code, &context.module)?;
+    let registers = context.registers.values.as_mut_ptr();
+    let locals = context.binding.locals_mut().as_mut_ptr();
+
+    Some(unsafe { function(registers, locals, state) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::instruction::{Instruction, Opcode};
+    use crate::vm::test::setup;
+
+    #[test]
+    fn test_function_threshold() {
+        let (machine, block, _process) = setup();
+        let state = &machine.state;
+        let name = state.intern_string("test".to_string());
+        let code = CompiledCode::new(
+            name,
+            name,
+            1,
+            vec![
+                Instruction::new(Opcode::GetNil, [0, 0, 0, 0, 0, 0], 1),
+                Instruction::new(Opcode::Return, [0, 0, 0, 0, 0, 0], 1),
+            ],
+        );
+
+        for _ in 1..state.config.jit_threshold {
+            assert!(code.jit.function(state, &code, &block.module).is_none());
+        }
+
+        assert!(code.jit.function(state, &code, &block.module).is_some());
+        assert!(code.jit.is_compiled());
+    }
+
+    #[test]
+    fn test_function_unsupported_code() {
+        let (machine, block, _process) = setup();
+        let state = &machine.state;
+        let name = state.intern_string("test".to_string());
+        let code = CompiledCode::new(
+            name,
+            name,
+            1,
+            vec![Instruction::new(Opcode::Return, [0, 0, 0, 0, 0, 0], 1)],
+        );
+
+        for _ in 0..=state.config.jit_threshold {
+            assert!(code.jit.function(state, &code, &block.module).is_none());
+        }
+
+        assert!(!code.jit.is_compiled());
+    }
+}
@@ -19,17 +19,23 @@ pub mod date_time;
 pub mod deref_pointer;
 pub mod directories;
 pub mod duration;
+pub mod embed;
 pub mod execution_context;
 pub mod external_functions;
 pub mod ffi;
 pub mod file;
+pub mod frozen;
 pub mod gc;
 pub mod generator;
 pub mod global_scope;
 pub mod hasher;
 pub mod immix;
 pub mod immutable_string;
+pub mod inline_cache;
 pub mod integer_operations;
+pub mod io_pool;
+#[cfg(feature = "jit")]
+pub mod jit;
 pub mod mailbox;
 pub mod module;
 pub mod modules;
@@ -38,12 +44,16 @@ pub mod numeric;
 pub mod object;
 pub mod object_pointer;
 pub mod object_value;
+pub mod opcode_statistics;
+pub mod peephole;
 pub mod platform;
 pub mod process;
 pub mod process_status;
+pub mod profiler;
 pub mod registers;
 pub mod runtime_error;
 pub mod scheduler;
+pub mod shape;
 pub mod slicing;
 pub mod socket;
 pub mod string_pool;
@@ -1,26 +1,143 @@
 use crate::object_pointer::{ObjectPointer, ObjectPointerPointer};
+use crate::process::RcProcess;
 use std::collections::VecDeque;
 
+/// What to do when sending a message to a mailbox that is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    /// Suspend the sender until there is room in the mailbox.
+    Suspend,
+
+    /// Remove the oldest message to make room for the new message.
+    DropOldest,
+
+    /// Produce an error in the sender.
+    Error,
+}
+
+impl OverflowPolicy {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(OverflowPolicy::Suspend),
+            1 => Some(OverflowPolicy::DropOldest),
+            2 => Some(OverflowPolicy::Error),
+            _ => None,
+        }
+    }
+}
+
 pub struct Mailbox {
     /// The messages stored in this mailbox.
     messages: VecDeque<ObjectPointer>,
+
+    /// The number of messages (starting at the front) that the last selective
+    /// receive checked without finding a match.
+    ///
+    /// A process waiting for a specific message only needs to be woken up when
+    /// a message arrives that it hasn't checked yet.
+    checked: usize,
+
+    /// The maximum number of messages the mailbox can store, if any.
+    capacity: Option<usize>,
+
+    /// What to do when a message is sent while the mailbox is full.
+    overflow_policy: OverflowPolicy,
+
+    /// The processes that are suspended until there is room in the mailbox.
+    blocked_senders: VecDeque<RcProcess>,
 }
 
 impl Mailbox {
     pub fn new() -> Self {
         Mailbox {
             messages: VecDeque::new(),
+            checked: 0,
+            capacity: None,
+            overflow_policy: OverflowPolicy::Suspend,
+            blocked_senders: VecDeque::new(),
         }
     }
 
+    /// Limits the number of messages the mailbox can store.
+    ///
+    /// A capacity of `None` removes the limit.
+    pub fn set_capacity(
+        &mut self,
+        capacity: Option<usize>,
+        overflow_policy: OverflowPolicy,
+    ) {
+        self.capacity = capacity;
+        self.overflow_policy = overflow_policy;
+    }
+
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.capacity
+            .map(|capacity| self.messages.len() >= capacity)
+            .unwrap_or(false)
+    }
+
+    /// Removes the oldest message from the mailbox.
+    pub fn drop_oldest(&mut self) {
+        if self.messages.pop_front().is_some() && self.checked > 0 {
+            self.checked -= 1;
+        }
+    }
+
+    pub fn add_blocked_sender(&mut self, sender: RcProcess) {
+        self.blocked_senders.push_back(sender);
+    }
+
+    pub fn take_blocked_sender(&mut self) -> Option<RcProcess> {
+        self.blocked_senders.pop_front()
+    }
+
+    pub fn take_blocked_senders(&mut self) -> Vec<RcProcess> {
+        self.blocked_senders.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
     pub fn send(&mut self, message: ObjectPointer) {
         self.messages.push_back(message);
     }
 
     pub fn receive(&mut self) -> Option<ObjectPointer> {
+        self.checked = 0;
         self.messages.pop_front()
     }
 
+    /// Receives the oldest message for which the matcher returns true.
+    ///
+    /// Messages that don't match are left in the mailbox, in the order they
+    /// were sent in.
+    pub fn receive_matching<F>(
+        &mut self,
+        mut matcher: F,
+    ) -> Option<ObjectPointer>
+    where
+        F: FnMut(ObjectPointer) -> bool,
+    {
+        let index = self.messages.iter().position(|msg| matcher(*msg));
+
+        if let Some(index) = index {
+            self.checked = 0;
+            self.messages.remove(index)
+        } else {
+            self.checked = self.messages.len();
+            None
+        }
+    }
+
     pub fn each_pointer<F>(&self, mut callback: F)
     where
         F: FnMut(ObjectPointerPointer),
@@ -30,8 +147,10 @@ impl Mailbox {
         }
     }
 
+    /// Returns true if the mailbox contains messages that weren't checked by a
+    /// failed selective receive.
     pub fn has_messages(&self) -> bool {
-        !self.messages.is_empty()
+        self.messages.len() > self.checked
     }
 }
 
@@ -78,4 +197,75 @@ mod tests {
 
         assert!(mailbox.has_messages());
     }
+
+    #[test]
+    fn test_is_full() {
+        let mut mailbox = Mailbox::new();
+
+        mailbox.send(ObjectPointer::integer(1));
+
+        assert_eq!(mailbox.is_full(), false);
+
+        mailbox.set_capacity(Some(1), OverflowPolicy::Error);
+
+        assert!(mailbox.is_full());
+        assert_eq!(mailbox.overflow_policy(), OverflowPolicy::Error);
+
+        mailbox.set_capacity(None, OverflowPolicy::Suspend);
+
+        assert_eq!(mailbox.is_full(), false);
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let mut mailbox = Mailbox::new();
+
+        mailbox.send(ObjectPointer::integer(1));
+        mailbox.send(ObjectPointer::integer(2));
+        mailbox.drop_oldest();
+
+        assert_eq!(mailbox.len(), 1);
+        assert!(mailbox.receive() == Some(ObjectPointer::integer(2)));
+    }
+
+    #[test]
+    fn test_overflow_policy_from_u8() {
+        assert_eq!(OverflowPolicy::from_u8(0), Some(OverflowPolicy::Suspend));
+        assert_eq!(
+            OverflowPolicy::from_u8(1),
+            Some(OverflowPolicy::DropOldest)
+        );
+        assert_eq!(OverflowPolicy::from_u8(2), Some(OverflowPolicy::Error));
+        assert_eq!(OverflowPolicy::from_u8(3), None);
+    }
+
+    #[test]
+    fn test_receive_matching() {
+        let mut mailbox = Mailbox::new();
+
+        mailbox.send(ObjectPointer::integer(1));
+        mailbox.send(ObjectPointer::integer(2));
+        mailbox.send(ObjectPointer::integer(3));
+
+        let received =
+            mailbox.receive_matching(|msg| msg == ObjectPointer::integer(2));
+
+        assert!(received == Some(ObjectPointer::integer(2)));
+        assert!(mailbox.receive() == Some(ObjectPointer::integer(1)));
+        assert!(mailbox.receive() == Some(ObjectPointer::integer(3)));
+    }
+
+    #[test]
+    fn test_receive_matching_without_match() {
+        let mut mailbox = Mailbox::new();
+
+        mailbox.send(ObjectPointer::integer(1));
+
+        assert!(mailbox.receive_matching(|_| false).is_none());
+        assert_eq!(mailbox.has_messages(), false);
+
+        mailbox.send(ObjectPointer::integer(2));
+
+        assert!(mailbox.has_messages());
+    }
 }
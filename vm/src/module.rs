@@ -81,6 +81,10 @@ impl Module {
         true
     }
 
+    pub fn literals(&self) -> &[ObjectPointer] {
+        &self.literals
+    }
+
     #[inline(always)]
     pub unsafe fn literal(&self, index: usize) -> ObjectPointer {
         *self.literals.get_unchecked(index)
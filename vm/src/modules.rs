@@ -1,23 +1,34 @@
 //! Collections of Inko modules.
 use crate::arc_without_weak::ArcWithoutWeak;
-use crate::bytecode_parser;
+use crate::bytecode_parser::{self, Image, LazyModule};
 use crate::module::Module;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
 use crate::vm::state::State;
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
+
+/// The prefix of the names of all modules of the standard library.
+const STD_PREFIX: &str = "std::";
 
 /// A collection of all Inko modules for the current program.
 pub struct Modules {
     entry_point: Option<String>,
+    compiler_version: Option<String>,
+    metadata: Vec<(String, String)>,
     map: AHashMap<String, ObjectPointer>,
+
+    /// Modules that are parsed when they are first requested.
+    lazy: AHashMap<String, LazyModule>,
 }
 
 impl Modules {
     pub fn new() -> Self {
         Modules {
             entry_point: None,
+            compiler_version: None,
+            metadata: Vec::new(),
             map: AHashMap::default(),
+            lazy: AHashMap::default(),
         }
     }
 
@@ -26,13 +37,69 @@ impl Modules {
         state: &State,
         path: &str,
     ) -> Result<(), String> {
-        let image =
-            bytecode_parser::parse_file(state, path).map_err(|err| {
-                format!("The bytecode image {} is invalid: {}", path, err)
-            })?;
+        let image = Self::parse_file(state, path)?;
 
+        self.set_image(state, image);
+        Ok(())
+    }
+
+    /// Adds the modules of an already parsed image, and uses the image as the
+    /// image of the program.
+    pub fn set_image(&mut self, state: &State, image: Image) {
         self.entry_point = Some(image.entry_point);
+        self.compiler_version = Some(image.compiler_version);
+        self.metadata = image.metadata;
         self.add(state, image.modules);
+        self.add_lazy(image.lazy_modules);
+    }
+
+    /// Parses a bytecode image, without adding its modules.
+    pub fn parse_file(state: &State, path: &str) -> Result<Image, String> {
+        bytecode_parser::parse_file(state, path).map_err(|err| {
+            format!("The bytecode image {} is invalid: {}", path, err)
+        })
+    }
+
+    /// Adds the modules of an additional bytecode image, such as an image
+    /// loaded while the program is running.
+    ///
+    /// The entry point and metadata of the image are ignored.
+    ///
+    /// Images include the modules of the standard library they use. If the
+    /// image is produced by the same compiler as the image of the program, the
+    /// standard library modules that are already defined are kept, and those
+    /// of the image are ignored. If any other module is already defined, or
+    /// the image defines the same module multiple times, no modules are added
+    /// and an error is returned.
+    pub fn add_image(
+        &mut self,
+        state: &State,
+        image: Image,
+    ) -> Result<(), String> {
+        let same_compiler =
+            self.compiler_version.as_ref() == Some(&image.compiler_version);
+        let mut names = AHashSet::new();
+        let mut modules = Vec::new();
+        let mut lazy_modules = Vec::new();
+
+        for module in image.modules {
+            let name = Self::module_name(&module);
+
+            if self.should_add(&mut names, name, same_compiler)? {
+                modules.push(module);
+            }
+        }
+
+        for module in image.lazy_modules {
+            let name = module.name.clone();
+
+            if self.should_add(&mut names, name, same_compiler)? {
+                lazy_modules.push(module);
+            }
+        }
+
+        self.add(state, modules);
+        self.add_lazy(lazy_modules);
 
         Ok(())
     }
@@ -41,17 +108,30 @@ impl Modules {
         self.entry_point.as_ref()
     }
 
+    /// Returns the version of the compiler that produced the image.
+    pub fn compiler_version(&self) -> Option<&String> {
+        self.compiler_version.as_ref()
+    }
+
+    /// Returns the value of a metadata entry of the image.
+    pub fn metadata(&self, key: &str) -> Option<&String> {
+        self.metadata
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the keys of all the metadata entries of the image.
+    pub fn metadata_keys(&self) -> Vec<&String> {
+        self.metadata.iter().map(|(key, _)| key).collect()
+    }
+
     pub fn add(&mut self, state: &State, modules: Vec<Module>) {
         let mut alloc = state.permanent_allocator.lock();
         let proto = state.module_prototype;
 
         for module in modules {
-            let name = module
-                .name()
-                .string_value()
-                .expect("Module names must be String pointers")
-                .to_owned_string();
-
+            let name = Self::module_name(&module);
             let val = object_value::module(ArcWithoutWeak::new(module));
             let ptr = alloc.allocate_with_prototype(val, proto);
 
@@ -59,25 +139,259 @@ impl Modules {
         }
     }
 
-    pub fn get(&self, name: &str) -> Result<ObjectPointer, String> {
-        self.map
-            .get(name)
-            .cloned()
-            .ok_or_else(|| format!("The module {} doesn't exist", name))
+    /// Adds modules that are to be parsed when they are first requested.
+    pub fn add_lazy(&mut self, modules: Vec<LazyModule>) {
+        for module in modules {
+            self.lazy.insert(module.name.clone(), module);
+        }
     }
 
-    pub fn list(&self) -> Vec<ObjectPointer> {
-        self.map.values().copied().collect()
+    pub fn get(
+        &mut self,
+        state: &State,
+        name: &str,
+    ) -> Result<ObjectPointer, String> {
+        if let Some(ptr) = self.map.get(name) {
+            return Ok(*ptr);
+        }
+
+        if !self.lazy.contains_key(name) {
+            return Err(format!("The module {} doesn't exist", name));
+        }
+
+        self.parse_lazy(state, name)
+    }
+
+    pub fn list(
+        &mut self,
+        state: &State,
+    ) -> Result<Vec<ObjectPointer>, String> {
+        let names: Vec<_> = self.lazy.keys().cloned().collect();
+
+        for name in names {
+            self.parse_lazy(state, &name)?;
+        }
+
+        Ok(self.map.values().copied().collect())
     }
 
     pub fn get_for_execution(
         &mut self,
+        state: &State,
         name: &str,
     ) -> Result<(ObjectPointer, bool), String> {
-        self.get(name).and_then(|ptr| {
+        self.get(state, name).and_then(|ptr| {
             let module = ptr.module_value_mut()?;
 
             Ok((ptr, module.mark_as_executed()))
         })
     }
+
+    /// Returns true if a module of an additional image should be added, or an
+    /// error if the module conflicts with an existing module.
+    ///
+    /// The `names` argument contains the names of the modules of the image
+    /// checked so far.
+    fn should_add(
+        &self,
+        names: &mut AHashSet<String>,
+        name: String,
+        same_compiler: bool,
+    ) -> Result<bool, String> {
+        if names.contains(&name) {
+            return Err(format!(
+                "The module {} is defined multiple times in the image",
+                name
+            ));
+        }
+
+        let defined =
+            self.map.contains_key(&name) || self.lazy.contains_key(&name);
+
+        names.insert(name.clone());
+
+        if !defined {
+            return Ok(true);
+        }
+
+        if !name.starts_with(STD_PREFIX) {
+            return Err(format!("The module {} is already defined", name));
+        }
+
+        if same_compiler {
+            Ok(false)
+        } else {
+            Err(format!(
+                "The module {} is already defined by a different version of \
+                the standard library",
+                name
+            ))
+        }
+    }
+
+    fn module_name(module: &Module) -> String {
+        module
+            .name()
+            .string_value()
+            .expect("Module names must be String pointers")
+            .to_owned_string()
+    }
+
+    /// Parses a lazy module and moves it to the list of parsed modules.
+    ///
+    /// If the module can't be parsed it's kept as a lazy module, so that
+    /// every attempt to use it produces the same error.
+    fn parse_lazy(
+        &mut self,
+        state: &State,
+        name: &str,
+    ) -> Result<ObjectPointer, String> {
+        let module = self.lazy[name].parse(state).map_err(|err| {
+            format!("The module {} is invalid: {}", name, err)
+        })?;
+
+        let val = object_value::module(ArcWithoutWeak::new(module));
+        let ptr = state
+            .permanent_allocator
+            .lock()
+            .allocate_with_prototype(val, state.module_prototype);
+
+        self.lazy.remove(name);
+        self.map.insert(name.to_string(), ptr);
+
+        Ok(ptr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiled_code::CompiledCode;
+    use crate::config::Config;
+    use crate::vm::instruction::{Instruction, Opcode};
+
+    fn image(state: &State, names: &[&str]) -> Image {
+        image_with_version(state, names, "0.9.0")
+    }
+
+    fn image_with_version(
+        state: &State,
+        names: &[&str],
+        version: &str,
+    ) -> Image {
+        let modules = names
+            .iter()
+            .map(|name| {
+                let name = state.intern_string(name.to_string());
+                let code = CompiledCode::new(
+                    name,
+                    name,
+                    1,
+                    vec![Instruction::new(Opcode::Return, [0; 6], 1)],
+                );
+
+                Module::new(name, code, Vec::new())
+            })
+            .collect();
+
+        Image {
+            compiler_version: version.to_string(),
+            metadata: Vec::new(),
+            entry_point: names[0].to_string(),
+            modules,
+            lazy_modules: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_add_image() {
+        let state = State::with_rc(Config::new(), &[]);
+        let mut modules = Modules::new();
+
+        modules.add_image(&state, image(&state, &["foo"])).unwrap();
+        modules.add_image(&state, image(&state, &["bar"])).unwrap();
+
+        assert!(modules.get(&state, "foo").is_ok());
+        assert!(modules.get(&state, "bar").is_ok());
+    }
+
+    #[test]
+    fn test_add_image_with_existing_module() {
+        let state = State::with_rc(Config::new(), &[]);
+        let mut modules = Modules::new();
+
+        modules.add_image(&state, image(&state, &["foo"])).unwrap();
+
+        let result = modules.add_image(&state, image(&state, &["bar", "foo"]));
+
+        assert_eq!(
+            result.err(),
+            Some("The module foo is already defined".to_string())
+        );
+        assert!(modules.get(&state, "bar").is_err());
+    }
+
+    #[test]
+    fn test_add_image_with_duplicate_module() {
+        let state = State::with_rc(Config::new(), &[]);
+        let mut modules = Modules::new();
+        let result =
+            modules.add_image(&state, image(&state, &["foo", "bar", "foo"]));
+
+        assert_eq!(
+            result.err(),
+            Some(
+                "The module foo is defined multiple times in the image"
+                    .to_string()
+            )
+        );
+        assert!(modules.get(&state, "bar").is_err());
+    }
+
+    #[test]
+    fn test_add_image_with_existing_std_module() {
+        let state = State::with_rc(Config::new(), &[]);
+        let mut modules = Modules::new();
+
+        modules.set_image(&state, image(&state, &["main", "std::foo"]));
+
+        let std_foo = modules.get(&state, "std::foo").unwrap();
+
+        modules
+            .add_image(&state, image(&state, &["bar", "std::foo"]))
+            .unwrap();
+
+        assert!(modules.get(&state, "bar").is_ok());
+        assert!(modules.get(&state, "std::foo").unwrap() == std_foo);
+    }
+
+    #[test]
+    fn test_add_image_with_std_module_of_different_compiler() {
+        let state = State::with_rc(Config::new(), &[]);
+        let mut modules = Modules::new();
+
+        modules.set_image(&state, image(&state, &["main", "std::foo"]));
+
+        let result = modules.add_image(
+            &state,
+            image_with_version(&state, &["bar", "std::foo"], "0.10.0"),
+        );
+
+        assert!(result.is_err());
+        assert!(modules.get(&state, "bar").is_err());
+    }
+
+    #[test]
+    fn test_get_invalid_lazy_module() {
+        let state = State::with_rc(Config::new(), &[]);
+        let mut modules = Modules::new();
+
+        modules.add_lazy(vec![LazyModule::new("foo".to_string(), vec![1])]);
+
+        let first = modules.get(&state, "foo").err().unwrap();
+        let second = modules.get(&state, "foo").err().unwrap();
+
+        assert!(first.starts_with("The module foo is invalid"));
+        assert_eq!(first, second);
+    }
 }
@@ -4,7 +4,7 @@ use crate::process::RcProcess;
 use crate::vm::state::RcState;
 use polling::{Event, Poller, Source};
 use std::io;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// The type of event a poller should wait for.
 pub enum Interest {
@@ -19,6 +19,9 @@ pub enum Interest {
 pub struct NetworkPoller {
     poller: Poller,
     alive: AtomicBool,
+
+    /// The number of processes waiting for a socket to become ready.
+    waiting: AtomicUsize,
 }
 
 impl NetworkPoller {
@@ -26,6 +29,7 @@ impl NetworkPoller {
         NetworkPoller {
             poller: Poller::new().expect("Failed to set up the network poller"),
             alive: AtomicBool::new(true),
+            waiting: AtomicUsize::new(0),
         }
     }
 
@@ -40,7 +44,9 @@ impl NetworkPoller {
         source: impl Source,
         interest: Interest,
     ) -> io::Result<()> {
-        self.poller.add(source, self.event(process, interest))
+        self.poller.add(source, self.event(process, interest))?;
+        self.waiting.fetch_add(1, Ordering::AcqRel);
+        Ok(())
     }
 
     pub fn modify(
@@ -49,7 +55,14 @@ impl NetworkPoller {
         source: impl Source,
         interest: Interest,
     ) -> io::Result<()> {
-        self.poller.modify(source, self.event(process, interest))
+        self.poller.modify(source, self.event(process, interest))?;
+        self.waiting.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Returns the number of processes waiting for a socket to become ready.
+    pub fn waiting_processes(&self) -> usize {
+        self.waiting.load(Ordering::Acquire)
     }
 
     pub fn terminate(&self) {
@@ -63,6 +76,10 @@ impl NetworkPoller {
         self.alive.load(Ordering::Acquire)
     }
 
+    fn decrement_waiting(&self) {
+        self.waiting.fetch_sub(1, Ordering::AcqRel);
+    }
+
     fn event(&self, process: &RcProcess, interest: Interest) -> Event {
         let key = ArcWithoutWeak::into_raw(process.clone()) as usize;
 
@@ -101,6 +118,7 @@ impl Worker {
                 let process =
                     unsafe { ArcWithoutWeak::from_raw(event.key as *mut _) };
 
+                self.state.network_poller.decrement_waiting();
                 self.state.scheduler.schedule(process);
             }
 
@@ -122,6 +140,7 @@ mod tests {
         let (_machine, _block, process) = setup();
 
         assert!(poller.add(&process, &output, Interest::Read).is_ok());
+        assert_eq!(poller.waiting_processes(), 1);
     }
 
     #[test]
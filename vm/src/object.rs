@@ -3,7 +3,6 @@
 //! The Object struct is used to represent an object created during runtime. It
 //! can be used to wrap native values (e.g. an integer or a string), look up
 //! methods, add attributes, etc.
-use ahash::AHashMap;
 use std::ops::Drop;
 use std::ptr;
 
@@ -11,18 +10,9 @@ use crate::object_pointer::{
     ObjectPointer, ObjectPointerPointer, RawObjectPointer,
 };
 use crate::object_value::ObjectValue;
+use crate::shape::Attributes;
 use crate::tagged_pointer::TaggedPointer;
 
-macro_rules! push_collection {
-    ($map:expr, $what:ident, $vec:expr) => {{
-        $vec.reserve($map.len());
-
-        for thing in $map.$what() {
-            $vec.push(*thing);
-        }
-    }};
-}
-
 /// The status of an object.
 #[derive(Eq, PartialEq, Debug)]
 pub enum ObjectStatus {
@@ -43,8 +33,6 @@ pub enum ObjectStatus {
     PendingMove,
 }
 
-pub type AttributesMap = AHashMap<ObjectPointer, ObjectPointer>;
-
 /// The bit to set for objects that are being forwarded.
 pub const PENDING_FORWARD_BIT: usize = 0;
 
@@ -76,7 +64,7 @@ pub struct Object {
     ///
     /// Multiple bits can be set as well. For example, `101` would mean the
     /// object is remembered and being forwarded.
-    pub attributes: TaggedPointer<AttributesMap>,
+    pub attributes: TaggedPointer<Attributes>,
 
     /// A native Rust value (e.g. a String) that belongs to this object.
     pub value: ObjectValue,
@@ -139,8 +127,8 @@ impl Object {
         &mut self,
         name: ObjectPointer,
     ) -> Option<ObjectPointer> {
-        if let Some(map) = self.attributes_map_mut() {
-            map.remove(&name)
+        if let Some(attributes) = self.attributes_mut() {
+            attributes.remove(name)
         } else {
             None
         }
@@ -148,24 +136,20 @@ impl Object {
 
     /// Returns all the attributes available to this object.
     pub fn attributes(&self) -> Vec<ObjectPointer> {
-        let mut attributes = Vec::new();
-
-        if let Some(map) = self.attributes_map() {
-            push_collection!(map, values, attributes);
+        if let Some(attributes) = self.attributes_ref() {
+            attributes.iter().map(|(_, value)| value).collect()
+        } else {
+            Vec::new()
         }
-
-        attributes
     }
 
     /// Returns all the attribute names available to this object.
     pub fn attribute_names(&self) -> Vec<ObjectPointer> {
-        let mut attributes = Vec::new();
-
-        if let Some(map) = self.attributes_map() {
-            push_collection!(map, keys, attributes);
+        if let Some(attributes) = self.attributes_ref() {
+            attributes.iter().map(|(name, _)| name).collect()
+        } else {
+            Vec::new()
         }
-
-        attributes
     }
 
     /// Looks up an attribute in either the current object or a parent object.
@@ -204,9 +188,9 @@ impl Object {
         name: ObjectPointer,
         object: ObjectPointer,
     ) {
-        self.allocate_attributes_map();
+        self.allocate_attributes();
 
-        self.attributes_map_mut().unwrap().insert(name, object);
+        self.attributes_mut().unwrap().insert(name, object);
     }
 
     /// Looks up an attribute without walking the prototype chain.
@@ -214,23 +198,23 @@ impl Object {
         &self,
         name: ObjectPointer,
     ) -> Option<ObjectPointer> {
-        if let Some(map) = self.attributes_map() {
-            map.get(&name).cloned()
+        if let Some(attributes) = self.attributes_ref() {
+            attributes.get(name)
         } else {
             None
         }
     }
 
     /// Returns an immutable reference to the attributes.
-    pub fn attributes_map(&self) -> Option<&AttributesMap> {
+    pub fn attributes_ref(&self) -> Option<&Attributes> {
         self.attributes.as_ref()
     }
 
-    pub fn attributes_map_mut(&self) -> Option<&mut AttributesMap> {
+    pub fn attributes_mut(&self) -> Option<&mut Attributes> {
         self.attributes.as_mut()
     }
 
-    pub fn set_attributes_map(&mut self, attrs: AttributesMap) {
+    pub fn set_attributes(&mut self, attrs: Attributes) {
         self.attributes = TaggedPointer::new(Box::into_raw(Box::new(attrs)));
     }
 
@@ -242,12 +226,10 @@ impl Object {
             callback(self.prototype.pointer());
         }
 
-        if let Some(map) = self.attributes_map() {
+        if let Some(attributes) = self.attributes_ref() {
             // Attribute keys are interned strings, which don't need to be
             // marked.
-            for (_, pointer) in map.iter() {
-                callback(pointer.pointer());
-            }
+            attributes.each_value(|pointer| callback(pointer.pointer()));
         }
 
         match self.value {
@@ -284,7 +266,7 @@ impl Object {
         // When taking over the attributes we want to automatically inherit the
         // "remembered" bit, but not the forwarding bits.
         let attrs = (self.attributes.raw as usize & !FORWARDING_MASK)
-            as *mut AttributesMap;
+            as *mut Attributes;
 
         new_obj.attributes = TaggedPointer::new(attrs);
 
@@ -324,7 +306,7 @@ impl Object {
         // one. This removes the need for checking both bits when determining if
         // forwarding is necessary.
         let new_attrs =
-            (pointer.raw.raw as usize | FORWARDING_MASK) as *mut AttributesMap;
+            (pointer.raw.raw as usize | FORWARDING_MASK) as *mut Attributes;
 
         self.attributes.atomic_store(new_attrs);
     }
@@ -386,10 +368,10 @@ impl Object {
         pointer
     }
 
-    /// Allocates an attribute map if needed.
-    fn allocate_attributes_map(&mut self) {
+    /// Allocates the attributes if needed.
+    fn allocate_attributes(&mut self) {
         if !self.has_attributes() {
-            self.set_attributes_map(AttributesMap::default());
+            self.set_attributes(Attributes::new());
         }
     }
 }
@@ -423,6 +405,12 @@ mod tests {
         ObjectPointer::new(0x4 as RawObjectPointer)
     }
 
+    fn fake_name() -> ObjectPointer {
+        // Adding an attribute checks if its name is permanent, which requires
+        // either a real object or a tagged integer.
+        ObjectPointer::integer(4)
+    }
+
     fn new_object() -> Object {
         Object::new(ObjectValue::None)
     }
@@ -487,7 +475,7 @@ mod tests {
     #[test]
     fn test_object_remove_attribute() {
         let mut obj = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         obj.add_attribute(name, fake_pointer());
 
@@ -501,7 +489,7 @@ mod tests {
     fn test_object_attributes() {
         let mut obj = new_object();
 
-        obj.add_attribute(fake_pointer(), fake_pointer());
+        obj.add_attribute(fake_name(), fake_pointer());
 
         assert_eq!(obj.attributes().len(), 1);
     }
@@ -510,7 +498,7 @@ mod tests {
     fn test_object_attribute_names() {
         let mut obj = new_object();
 
-        obj.add_attribute(fake_pointer(), fake_pointer());
+        obj.add_attribute(fake_name(), fake_pointer());
 
         assert_eq!(obj.attribute_names().len(), 1);
     }
@@ -518,7 +506,7 @@ mod tests {
     #[test]
     fn test_object_lookup_attribute_defined_in_receiver() {
         let mut obj = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         obj.add_attribute(name.clone(), fake_pointer());
 
@@ -529,7 +517,7 @@ mod tests {
     fn test_object_lookup_attribute_defined_in_prototype() {
         let mut proto = new_object();
         let mut child = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         proto.add_attribute(name.clone(), fake_pointer());
         child.set_prototype(object_pointer_for(&proto));
@@ -541,7 +529,7 @@ mod tests {
     fn test_object_lookup_attribute_with_prototype_without_attribute() {
         let proto = new_object();
         let mut child = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         child.set_prototype(object_pointer_for(&proto));
 
@@ -551,7 +539,7 @@ mod tests {
     #[test]
     fn test_object_add_attribute() {
         let mut obj = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         obj.add_attribute(name.clone(), fake_pointer());
 
@@ -561,7 +549,7 @@ mod tests {
     #[test]
     fn test_object_lookup_attribute_without_attribute() {
         let obj = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         assert!(obj.lookup_attribute(name).is_none());
     }
@@ -569,7 +557,7 @@ mod tests {
     #[test]
     fn test_object_lookup_attribute_with_attribute() {
         let mut obj = new_object();
-        let name = fake_pointer();
+        let name = fake_name();
 
         obj.add_attribute(name.clone(), fake_pointer());
 
@@ -577,30 +565,30 @@ mod tests {
     }
 
     #[test]
-    fn test_object_attributes_map_without_map() {
+    fn test_object_attributes_ref_without_attributes() {
         let obj = new_object();
 
-        assert!(obj.attributes_map().is_none());
+        assert!(obj.attributes_ref().is_none());
     }
 
     #[test]
-    fn test_object_attributes_map_with_map() {
+    fn test_object_attributes_ref_with_attributes() {
         let mut obj = new_object();
 
-        obj.add_attribute(fake_pointer(), fake_pointer());
+        obj.add_attribute(fake_name(), fake_pointer());
 
-        assert!(obj.attributes_map().is_some());
-        assert!(obj.attributes_map_mut().is_some());
+        assert!(obj.attributes_ref().is_some());
+        assert!(obj.attributes_mut().is_some());
     }
 
     #[test]
-    fn test_object_attributes_map_set_map() {
+    fn test_object_set_attributes() {
         let mut obj = new_object();
-        let map = AttributesMap::default();
+        let attributes = Attributes::new();
 
-        obj.set_attributes_map(map);
+        obj.set_attributes(attributes);
 
-        assert!(obj.attributes_map().is_some());
+        assert!(obj.attributes_ref().is_some());
     }
 
     #[test]
@@ -618,7 +606,7 @@ mod tests {
         let mut obj = new_object();
         let mut pointers = Vec::new();
 
-        obj.add_attribute(fake_pointer(), fake_pointer());
+        obj.add_attribute(fake_name(), fake_pointer());
         obj.each_pointer(|ptr| pointers.push(ptr));
 
         let pointer_pointer = pointers.pop().unwrap();
@@ -764,16 +752,16 @@ mod tests {
     #[test]
     fn test_object_take() {
         let mut obj = Object::new(ObjectValue::Float(10.0));
-        let map = AttributesMap::default();
+        let attributes = Attributes::new();
 
-        obj.set_attributes_map(map);
+        obj.set_attributes(attributes);
 
         let new_obj = obj.take();
 
-        assert!(obj.attributes_map().is_none());
+        assert!(obj.attributes_ref().is_none());
         assert!(obj.value.is_none());
 
-        assert!(new_obj.attributes_map().is_some());
+        assert!(new_obj.attributes_ref().is_some());
         assert!(new_obj.value.is_float());
     }
 
@@ -792,9 +780,9 @@ mod tests {
     #[test]
     fn test_object_has_attributes() {
         let mut obj = Object::new(ObjectValue::Float(10.0));
-        let map = AttributesMap::default();
+        let attributes = Attributes::new();
 
-        obj.set_attributes_map(map);
+        obj.set_attributes(attributes);
 
         assert!(obj.has_attributes());
     }
@@ -813,9 +801,9 @@ mod tests {
     #[test]
     fn test_object_has_attributes_remembered() {
         let mut obj = Object::new(ObjectValue::Float(10.0));
-        let map = AttributesMap::default();
+        let attributes = Attributes::new();
 
-        obj.set_attributes_map(map);
+        obj.set_attributes(attributes);
         obj.mark_as_remembered();
 
         assert!(obj.has_attributes());
@@ -888,10 +876,10 @@ mod tests {
     fn test_drop_attributes() {
         let mut obj = new_object();
 
-        obj.add_attribute(fake_pointer(), fake_pointer());
+        obj.add_attribute(fake_name(), fake_pointer());
         obj.drop_attributes();
 
-        assert!(obj.attributes_map().is_none());
+        assert!(obj.attributes_ref().is_none());
     }
 
     #[test]
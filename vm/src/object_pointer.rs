@@ -26,6 +26,7 @@ use crate::block::Block;
 use crate::external_functions::ExternalFunction;
 use crate::ffi::{Library, Pointer, RcFunction};
 use crate::file::File;
+use crate::frozen::FrozenValue;
 use crate::generator::RcGenerator;
 use crate::hasher::Hasher;
 use crate::immix::block;
@@ -598,6 +599,12 @@ impl ObjectPointer {
     def_value_getter!(string_value, get, as_string, &ImmutableString);
 
     def_value_getter!(array_value, get, as_array, &Vec<ObjectPointer>);
+    def_value_getter!(
+        frozen_array_value,
+        get,
+        as_frozen_array,
+        &Vec<FrozenValue>
+    );
     def_value_getter!(
         array_value_mut,
         get_mut,
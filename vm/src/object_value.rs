@@ -9,6 +9,7 @@ use crate::block::Block;
 use crate::external_functions::ExternalFunction;
 use crate::ffi::{Library, Pointer, RcFunction};
 use crate::file::File;
+use crate::frozen::{FrozenObject, FrozenValue};
 use crate::generator::RcGenerator;
 use crate::hasher::Hasher;
 use crate::immutable_string::ImmutableString;
@@ -51,6 +52,20 @@ pub enum ObjectValue {
     /// An Array of bytes, typically produced by reading from a stream of sorts.
     ByteArray(Box<Vec<u8>>),
 
+    /// A ByteArray that can no longer be modified. Frozen byte arrays use an
+    /// Arc so they can be sent to other processes without requiring a full
+    /// copy of the data.
+    FrozenByteArray(ArcWithoutWeak<Vec<u8>>),
+
+    /// An Array that can no longer be modified. The values of a frozen Array
+    /// are shared with other processes when the Array is sent as a message.
+    FrozenArray(ArcWithoutWeak<Vec<FrozenValue>>),
+
+    /// A regular object that can no longer be modified. The object's
+    /// attributes are shared with other processes when the object is sent as
+    /// a message.
+    FrozenObject(ArcWithoutWeak<FrozenObject>),
+
     /// A C library opened using the FFI.
     Library(Library),
 
@@ -171,6 +186,9 @@ impl ObjectValue {
     pub fn as_array(&self) -> Result<&Vec<ObjectPointer>, String> {
         match *self {
             ObjectValue::Array(ref val) => Ok(val),
+            ObjectValue::FrozenArray(_) => {
+                Err("as_array called on a frozen array".to_string())
+            }
             _ => Err("as_array called non a non array value".to_string()),
         }
     }
@@ -178,6 +196,9 @@ impl ObjectValue {
     pub fn as_array_mut(&mut self) -> Result<&mut Vec<ObjectPointer>, String> {
         match *self {
             ObjectValue::Array(ref mut val) => Ok(val),
+            ObjectValue::FrozenArray(_) => {
+                Err("A frozen Array can't be modified".to_string())
+            }
             _ => Err("as_array_mut called on a non array".to_string()),
         }
     }
@@ -185,6 +206,7 @@ impl ObjectValue {
     pub fn as_byte_array(&self) -> Result<&Vec<u8>, String> {
         match *self {
             ObjectValue::ByteArray(ref val) => Ok(val),
+            ObjectValue::FrozenByteArray(ref val) => Ok(val),
             _ => {
                 Err("as_byte_array called non a non byte array value"
                     .to_string())
@@ -195,12 +217,57 @@ impl ObjectValue {
     pub fn as_byte_array_mut(&mut self) -> Result<&mut Vec<u8>, String> {
         match *self {
             ObjectValue::ByteArray(ref mut val) => Ok(val),
+            ObjectValue::FrozenByteArray(_) => {
+                Err("A frozen ByteArray can't be modified".to_string())
+            }
             _ => {
                 Err("as_byte_array_mut called on a non byte array".to_string())
             }
         }
     }
 
+    /// Turns a ByteArray into a frozen ByteArray, without copying its bytes.
+    pub fn freeze_byte_array(&mut self) -> Result<(), String> {
+        let bytes = match mem::replace(self, ObjectValue::None) {
+            ObjectValue::ByteArray(bytes) => ArcWithoutWeak::new(*bytes),
+            ObjectValue::FrozenByteArray(bytes) => bytes,
+            value => {
+                *self = value;
+
+                return Err(
+                    "freeze_byte_array called on a non byte array".to_string()
+                );
+            }
+        };
+
+        *self = ObjectValue::FrozenByteArray(bytes);
+
+        Ok(())
+    }
+
+    pub fn is_frozen_byte_array(&self) -> bool {
+        matches!(self, ObjectValue::FrozenByteArray(_))
+    }
+
+    pub fn as_frozen_array(&self) -> Result<&Vec<FrozenValue>, String> {
+        match *self {
+            ObjectValue::FrozenArray(ref val) => Ok(val),
+            _ => {
+                Err("as_frozen_array called on a non frozen array".to_string())
+            }
+        }
+    }
+
+    /// Returns true if this value is a frozen ByteArray, Array, or object.
+    pub fn is_frozen(&self) -> bool {
+        matches!(
+            self,
+            ObjectValue::FrozenByteArray(_)
+                | ObjectValue::FrozenArray(_)
+                | ObjectValue::FrozenObject(_)
+        )
+    }
+
     pub fn as_string(&self) -> Result<&ImmutableString, String> {
         match *self {
             ObjectValue::String(ref val) => Ok(val),
@@ -414,6 +481,9 @@ impl ObjectValue {
             | ObjectValue::InternedString(_)
             | ObjectValue::Hasher(_)
             | ObjectValue::ByteArray(_)
+            | ObjectValue::FrozenByteArray(_)
+            | ObjectValue::FrozenArray(_)
+            | ObjectValue::FrozenObject(_)
             | ObjectValue::Library(_)
             | ObjectValue::Function(_)
             | ObjectValue::Pointer(_)
@@ -428,17 +498,19 @@ impl ObjectValue {
 
     pub fn name(&self) -> &str {
         match *self {
-            ObjectValue::None => "Object",
+            ObjectValue::None | ObjectValue::FrozenObject(_) => "Object",
             ObjectValue::Float(_) => "Float",
             ObjectValue::String(_) | ObjectValue::InternedString(_) => "String",
-            ObjectValue::Array(_) => "Array",
+            ObjectValue::Array(_) | ObjectValue::FrozenArray(_) => "Array",
             ObjectValue::File(_) => "File",
             ObjectValue::Block(_) => "Block",
             ObjectValue::Binding(_) => "Binding",
             ObjectValue::BigInt(_) => "BigInteger",
             ObjectValue::Integer(_) => "Integer",
             ObjectValue::Hasher(_) => "Hasher",
-            ObjectValue::ByteArray(_) => "ByteArray",
+            ObjectValue::ByteArray(_) | ObjectValue::FrozenByteArray(_) => {
+                "ByteArray"
+            }
             ObjectValue::Library(_) => "Library",
             ObjectValue::Function(_) => "Function",
             ObjectValue::Pointer(_) => "Pointer",
@@ -875,6 +947,15 @@ mod tests {
         assert_eq!(result.unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_as_byte_array_with_frozen_byte_array() {
+        let value = ObjectValue::FrozenByteArray(ArcWithoutWeak::new(vec![1]));
+        let result = value.as_byte_array();
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().len(), 1);
+    }
+
     #[test]
     fn test_as_byte_array_mut_without_byte_array() {
         assert!(ObjectValue::None.as_byte_array_mut().is_err());
@@ -890,6 +971,34 @@ mod tests {
         assert_eq!(result.unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_as_byte_array_mut_with_frozen_byte_array() {
+        let mut value =
+            ObjectValue::FrozenByteArray(ArcWithoutWeak::new(vec![1]));
+
+        assert!(value.as_byte_array_mut().is_err());
+    }
+
+    #[test]
+    fn test_freeze_byte_array() {
+        let mut value = byte_array(vec![1, 2]);
+
+        assert!(value.freeze_byte_array().is_ok());
+        assert!(value.is_frozen_byte_array());
+        assert_eq!(value.as_byte_array().unwrap(), &vec![1, 2]);
+
+        assert!(value.freeze_byte_array().is_ok());
+        assert!(value.is_frozen_byte_array());
+    }
+
+    #[test]
+    fn test_freeze_byte_array_without_byte_array() {
+        let mut value = float(1.5);
+
+        assert!(value.freeze_byte_array().is_err());
+        assert!(value.is_float());
+    }
+
     #[test]
     #[cfg(any(
         target_os = "windows",
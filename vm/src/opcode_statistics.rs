@@ -0,0 +1,117 @@
+//! Counting how often pairs of opcodes are executed.
+//!
+//! Knowing which instructions are often executed one after another helps
+//! deciding which instructions to combine into superinstructions. When enabled,
+//! the interpreter records every pair of opcodes it executes. Superinstructions
+//! are recorded as well, so disabling bytecode optimisations may be needed to
+//! get an accurate picture of the instructions produced by the compiler.
+use crate::vm::instruction::{Opcode, OPCODE_COUNT};
+use std::cmp::Reverse;
+use std::fmt::Write;
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of pairs to include in a report.
+const REPORT_SIZE: usize = 50;
+
+/// The number of times every pair of opcodes has been executed.
+pub struct OpcodeStatistics {
+    /// The counters of all pairs, indexed by the first opcode multiplied by
+    /// the number of opcodes, plus the second opcode.
+    pairs: Vec<AtomicU64>,
+}
+
+impl OpcodeStatistics {
+    pub fn new() -> Self {
+        OpcodeStatistics {
+            pairs: (0..OPCODE_COUNT * OPCODE_COUNT)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+        }
+    }
+
+    /// Records that the second opcode was executed after the first one.
+    pub fn record(&self, first: Opcode, second: Opcode) {
+        self.pairs[first as usize * OPCODE_COUNT + second as usize]
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the given number of pairs that were executed the most, sorted
+    /// from most to least frequent.
+    pub fn most_frequent(&self, amount: usize) -> Vec<(Opcode, Opcode, u64)> {
+        let mut pairs = self
+            .pairs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, counter)| {
+                let count = counter.load(Ordering::Relaxed);
+
+                if count == 0 {
+                    return None;
+                }
+
+                Some((
+                    opcode(index / OPCODE_COUNT),
+                    opcode(index % OPCODE_COUNT),
+                    count,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        pairs.sort_by_key(|pair| Reverse(pair.2));
+        pairs.truncate(amount);
+        pairs
+    }
+
+    /// Returns a human-readable report of the most frequently executed pairs.
+    pub fn report(&self) -> String {
+        let mut report =
+            "Most frequently executed pairs of opcodes:\n\n".to_string();
+
+        for (first, second, count) in self.most_frequent(REPORT_SIZE) {
+            // Writing to a String never fails.
+            let _ =
+                writeln!(report, "{:>12}  {:?} -> {:?}", count, first, second);
+        }
+
+        report
+    }
+}
+
+fn opcode(index: usize) -> Opcode {
+    // The index is always lower than OPCODE_COUNT, making this safe.
+    unsafe { mem::transmute::<u8, Opcode>(index as u8) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_frequent() {
+        let stats = OpcodeStatistics::new();
+
+        stats.record(Opcode::GetLocal, Opcode::GetAttribute);
+        stats.record(Opcode::GetAttribute, Opcode::RunBlockWithReceiver);
+        stats.record(Opcode::GetAttribute, Opcode::RunBlockWithReceiver);
+        stats.record(Opcode::Throw, Opcode::IntegerCompareGotoIfFalse);
+
+        let pairs = stats.most_frequent(2);
+
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(
+            pairs[0],
+            (Opcode::GetAttribute, Opcode::RunBlockWithReceiver, 2)
+        );
+        assert_eq!(pairs[1].2, 1);
+    }
+
+    #[test]
+    fn test_report() {
+        let stats = OpcodeStatistics::new();
+
+        stats.record(Opcode::GetLocal, Opcode::GetAttribute);
+
+        assert!(stats.report().contains("1  GetLocal -> GetAttribute"));
+    }
+}
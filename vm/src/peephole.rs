@@ -0,0 +1,404 @@
+//! Optimising instructions when loading bytecode.
+//!
+//! Many instructions are almost always followed by the same instruction. For
+//! example, a method call looks up the method using `GetAttribute`, then runs
+//! it using `RunBlockWithReceiver`. Every instruction executed has a cost, as
+//! the interpreter has to fetch and dispatch it. To reduce this cost, common
+//! pairs of instructions are combined into a single "superinstruction" when
+//! loading bytecode.
+//!
+//! In addition, `CopyRegister` instructions that have no effect are removed.
+//! This includes instructions that copy a register into itself, and copies
+//! that are identical to the instruction that precedes them.
+//!
+//! Instructions are only combined or removed if this can't change the
+//! behaviour of a program. For example, an instruction jumped to is never
+//! removed, as the jump would otherwise skip instructions it didn't skip
+//! before. After optimising a list of instructions, all jumps and catch table
+//! entries are updated to account for the removed instructions.
+//!
+//! Instructions have a limited number of arguments, so only pairs of
+//! instructions are combined.
+use crate::catch_table::CatchTable;
+use crate::vm::instruction::{Instruction, Opcode, Operand};
+
+/// Optimises a list of instructions and the catch table that belongs to it.
+pub fn optimise(
+    instructions: &mut Vec<Instruction>,
+    catch_table: &mut CatchTable,
+) {
+    let targets = jump_targets(instructions, catch_table);
+    let input = &instructions[..];
+    let mut output = Vec::with_capacity(input.len());
+
+    // The new index of every instruction, including the index directly after
+    // the last instruction.
+    let mut indexes = Vec::with_capacity(input.len() + 1);
+    let mut index = 0;
+
+    while index < input.len() {
+        let current = &input[index];
+
+        indexes.push(output.len());
+        index += 1;
+
+        if !targets[index - 1] && redundant_copy(current, output.last()) {
+            continue;
+        }
+
+        let fused = if index < input.len() && !targets[index] {
+            fuse(current, &input[index], input.get(index + 1))
+        } else {
+            None
+        };
+
+        if let Some(fused) = fused {
+            output.push(fused);
+            indexes.push(output.len());
+            index += 1;
+        } else {
+            output.push(current.clone());
+        }
+    }
+
+    indexes.push(output.len());
+
+    let new_index = |old: usize| indexes.get(old).cloned().unwrap_or(old);
+
+    for instruction in &mut output {
+        each_jump_argument(instruction.opcode, |argument| {
+            let target = new_index(instruction.arg(argument) as usize);
+
+            instruction.set_arg(argument, target as u16);
+        });
+    }
+
+    for entry in &mut catch_table.entries {
+        entry.start = new_index(entry.start);
+        entry.end = new_index(entry.end);
+        entry.jump_to = new_index(entry.jump_to);
+    }
+
+    *instructions = output;
+}
+
+/// Returns a list of flags that indicate which instruction indexes are jumped
+/// to, or are used as the boundary of a catch table entry.
+fn jump_targets(
+    instructions: &[Instruction],
+    catch_table: &CatchTable,
+) -> Vec<bool> {
+    let mut targets = vec![false; instructions.len() + 1];
+    let mut mark = |index: usize| {
+        if let Some(target) = targets.get_mut(index) {
+            *target = true;
+        }
+    };
+
+    for instruction in instructions {
+        each_jump_argument(instruction.opcode, |argument| {
+            mark(instruction.arg(argument) as usize);
+        });
+    }
+
+    for entry in &catch_table.entries {
+        mark(entry.start);
+        mark(entry.end);
+        mark(entry.jump_to);
+    }
+
+    targets
+}
+
+/// Calls the closure with the index of every argument that stores the target
+/// of a jump.
+fn each_jump_argument<F>(opcode: Opcode, mut callback: F)
+where
+    F: FnMut(usize),
+{
+    let mut argument = 0;
+
+    for operand in opcode.operands() {
+        if *operand == Operand::Jump {
+            callback(argument);
+        }
+
+        argument += operand.width();
+    }
+}
+
+/// Returns true if an instruction is a `CopyRegister` that has no effect.
+fn redundant_copy(
+    instruction: &Instruction,
+    previous: Option<&Instruction>,
+) -> bool {
+    if instruction.opcode != Opcode::CopyRegister {
+        return false;
+    }
+
+    instruction.arg(0) == instruction.arg(1)
+        || previous.map(|prev| prev == instruction).unwrap_or(false)
+}
+
+/// Combines two instructions into a superinstruction, if possible.
+///
+/// The instruction that follows the two instructions is used to decide which
+/// pair to combine when a sequence of three instructions can be combined in
+/// two different ways.
+fn fuse(
+    first: &Instruction,
+    second: &Instruction,
+    third: Option<&Instruction>,
+) -> Option<Instruction> {
+    let (opcode, arguments) = match (first.opcode, second.opcode) {
+        (Opcode::GetLocal, Opcode::GetAttribute)
+            if second.arg(1) == first.arg(0)
+                && !third
+                    .map(|third| method_call(second, third))
+                    .unwrap_or(false) =>
+        {
+            (
+                Opcode::GetLocalGetAttribute,
+                [
+                    second.arg(0),
+                    second.arg(1),
+                    second.arg(2),
+                    0,
+                    first.arg(1),
+                    0,
+                ],
+            )
+        }
+        (Opcode::GetAttribute, Opcode::RunBlockWithReceiver)
+            if method_call(first, second) =>
+        {
+            (
+                Opcode::GetAttributeRunBlockWithReceiver,
+                [
+                    first.arg(0),
+                    first.arg(1),
+                    first.arg(2),
+                    0,
+                    second.arg(2),
+                    second.arg(3),
+                ],
+            )
+        }
+        (Opcode::IntegerSmaller, Opcode::GotoIfFalse)
+        | (Opcode::IntegerSmallerOrEqual, Opcode::GotoIfFalse)
+        | (Opcode::IntegerGreater, Opcode::GotoIfFalse)
+        | (Opcode::IntegerGreaterOrEqual, Opcode::GotoIfFalse)
+        | (Opcode::IntegerEquals, Opcode::GotoIfFalse)
+            if second.arg(1) == first.arg(0) =>
+        {
+            (
+                Opcode::IntegerCompareGotoIfFalse,
+                [
+                    first.arg(0),
+                    first.arg(1),
+                    first.arg(2),
+                    second.arg(0),
+                    first.opcode as u16,
+                    0,
+                ],
+            )
+        }
+        _ => return None,
+    };
+
+    Some(Instruction::new(opcode, arguments, first.line))
+}
+
+/// Returns true if the instructions look up a method and then run it.
+fn method_call(get: &Instruction, run: &Instruction) -> bool {
+    get.opcode == Opcode::GetAttribute
+        && run.opcode == Opcode::RunBlockWithReceiver
+        && run.arg(0) == get.arg(0)
+        && run.arg(1) == get.arg(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catch_table::CatchEntry;
+
+    fn ins(opcode: Opcode, arguments: [u16; 6]) -> Instruction {
+        Instruction::new(opcode, arguments, 1)
+    }
+
+    fn optimised(
+        mut instructions: Vec<Instruction>,
+    ) -> (Vec<Instruction>, CatchTable) {
+        let mut catch_table = CatchTable::new();
+
+        optimise(&mut instructions, &mut catch_table);
+
+        (instructions, catch_table)
+    }
+
+    #[test]
+    fn test_optimise_method_call() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+            ins(Opcode::RunBlockWithReceiver, [0, 1, 3, 2, 0, 0]),
+            ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            instructions[0],
+            ins(Opcode::GetAttributeRunBlockWithReceiver, [0, 1, 2, 0, 3, 2])
+        );
+    }
+
+    #[test]
+    fn test_optimise_method_call_with_different_receiver() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+            ins(Opcode::RunBlockWithReceiver, [0, 4, 3, 2, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].opcode, Opcode::GetAttribute);
+    }
+
+    #[test]
+    fn test_optimise_get_local_get_attribute() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::GetLocal, [1, 4, 0, 0, 0, 0]),
+            ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+            ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            instructions[0],
+            ins(Opcode::GetLocalGetAttribute, [0, 1, 2, 0, 4, 0])
+        );
+    }
+
+    #[test]
+    fn test_optimise_prefers_method_calls() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::GetLocal, [1, 4, 0, 0, 0, 0]),
+            ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+            ins(Opcode::RunBlockWithReceiver, [0, 1, 3, 2, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].opcode, Opcode::GetLocal);
+        assert_eq!(
+            instructions[1].opcode,
+            Opcode::GetAttributeRunBlockWithReceiver
+        );
+    }
+
+    #[test]
+    fn test_optimise_integer_comparison() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::IntegerSmaller, [2, 0, 1, 0, 0, 0]),
+            ins(Opcode::GotoIfFalse, [3, 2, 0, 0, 0, 0]),
+            ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ins(Opcode::Return, [0, 1, 0, 0, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(
+            instructions[0],
+            ins(
+                Opcode::IntegerCompareGotoIfFalse,
+                [2, 0, 1, 2, Opcode::IntegerSmaller as u16, 0]
+            )
+        );
+    }
+
+    #[test]
+    fn test_optimise_removes_redundant_copies() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::CopyRegister, [0, 0, 0, 0, 0, 0]),
+            ins(Opcode::CopyRegister, [0, 1, 0, 0, 0, 0]),
+            ins(Opcode::CopyRegister, [0, 1, 0, 0, 0, 0]),
+            ins(Opcode::CopyRegister, [1, 0, 0, 0, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            instructions[0],
+            ins(Opcode::CopyRegister, [0, 1, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            instructions[1],
+            ins(Opcode::CopyRegister, [1, 0, 0, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn test_optimise_keeps_jump_targets() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::CopyRegister, [0, 1, 0, 0, 0, 0]),
+            ins(Opcode::CopyRegister, [0, 1, 0, 0, 0, 0]),
+            ins(Opcode::IntegerSmaller, [2, 0, 1, 0, 0, 0]),
+            ins(Opcode::GotoIfFalse, [1, 2, 0, 0, 0, 0]),
+            ins(Opcode::Goto, [3, 0, 0, 0, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(instructions[3].opcode, Opcode::GotoIfFalse);
+    }
+
+    #[test]
+    fn test_optimise_updates_jumps() {
+        let (instructions, _) = optimised(vec![
+            ins(Opcode::CopyRegister, [0, 0, 0, 0, 0, 0]),
+            ins(Opcode::IntegerEquals, [2, 0, 1, 0, 0, 0]),
+            ins(Opcode::GotoIfFalse, [4, 2, 0, 0, 0, 0]),
+            ins(Opcode::Goto, [1, 0, 0, 0, 0, 0]),
+            ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+        ]);
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[0].opcode, Opcode::IntegerCompareGotoIfFalse);
+        assert_eq!(instructions[0].arg(3), 2);
+        assert_eq!(instructions[1], ins(Opcode::Goto, [0, 0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_optimise_updates_catch_table() {
+        let mut instructions = vec![
+            ins(Opcode::CopyRegister, [0, 0, 0, 0, 0, 0]),
+            ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+            ins(Opcode::RunBlockWithReceiver, [0, 1, 3, 2, 0, 0]),
+            ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+            ins(Opcode::Return, [0, 1, 0, 0, 0, 0]),
+        ];
+        let mut catch_table = CatchTable::new();
+
+        catch_table.entries.push(CatchEntry::new(1, 4, 4));
+
+        optimise(&mut instructions, &mut catch_table);
+
+        let entry = &catch_table.entries[0];
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(entry.start, 0);
+        assert_eq!(entry.end, 2);
+        assert_eq!(entry.jump_to, 2);
+    }
+
+    #[test]
+    fn test_optimise_keeps_catch_table_boundaries() {
+        let mut instructions = vec![
+            ins(Opcode::GetAttribute, [0, 1, 2, 0, 0, 0]),
+            ins(Opcode::RunBlockWithReceiver, [0, 1, 3, 2, 0, 0]),
+            ins(Opcode::Return, [0, 0, 0, 0, 0, 0]),
+        ];
+        let mut catch_table = CatchTable::new();
+
+        catch_table.entries.push(CatchEntry::new(1, 2, 2));
+
+        optimise(&mut instructions, &mut catch_table);
+
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(instructions[0].opcode, Opcode::GetAttribute);
+    }
+}
@@ -2,12 +2,20 @@ use crate::arc_without_weak::ArcWithoutWeak;
 use crate::block::Block;
 use crate::config::Config;
 use crate::execution_context::ExecutionContext;
+use crate::frozen::FrozenValue;
+use crate::gc::statistics::{
+    AtomicGcStatistics, CollectionStatistics, GcStatistics,
+};
 use crate::generator::{Generator, RcGenerator};
+use crate::immix::block::BLOCK_SIZE;
 use crate::immix::block_list::BlockList;
 use crate::immix::copy_object::CopyObject;
 use crate::immix::global_allocator::RcGlobalAllocator;
-use crate::immix::local_allocator::LocalAllocator;
-use crate::mailbox::Mailbox;
+use crate::immix::local_allocator::{
+    HeapLimitPolicy, HeapStatistics, LocalAllocator,
+};
+use crate::io_pool::IoResult;
+use crate::mailbox::{Mailbox, OverflowPolicy};
 use crate::object_pointer::{ObjectPointer, ObjectPointerPointer};
 use crate::object_value;
 use crate::process_status::ProcessStatus;
@@ -19,12 +27,14 @@ use num_bigint::BigInt;
 use num_traits::FromPrimitive;
 use parking_lot::Mutex;
 use std::cell::UnsafeCell;
+use std::fs::File;
 use std::i64;
 use std::mem;
 use std::ops::Drop;
 use std::panic::RefUnwindSafe;
 use std::ptr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::time::Instant;
 
 pub type RcProcess = ArcWithoutWeak<Process>;
 
@@ -45,6 +55,95 @@ pub enum RescheduleRights {
     AcquiredWithTimeout(ArcWithoutWeak<Timeout>),
 }
 
+/// The reason a process terminated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitReason {
+    /// The process finished running its code.
+    Normal,
+
+    /// The process panicked, producing the given stack trace and panic
+    /// message.
+    Panic(String),
+}
+
+/// The priority of a process, used when scheduling it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Priority {
+    /// The process is scheduled like a regular process, but may only perform
+    /// half the usual number of reductions before being suspended.
+    Low,
+
+    /// The default priority of a process.
+    Normal,
+
+    /// The process is scheduled before processes with a lower priority, and
+    /// may perform twice the usual number of reductions before being
+    /// suspended.
+    High,
+}
+
+impl Priority {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Priority::Low),
+            1 => Some(Priority::Normal),
+            2 => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Priority::Low => 0,
+            Priority::Normal => 1,
+            Priority::High => 2,
+        }
+    }
+
+    /// Returns the number of reductions a process with this priority can
+    /// perform, based on the configured number of reductions.
+    pub fn reductions(self, reductions: usize) -> usize {
+        match self {
+            Priority::Low => (reductions / 2).max(1),
+            Priority::Normal => reductions,
+            Priority::High => reductions.saturating_mul(2),
+        }
+    }
+}
+
+/// The monitors and links of a process.
+#[derive(Default)]
+pub struct Watchers {
+    /// The processes monitoring this process.
+    ///
+    /// A process may be included more than once, if it monitors this process
+    /// multiple times.
+    monitors: Vec<RcProcess>,
+
+    /// The processes monitored by this process.
+    ///
+    /// When this process terminates, it's removed from the monitors of these
+    /// processes.
+    monitoring: Vec<RcProcess>,
+
+    /// The processes linked to this process.
+    ///
+    /// Links are bidirectional, so this process is also included in the links
+    /// of these processes.
+    links: Vec<RcProcess>,
+
+    /// A boolean indicating if a panic in a linked process should only produce
+    /// an exit message, instead of also terminating this process.
+    trap_exits: bool,
+
+    /// The identifier of a linked process that panicked, set when this process
+    /// doesn't trap exits.
+    linked_panic: Option<usize>,
+
+    /// The reason the process terminated, set once it terminates.
+    exit_reason: Option<ExitReason>,
+}
+
 impl RescheduleRights {
     pub fn are_acquired(&self) -> bool {
         match self {
@@ -79,6 +178,19 @@ pub struct LocalData {
 
     /// The status of the process.
     status: ProcessStatus,
+
+    /// The point in time at which a selective receive with a timeout expires.
+    ///
+    /// A process waiting for a specific message may be woken up by messages
+    /// that don't match. Storing the deadline ensures the timeout isn't reset
+    /// every time this happens.
+    receive_deadline: Option<Instant>,
+
+    /// The file to write a heap snapshot to after the next collection.
+    heap_snapshot: Option<File>,
+
+    /// The number of heap snapshot signals this process responded to.
+    heap_snapshot_signals: usize,
 }
 
 pub struct Process {
@@ -106,6 +218,31 @@ pub struct Process {
     /// operations and tagging, something which isn't possible using an
     /// `Option<T>`.
     suspended: TaggedPointer<Timeout>,
+
+    /// The processes monitoring or linked to this process.
+    watchers: Mutex<Watchers>,
+
+    /// The priority of this process, stored as the value of a `Priority`.
+    ///
+    /// The priority is stored atomically as it may be changed by another
+    /// process, while the scheduler reads it when rescheduling this process.
+    priority: AtomicU8,
+
+    /// The result of a file operation performed by the I/O pool.
+    ///
+    /// The result is stored by an I/O thread, so it can't be stored in the
+    /// LocalData structure.
+    io_result: Mutex<Option<IoResult>>,
+
+    /// The total number of reductions performed by this process.
+    ///
+    /// This counter is only updated by the thread running the process, but
+    /// other processes may read it when requesting statistics about this
+    /// process.
+    reductions: AtomicUsize,
+
+    /// The statistics of all garbage collections of this process.
+    gc_statistics: AtomicGcStatistics,
 }
 
 unsafe impl Sync for LocalData {}
@@ -125,12 +262,20 @@ impl Process {
             thread_id: None,
             mailbox: Mutex::new(Mailbox::new()),
             status: ProcessStatus::new(),
+            receive_deadline: None,
+            heap_snapshot: None,
+            heap_snapshot_signals: 0,
         };
 
         ArcWithoutWeak::new(Process {
             local_data: UnsafeCell::new(local_data),
             waiting_for_message: AtomicBool::new(false),
             suspended: TaggedPointer::null(),
+            watchers: Mutex::new(Watchers::default()),
+            priority: AtomicU8::new(Priority::Normal.to_u8()),
+            io_result: Mutex::new(None),
+            reductions: AtomicUsize::new(0),
+            gc_statistics: AtomicGcStatistics::new(),
         })
     }
 
@@ -173,15 +318,37 @@ impl Process {
     }
 
     pub fn set_thread_id(&self, id: u8) {
-        self.local_data_mut().thread_id = Some(id);
+        let local_data = self.local_data_mut();
+
+        local_data.thread_id = Some(id);
+        local_data.status.set_pinned(true);
     }
 
     pub fn unset_thread_id(&self) {
-        self.local_data_mut().thread_id = None;
+        let local_data = self.local_data_mut();
+
+        local_data.thread_id = None;
+        local_data.status.set_pinned(false);
     }
 
+    /// Returns true if the process is pinned to an OS thread.
+    ///
+    /// Unlike `thread_id()`, this method can be used by other processes.
     pub fn is_pinned(&self) -> bool {
-        self.thread_id().is_some()
+        self.local_data().status.is_pinned()
+    }
+
+    pub fn priority(&self) -> Priority {
+        Priority::from_u8(self.priority.load(Ordering::Acquire))
+            .unwrap_or(Priority::Normal)
+    }
+
+    pub fn set_priority(&self, priority: Priority) {
+        self.priority.store(priority.to_u8(), Ordering::Release);
+    }
+
+    pub fn is_high_priority(&self) -> bool {
+        self.priority() == Priority::High
     }
 
     pub fn suspend_with_timeout(&self, timeout: ArcWithoutWeak<Timeout>) {
@@ -198,6 +365,11 @@ impl Process {
         self.suspended.atomic_store(tagged);
     }
 
+    /// Returns true if the process is suspended, with or without a timeout.
+    pub fn is_suspended(&self) -> bool {
+        !self.suspended.atomic_load().is_null()
+    }
+
     pub fn is_suspended_with_timeout(
         &self,
         timeout: &ArcWithoutWeak<Timeout>,
@@ -374,6 +546,11 @@ impl Process {
         local_data.allocator.allocate_without_prototype(value)
     }
 
+    /// Allocates the object of a value stored in a frozen Array or object.
+    pub fn allocate_frozen(&self, value: &FrozenValue) -> ObjectPointer {
+        value.allocate(&mut self.local_data_mut().allocator)
+    }
+
     pub fn send_message_from_external_process(
         &self,
         message_to_copy: ObjectPointer,
@@ -395,6 +572,49 @@ impl Process {
         Ok(())
     }
 
+    /// Sends a message from another process, taking into account the capacity
+    /// of the mailbox.
+    ///
+    /// If the mailbox is full and the sender has to wait, the sender is
+    /// suspended and `false` is returned. The sender is rescheduled once a
+    /// message is received, after which it should try sending the message
+    /// again.
+    pub fn send_bounded_message_from_external_process(
+        &self,
+        sender: &RcProcess,
+        message_to_copy: ObjectPointer,
+    ) -> Result<bool, RuntimeError> {
+        let local_data = self.local_data_mut();
+        let mut mailbox = local_data.mailbox.lock();
+
+        if self.is_terminated() {
+            return Ok(true);
+        }
+
+        if mailbox.is_full() {
+            match mailbox.overflow_policy() {
+                OverflowPolicy::Suspend => {
+                    // The sender must be suspended before releasing the lock,
+                    // otherwise the receiver may fail to reschedule it.
+                    sender.suspend_without_timeout();
+                    mailbox.add_blocked_sender(sender.clone());
+
+                    return Ok(false);
+                }
+                OverflowPolicy::DropOldest => mailbox.drop_oldest(),
+                OverflowPolicy::Error => {
+                    return Err(RuntimeError::ErrorMessage(
+                        "The mailbox of the receiving process is full"
+                            .to_string(),
+                    ));
+                }
+            }
+        }
+
+        mailbox.send(local_data.allocator.copy_object(message_to_copy)?);
+        Ok(true)
+    }
+
     pub fn send_message_from_self(&self, message: ObjectPointer) {
         self.local_data_mut().mailbox.lock().send(message);
     }
@@ -403,6 +623,92 @@ impl Process {
         self.local_data_mut().mailbox.lock().receive()
     }
 
+    pub fn receive_matching_message<F>(
+        &self,
+        matcher: F,
+    ) -> Option<ObjectPointer>
+    where
+        F: FnMut(ObjectPointer) -> bool,
+    {
+        self.local_data_mut()
+            .mailbox
+            .lock()
+            .receive_matching(matcher)
+    }
+
+    pub fn take_blocked_sender(&self) -> Option<RcProcess> {
+        self.local_data_mut().mailbox.lock().take_blocked_sender()
+    }
+
+    pub fn set_mailbox_capacity(
+        &self,
+        capacity: Option<usize>,
+        overflow_policy: OverflowPolicy,
+    ) {
+        self.local_data_mut()
+            .mailbox
+            .lock()
+            .set_capacity(capacity, overflow_policy);
+    }
+
+    pub fn mailbox_length(&self) -> usize {
+        self.local_data().mailbox.lock().len()
+    }
+
+    /// Returns statistics about the heap of this process.
+    ///
+    /// These statistics can be safely read while the process is running.
+    pub fn heap_statistics(&self) -> &HeapStatistics {
+        &self.local_data().allocator.statistics
+    }
+
+    /// Returns the number of memory blocks used by the heap of this process.
+    pub fn heap_blocks(&self) -> usize {
+        self.young_blocks() + self.mature_blocks()
+    }
+
+    /// Returns the number of memory blocks used by the young generation.
+    pub fn young_blocks(&self) -> usize {
+        self.heap_statistics().young_blocks()
+    }
+
+    /// Returns the number of memory blocks used by the mature generation.
+    pub fn mature_blocks(&self) -> usize {
+        self.heap_statistics().mature_blocks()
+    }
+
+    pub fn gc_statistics(&self) -> GcStatistics {
+        self.gc_statistics.load()
+    }
+
+    pub fn record_gc_statistics(&self, stats: &CollectionStatistics) {
+        self.gc_statistics.record(stats);
+    }
+
+    pub fn reductions(&self) -> usize {
+        self.reductions.load(Ordering::Relaxed)
+    }
+
+    pub fn increment_reductions(&self, amount: usize) {
+        // Only the thread running this process updates the counter, so there's
+        // no need for an atomic read-modify-write operation.
+        let reductions = self.reductions().saturating_add(amount);
+
+        self.reductions.store(reductions, Ordering::Relaxed);
+    }
+
+    pub fn receive_deadline(&self) -> Option<Instant> {
+        self.local_data().receive_deadline
+    }
+
+    pub fn set_receive_deadline(&self, deadline: Instant) {
+        self.local_data_mut().receive_deadline = Some(deadline);
+    }
+
+    pub fn unset_receive_deadline(&self) {
+        self.local_data_mut().receive_deadline = None;
+    }
+
     pub fn context(&self) -> &ExecutionContext {
         self.local_data().generator.context()
     }
@@ -412,10 +718,85 @@ impl Process {
         self.local_data_mut().generator.context_mut()
     }
 
+    pub fn set_io_result(&self, result: IoResult) {
+        *self.io_result.lock() = Some(result);
+    }
+
+    /// Returns the result of the last file operation, if it finished.
+    pub fn take_io_result(&self) -> Option<IoResult> {
+        self.io_result.lock().take()
+    }
+
     pub fn has_messages(&self) -> bool {
         self.local_data().mailbox.lock().has_messages()
     }
 
+    /// Requests a garbage collection of this process.
+    ///
+    /// The collection is performed the next time the process reaches a GC
+    /// safepoint. If `mature` is true, the mature generation is collected as
+    /// well.
+    pub fn request_collection(&self, mature: bool) {
+        self.local_data_mut().allocator.request_collection(mature);
+    }
+
+    /// Requests a heap snapshot of this process.
+    ///
+    /// The snapshot is written to the file after the next collection, which is
+    /// a full collection requested by this method.
+    pub fn request_heap_snapshot(&self, file: File) {
+        self.local_data_mut().heap_snapshot = Some(file);
+        self.request_collection(true);
+    }
+
+    /// Returns the file to write a requested heap snapshot to, if any.
+    pub fn take_heap_snapshot_request(&self) -> Option<File> {
+        self.local_data_mut().heap_snapshot.take()
+    }
+
+    /// Updates the number of heap snapshot signals this process responded to,
+    /// returning `true` if any new signals were received.
+    pub fn acknowledge_heap_snapshot_signals(&self, received: usize) -> bool {
+        let local_data = self.local_data_mut();
+
+        if received <= local_data.heap_snapshot_signals {
+            return false;
+        }
+
+        local_data.heap_snapshot_signals = received;
+        true
+    }
+
+    pub fn set_heap_limit(&self, bytes: usize, policy: HeapLimitPolicy) {
+        self.local_data_mut()
+            .allocator
+            .set_heap_limit(bytes, policy);
+    }
+
+    pub fn heap_limit_exceeded(&self) -> bool {
+        self.local_data().allocator.heap_limit_exceeded()
+    }
+
+    pub fn heap_limit_exceeded_after_collection(&self) -> bool {
+        self.local_data_mut()
+            .allocator
+            .heap_limit_exceeded_after_collection()
+    }
+
+    /// Returns the error to produce when the heap limit is exceeded.
+    pub fn heap_limit_error(&self) -> RuntimeError {
+        let allocator = &self.local_data().allocator;
+        let message = format!(
+            "The process exceeded its heap limit of {} bytes",
+            allocator.heap_limit * BLOCK_SIZE
+        );
+
+        match allocator.heap_limit_policy {
+            HeapLimitPolicy::Panic => RuntimeError::Panic(message),
+            HeapLimitPolicy::Error => RuntimeError::ErrorMessage(message),
+        }
+    }
+
     pub fn should_collect_young_generation(&self) -> bool {
         self.local_data().allocator.should_collect_young()
     }
@@ -428,6 +809,32 @@ impl Process {
         self.local_data().generator.contexts()
     }
 
+    /// Returns a human readable stack trace of the process, with the most
+    /// recent call coming last.
+    pub fn stacktrace(&self) -> String {
+        let mut frames = Vec::new();
+        let mut buffer = String::new();
+
+        for context in self.contexts() {
+            frames.push(format!(
+                "\"{}\" line {}, in \"{}\"",
+                context.code.file.string_value().unwrap(),
+                context.line().to_string(),
+                context.code.name.string_value().unwrap()
+            ));
+        }
+
+        frames.reverse();
+
+        buffer.push_str("Stack trace (the most recent call comes last):");
+
+        for (index, line) in frames.iter().enumerate() {
+            buffer.push_str(&format!("\n  {}: {}", index, line));
+        }
+
+        buffer
+    }
+
     /// Write barrier for tracking cross generation writes.
     ///
     /// This barrier is based on the Steele write barrier and tracks the object
@@ -471,9 +878,13 @@ impl Process {
         // The mailbox lock _must_ be acquired first, otherwise we may end up
         // reclaiming blocks while another process is allocating message into
         // them.
-        let _mailbox = self.local_data_mut().mailbox.lock();
+        let mut mailbox = self.local_data_mut().mailbox.lock();
         let mut blocks = self.reclaim_all_blocks();
 
+        if !self.is_terminated() {
+            state.scheduler.decrement_live_processes();
+        }
+
         // Once terminated we don't want to receive any messages any more, as
         // they will never be received and thus lead to an increase in memory.
         // Thus, we mark the process as terminated. We must do this _after_
@@ -487,6 +898,64 @@ impl Process {
         }
 
         state.global_allocator.add_blocks(&mut blocks);
+        self.remove_from_watched_processes();
+
+        // Senders waiting for room in our mailbox are rescheduled, allowing
+        // them to observe that we terminated.
+        for sender in mailbox.take_blocked_senders() {
+            if sender.acquire_rescheduling_rights().are_acquired() {
+                state.scheduler.schedule(sender);
+            }
+        }
+    }
+
+    /// Sets whether panics in linked processes are only reported using an
+    /// exit message, instead of also terminating this process.
+    pub fn set_trap_exits(&self, enable: bool) {
+        self.watchers.lock().trap_exits = enable;
+    }
+
+    pub fn traps_exits(&self) -> bool {
+        self.watchers.lock().trap_exits
+    }
+
+    /// Returns the panic message to use if a linked process panicked, and this
+    /// process should terminate as a result.
+    pub fn take_linked_panic(&self) -> Option<String> {
+        let status = &self.local_data().status;
+
+        if !status.is_linked_panic() {
+            return None;
+        }
+
+        status.set_linked_panic(false);
+
+        self.watchers
+            .lock()
+            .linked_panic
+            .take()
+            .map(|id| format!("The linked process {:#x} panicked", id))
+    }
+
+    /// Removes this process from the watchers of the processes it monitors or
+    /// is linked to.
+    fn remove_from_watched_processes(&self) {
+        let (monitoring, links) = {
+            let mut watchers = self.watchers.lock();
+
+            (
+                watchers.monitoring.drain(..).collect::<Vec<_>>(),
+                watchers.links.drain(..).collect::<Vec<_>>(),
+            )
+        };
+
+        for target in monitoring {
+            remove_process(&mut target.watchers.lock().monitors, self);
+        }
+
+        for link in links {
+            remove_process(&mut link.watchers.lock().links, self);
+        }
     }
 
     pub fn each_global_pointer<F>(&self, mut callback: F)
@@ -550,6 +1019,107 @@ impl RcProcess {
     pub fn identifier(&self) -> usize {
         self.as_ptr() as usize
     }
+
+    /// Monitors another process from this process.
+    ///
+    /// If the target already terminated the monitor isn't added, and the
+    /// reason the target terminated is returned instead.
+    pub fn monitor(&self, target: &RcProcess) -> Option<ExitReason> {
+        // The monitor is recorded on our end first, so a target terminating
+        // concurrently always finds it when removing itself.
+        self.watchers.lock().monitoring.push(target.clone());
+
+        let reason = {
+            let mut watchers = target.watchers.lock();
+
+            if watchers.exit_reason.is_none() {
+                watchers.monitors.push(self.clone());
+            }
+
+            watchers.exit_reason.clone()
+        };
+
+        if reason.is_some() {
+            remove_process(&mut self.watchers.lock().monitoring, target);
+        }
+
+        reason
+    }
+
+    /// Removes a single monitor of another process.
+    pub fn demonitor(&self, target: &RcProcess) {
+        remove_process(&mut target.watchers.lock().monitors, self);
+        remove_process(&mut self.watchers.lock().monitoring, target);
+    }
+
+    /// Links this process and another process.
+    ///
+    /// If the target already terminated the link isn't added, and the reason
+    /// the target terminated is returned instead.
+    pub fn link(&self, target: &RcProcess) -> Option<ExitReason> {
+        self.watchers.lock().links.push(target.clone());
+
+        let reason = {
+            let mut watchers = target.watchers.lock();
+
+            if watchers.exit_reason.is_none() {
+                watchers.links.push(self.clone());
+            }
+
+            watchers.exit_reason.clone()
+        };
+
+        if reason.is_some() {
+            remove_process(&mut self.watchers.lock().links, target);
+        }
+
+        reason
+    }
+
+    /// Removes a single link between this process and another process.
+    pub fn unlink(&self, target: &RcProcess) {
+        remove_process(&mut target.watchers.lock().links, self);
+        remove_process(&mut self.watchers.lock().links, target);
+    }
+
+    /// Records the reason this process terminated, returning the processes to
+    /// notify.
+    ///
+    /// Linked processes that don't trap exits are also marked for termination
+    /// if this process panicked. Once called, monitors and links added later
+    /// are no longer stored.
+    pub fn take_watchers(&self, reason: ExitReason) -> Vec<RcProcess> {
+        let (monitors, links) = {
+            let mut watchers = self.watchers.lock();
+
+            watchers.exit_reason = Some(reason.clone());
+
+            (
+                watchers.monitors.drain(..).collect::<Vec<_>>(),
+                watchers.links.drain(..).collect::<Vec<_>>(),
+            )
+        };
+
+        let panicked = matches!(reason, ExitReason::Panic(_));
+
+        for monitor in &monitors {
+            remove_process(&mut monitor.watchers.lock().monitoring, self);
+        }
+
+        for link in &links {
+            let mut watchers = link.watchers.lock();
+
+            remove_process(&mut watchers.links, self);
+
+            if panicked && !watchers.trap_exits {
+                watchers.linked_panic = Some(self.identifier());
+
+                link.local_data().status.set_linked_panic(true);
+            }
+        }
+
+        monitors.into_iter().chain(links).collect()
+    }
 }
 
 impl PartialEq for RcProcess {
@@ -560,13 +1130,26 @@ impl PartialEq for RcProcess {
 
 impl Eq for RcProcess {}
 
+/// Removes a single occurrence of a process from a list of processes.
+fn remove_process(processes: &mut Vec<RcProcess>, process: &Process) {
+    if let Some(index) =
+        processes.iter().position(|proc| ptr::eq(&**proc, process))
+    {
+        processes.remove(index);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::io_pool::IoOutput;
     use crate::object_value;
+    use crate::vm::machine::Machine;
     use crate::vm::test::setup;
     use num_bigint::BigInt;
+    use std::env;
     use std::f64;
+    use std::fs;
     use std::i32;
     use std::i64;
     use std::mem;
@@ -578,6 +1161,32 @@ mod tests {
         assert_eq!(process.contexts().len(), 1);
     }
 
+    #[test]
+    fn test_stacktrace() {
+        let (_machine, _block, process) = setup();
+
+        assert_eq!(
+            process.stacktrace(),
+            "Stack trace (the most recent call comes last):\n  \
+             0: \"a\" line 1, in \"a\""
+        );
+    }
+
+    #[test]
+    fn test_is_suspended() {
+        let (_machine, _block, process) = setup();
+
+        assert_eq!(process.is_suspended(), false);
+
+        process.suspend_without_timeout();
+
+        assert!(process.is_suspended());
+
+        process.acquire_rescheduling_rights();
+
+        assert_eq!(process.is_suspended(), false);
+    }
+
     #[test]
     fn test_reclaim_blocks_without_mature() {
         let (machine, _block, process) = setup();
@@ -636,7 +1245,7 @@ mod tests {
         assert!(received.is_young());
         assert!(received.get().value.is_integer());
         assert!(received.get().prototype().is_some());
-        assert!(received.get().attributes_map().is_some());
+        assert!(received.get().attributes_ref().is_some());
         assert!(received.is_finalizable());
         assert!(received.raw.raw != input_message.raw.raw);
     }
@@ -654,6 +1263,121 @@ mod tests {
         assert!(process.receive_message().is_none());
     }
 
+    #[test]
+    fn test_send_message_from_external_process_with_frozen_byte_array() {
+        let (machine, block, process) = setup();
+        let sender = Process::from_block(
+            &block,
+            machine.state.global_allocator.clone(),
+            &machine.state.config,
+        );
+        let mut value = object_value::byte_array(vec![1, 2]);
+
+        value.freeze_byte_array().unwrap();
+
+        let message = sender.allocate_without_prototype(value);
+
+        process.send_message_from_external_process(message).unwrap();
+
+        let received = process.receive_message().unwrap();
+
+        assert!(received != message);
+        assert!(received.get().value.is_frozen_byte_array());
+        assert_eq!(
+            received.byte_array_value().unwrap().as_ptr(),
+            message.byte_array_value().unwrap().as_ptr()
+        );
+    }
+
+    #[test]
+    fn test_send_bounded_message_with_room() {
+        let (machine, block, process) = setup();
+        let sender = Process::from_block(
+            &block,
+            machine.state.global_allocator.clone(),
+            &machine.state.config,
+        );
+
+        process.set_mailbox_capacity(Some(1), OverflowPolicy::Error);
+
+        let sent = process
+            .send_bounded_message_from_external_process(
+                &sender,
+                ObjectPointer::integer(1),
+            )
+            .unwrap();
+
+        assert!(sent);
+        assert_eq!(process.mailbox_length(), 1);
+    }
+
+    #[test]
+    fn test_send_bounded_message_with_error_policy() {
+        let (machine, block, process) = setup();
+        let sender = Process::from_block(
+            &block,
+            machine.state.global_allocator.clone(),
+            &machine.state.config,
+        );
+
+        process.set_mailbox_capacity(Some(1), OverflowPolicy::Error);
+        process.send_message_from_self(ObjectPointer::integer(1));
+
+        let result = process.send_bounded_message_from_external_process(
+            &sender,
+            ObjectPointer::integer(2),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(process.mailbox_length(), 1);
+    }
+
+    #[test]
+    fn test_send_bounded_message_with_drop_oldest_policy() {
+        let (machine, block, process) = setup();
+        let sender = Process::from_block(
+            &block,
+            machine.state.global_allocator.clone(),
+            &machine.state.config,
+        );
+
+        process.set_mailbox_capacity(Some(1), OverflowPolicy::DropOldest);
+        process.send_message_from_self(ObjectPointer::integer(1));
+        process
+            .send_bounded_message_from_external_process(
+                &sender,
+                ObjectPointer::integer(2),
+            )
+            .unwrap();
+
+        assert!(process.receive_message() == Some(ObjectPointer::integer(2)));
+    }
+
+    #[test]
+    fn test_send_bounded_message_with_suspend_policy() {
+        let (machine, block, process) = setup();
+        let sender = Process::from_block(
+            &block,
+            machine.state.global_allocator.clone(),
+            &machine.state.config,
+        );
+
+        process.set_mailbox_capacity(Some(1), OverflowPolicy::Suspend);
+        process.send_message_from_self(ObjectPointer::integer(1));
+
+        let sent = process
+            .send_bounded_message_from_external_process(
+                &sender,
+                ObjectPointer::integer(2),
+            )
+            .unwrap();
+
+        assert_eq!(sent, false);
+        assert_eq!(process.mailbox_length(), 1);
+        assert!(process.take_blocked_sender() == Some(sender.clone()));
+        assert!(sender.acquire_rescheduling_rights().are_acquired());
+    }
+
     #[test]
     fn test_allocate_f64_as_i64_with_a_small_float() {
         let (machine, _block, process) = setup();
@@ -726,7 +1450,7 @@ mod tests {
     fn test_process_type_size() {
         // This test is put in place to ensure the type size doesn't change
         // unintentionally.
-        assert_eq!(mem::size_of::<Process>(), 352);
+        assert_eq!(mem::size_of::<Process>(), 696);
     }
 
     #[test]
@@ -734,14 +1458,106 @@ mod tests {
         let (_machine, _block, process) = setup();
 
         assert!(process.thread_id().is_none());
+        assert!(!process.is_pinned());
 
         process.set_thread_id(4);
 
         assert_eq!(process.thread_id(), Some(4));
+        assert!(process.is_pinned());
 
         process.unset_thread_id();
 
         assert!(process.thread_id().is_none());
+        assert!(!process.is_pinned());
+    }
+
+    #[test]
+    fn test_process_set_priority() {
+        let (_machine, _block, process) = setup();
+
+        assert_eq!(process.priority(), Priority::Normal);
+        assert!(!process.is_high_priority());
+
+        process.set_priority(Priority::High);
+
+        assert_eq!(process.priority(), Priority::High);
+        assert!(process.is_high_priority());
+    }
+
+    #[test]
+    fn test_priority_from_u8() {
+        assert_eq!(Priority::from_u8(0), Some(Priority::Low));
+        assert_eq!(Priority::from_u8(1), Some(Priority::Normal));
+        assert_eq!(Priority::from_u8(2), Some(Priority::High));
+        assert_eq!(Priority::from_u8(3), None);
+    }
+
+    #[test]
+    fn test_priority_reductions() {
+        assert_eq!(Priority::Low.reductions(1000), 500);
+        assert_eq!(Priority::Low.reductions(1), 1);
+        assert_eq!(Priority::Normal.reductions(1000), 1000);
+        assert_eq!(Priority::High.reductions(1000), 2000);
+    }
+
+    #[test]
+    fn test_increment_reductions() {
+        let (_machine, _block, process) = setup();
+
+        process.increment_reductions(10);
+        process.increment_reductions(5);
+
+        assert_eq!(process.reductions(), 15);
+    }
+
+    #[test]
+    fn test_heap_blocks() {
+        let (_machine, _block, process) = setup();
+
+        assert_eq!(process.heap_blocks(), 0);
+
+        process.allocate_empty();
+
+        assert_eq!(process.heap_blocks(), 1);
+        assert_eq!(process.young_blocks(), 1);
+        assert_eq!(process.mature_blocks(), 0);
+    }
+
+    #[test]
+    fn test_request_heap_snapshot() {
+        let (_machine, _block, process) = setup();
+        let path = env::temp_dir().join("inko_test_request_heap_snapshot");
+        let file = File::create(&path).unwrap();
+
+        process.request_heap_snapshot(file);
+
+        assert!(process.should_collect_mature_generation());
+        assert!(process.take_heap_snapshot_request().is_some());
+        assert!(process.take_heap_snapshot_request().is_none());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_acknowledge_heap_snapshot_signals() {
+        let (_machine, _block, process) = setup();
+
+        assert_eq!(process.acknowledge_heap_snapshot_signals(0), false);
+        assert!(process.acknowledge_heap_snapshot_signals(2));
+        assert_eq!(process.acknowledge_heap_snapshot_signals(2), false);
+        assert!(process.acknowledge_heap_snapshot_signals(3));
+    }
+
+    #[test]
+    fn test_io_result() {
+        let (_machine, _block, process) = setup();
+
+        assert!(process.take_io_result().is_none());
+
+        process.set_io_result(Ok(IoOutput::Nil));
+
+        assert!(process.take_io_result().is_some());
+        assert!(process.take_io_result().is_none());
     }
 
     #[test]
@@ -751,6 +1567,138 @@ mod tests {
         assert!(process.identifier() > 0);
     }
 
+    fn other_process(machine: &Machine, block: &Block) -> RcProcess {
+        Process::from_block(
+            block,
+            machine.state.global_allocator.clone(),
+            &machine.state.config,
+        )
+    }
+
+    #[test]
+    fn test_monitor() {
+        let (machine, block, process) = setup();
+        let watcher = other_process(&machine, &block);
+
+        assert!(watcher.monitor(&process).is_none());
+        assert!(watcher.watchers.lock().monitoring == vec![process.clone()]);
+        assert!(
+            process.take_watchers(ExitReason::Normal) == vec![watcher.clone()]
+        );
+        assert!(watcher.watchers.lock().monitoring.is_empty());
+    }
+
+    #[test]
+    fn test_monitor_after_terminating() {
+        let (machine, block, process) = setup();
+        let watcher = other_process(&machine, &block);
+
+        process.take_watchers(ExitReason::Panic("oops".to_string()));
+
+        assert_eq!(
+            watcher.monitor(&process),
+            Some(ExitReason::Panic("oops".to_string()))
+        );
+        assert!(watcher.watchers.lock().monitoring.is_empty());
+        assert!(process.take_watchers(ExitReason::Normal).is_empty());
+    }
+
+    #[test]
+    fn test_demonitor() {
+        let (machine, block, process) = setup();
+        let watcher = other_process(&machine, &block);
+
+        watcher.monitor(&process);
+        watcher.monitor(&process);
+        watcher.demonitor(&process);
+
+        assert_eq!(watcher.watchers.lock().monitoring.len(), 1);
+        assert_eq!(process.take_watchers(ExitReason::Normal).len(), 1);
+    }
+
+    #[test]
+    fn test_terminate_removes_monitors() {
+        let (machine, block, process) = setup();
+        let watcher = other_process(&machine, &block);
+
+        watcher.monitor(&process);
+        watcher.terminate(&machine.state);
+
+        assert!(watcher.watchers.lock().monitoring.is_empty());
+        assert!(process.take_watchers(ExitReason::Normal).is_empty());
+    }
+
+    #[test]
+    fn test_link() {
+        let (machine, block, process) = setup();
+        let linked = other_process(&machine, &block);
+
+        assert!(linked.link(&process).is_none());
+        assert!(
+            process.take_watchers(ExitReason::Normal) == vec![linked.clone()]
+        );
+        assert!(linked.watchers.lock().links.is_empty());
+        assert!(linked.take_linked_panic().is_none());
+    }
+
+    #[test]
+    fn test_link_with_panic() {
+        let (machine, block, process) = setup();
+        let linked = other_process(&machine, &block);
+
+        linked.link(&process);
+        process.take_watchers(ExitReason::Panic("oops".to_string()));
+
+        assert_eq!(
+            linked.take_linked_panic(),
+            Some(format!(
+                "The linked process {:#x} panicked",
+                process.identifier()
+            ))
+        );
+        assert!(linked.take_linked_panic().is_none());
+    }
+
+    #[test]
+    fn test_link_with_panic_when_trapping_exits() {
+        let (machine, block, process) = setup();
+        let linked = other_process(&machine, &block);
+
+        linked.set_trap_exits(true);
+        linked.link(&process);
+
+        assert_eq!(
+            process
+                .take_watchers(ExitReason::Panic("oops".to_string()))
+                .len(),
+            1
+        );
+        assert!(linked.take_linked_panic().is_none());
+    }
+
+    #[test]
+    fn test_unlink() {
+        let (machine, block, process) = setup();
+        let linked = other_process(&machine, &block);
+
+        linked.link(&process);
+        linked.unlink(&process);
+
+        assert!(linked.watchers.lock().links.is_empty());
+        assert!(process.take_watchers(ExitReason::Normal).is_empty());
+    }
+
+    #[test]
+    fn test_terminate_removes_links() {
+        let (machine, block, process) = setup();
+        let linked = other_process(&machine, &block);
+
+        linked.link(&process);
+        linked.terminate(&machine.state);
+
+        assert!(process.watchers.lock().links.is_empty());
+    }
+
     #[test]
     fn test_each_global_pointer() {
         let (_machine, _block, process) = setup();
@@ -8,7 +8,8 @@ use std::sync::atomic::{AtomicU8, Ordering};
 /// enters the blocking status).
 ///
 /// While concurrent reads are allowed, only the owning process should change
-/// the status.
+/// the status. The only exception is the bit indicating a linked process
+/// panicked, which is set by the process that panicked.
 pub struct ProcessStatus {
     /// The bits used to indicate the status of the process.
     ///
@@ -30,6 +31,12 @@ impl ProcessStatus {
     /// The process is terminated.
     const TERMINATED: u8 = 0b100;
 
+    /// The process is pinned to a single OS thread.
+    const PINNED: u8 = 0b1000;
+
+    /// A process linked to this process panicked.
+    const LINKED_PANIC: u8 = 0b10000;
+
     pub fn new() -> Self {
         Self {
             bits: AtomicU8::new(Self::NORMAL),
@@ -60,11 +67,30 @@ impl ProcessStatus {
         self.bit_is_set(Self::TERMINATED)
     }
 
-    fn update_bits(&mut self, mask: u8, enable: bool) {
-        let bits = self.bits.load(Ordering::Acquire);
-        let new_bits = if enable { bits | mask } else { bits & !mask };
+    pub fn set_pinned(&mut self, enable: bool) {
+        self.update_bits(Self::PINNED, enable);
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.bit_is_set(Self::PINNED)
+    }
+
+    pub fn set_linked_panic(&self, enable: bool) {
+        self.update_bits(Self::LINKED_PANIC, enable);
+    }
 
-        self.bits.store(new_bits, Ordering::Release);
+    pub fn is_linked_panic(&self) -> bool {
+        self.bit_is_set(Self::LINKED_PANIC)
+    }
+
+    fn update_bits(&self, mask: u8, enable: bool) {
+        // Bits may be set by other processes, so we must update them
+        // atomically instead of writing back a value we loaded earlier.
+        if enable {
+            self.bits.fetch_or(mask, Ordering::AcqRel);
+        } else {
+            self.bits.fetch_and(!mask, Ordering::AcqRel);
+        }
     }
 
     fn bit_is_set(&self, bit: u8) -> bool {
@@ -83,6 +109,8 @@ mod tests {
         assert_eq!(status.is_main(), false);
         assert_eq!(status.is_blocking(), false);
         assert_eq!(status.is_terminated(), false);
+        assert_eq!(status.is_pinned(), false);
+        assert_eq!(status.is_linked_panic(), false);
     }
 
     #[test]
@@ -121,4 +149,35 @@ mod tests {
 
         assert!(status.is_terminated());
     }
+
+    #[test]
+    fn test_set_pinned() {
+        let mut status = ProcessStatus::new();
+
+        assert_eq!(status.is_pinned(), false);
+
+        status.set_pinned(true);
+
+        assert!(status.is_pinned());
+
+        status.set_pinned(false);
+
+        assert_eq!(status.is_pinned(), false);
+    }
+
+    #[test]
+    fn test_set_linked_panic() {
+        let mut status = ProcessStatus::new();
+
+        status.set_blocking(true);
+        status.set_linked_panic(true);
+
+        assert!(status.is_linked_panic());
+        assert!(status.is_blocking());
+
+        status.set_linked_panic(false);
+
+        assert_eq!(status.is_linked_panic(), false);
+        assert!(status.is_blocking());
+    }
 }
@@ -0,0 +1,187 @@
+//! Sampling CPU profiling of Inko code.
+//!
+//! When enabled, a separate thread increments the sample epoch at a fixed
+//! interval. Whenever a thread running a process notices the epoch changed, it
+//! records the call stack of the process it is running. Processes that aren't
+//! running (e.g. because they are waiting for a message) are not sampled.
+//!
+//! Samples are written as folded stacks: one line per unique call stack,
+//! containing the frames (starting with the outermost frame) separated by a
+//! semicolon, followed by the number of samples. This format can be turned into
+//! a flame graph using tools such as flamegraph.pl and inferno.
+use crate::execution_context::ExecutionContext;
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// A type that collects samples of the call stacks of running processes.
+pub struct Profiler {
+    /// The time to wait between two samples.
+    interval: Duration,
+
+    /// A counter that is incremented every time samples should be taken.
+    epoch: AtomicUsize,
+
+    /// The number of samples of every call stack, in folded form.
+    samples: Mutex<HashMap<String, u64>>,
+
+    /// A boolean indicating if the profiler should keep running.
+    alive: Mutex<bool>,
+
+    /// Used for waking up the profiler when it needs to terminate.
+    cvar: Condvar,
+}
+
+impl Profiler {
+    pub fn new(interval: Duration) -> Self {
+        Profiler {
+            interval,
+            epoch: AtomicUsize::new(0),
+            samples: Mutex::new(HashMap::new()),
+            alive: Mutex::new(true),
+            cvar: Condvar::new(),
+        }
+    }
+
+    /// Returns the current sample epoch.
+    pub fn epoch(&self) -> usize {
+        self.epoch.load(Ordering::Relaxed)
+    }
+
+    /// Records a sample of the call stack starting at the given context.
+    ///
+    /// The instruction index of the context must point to the instruction
+    /// after the one that is running.
+    pub fn sample(&self, context: &ExecutionContext) {
+        let mut frames = context.contexts().map(frame).collect::<Vec<_>>();
+
+        frames.reverse();
+
+        *self.samples.lock().entry(frames.join(";")).or_insert(0) += 1;
+    }
+
+    pub fn terminate(&self) {
+        *self.alive.lock() = false;
+        self.cvar.notify_all();
+    }
+
+    /// Requests a new sample every interval, until the profiler is terminated.
+    pub fn run(&self) {
+        while self.wait() {
+            self.epoch.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns all samples as folded stacks, sorted by their call stacks.
+    pub fn folded_stacks(&self) -> String {
+        let samples = self.samples.lock();
+        let mut stacks = samples.iter().collect::<Vec<_>>();
+        let mut output = String::new();
+
+        stacks.sort();
+
+        for (stack, count) in stacks {
+            output.push_str(&format!("{} {}\n", stack, count));
+        }
+
+        output
+    }
+
+    /// Writes all samples as folded stacks to the given file.
+    pub fn write_file(&self, path: &Path) -> Result<(), String> {
+        File::create(path)
+            .and_then(|mut file| {
+                file.write_all(self.folded_stacks().as_bytes())
+            })
+            .map_err(|err| {
+                format!(
+                    "Failed to write the profile to {}: {}",
+                    path.display(),
+                    err
+                )
+            })
+    }
+
+    /// Waits for the next sample, returning `false` if the profiler should
+    /// terminate.
+    fn wait(&self) -> bool {
+        let mut alive = self.alive.lock();
+
+        if *alive {
+            self.cvar.wait_for(&mut alive, self.interval);
+        }
+
+        *alive
+    }
+}
+
+/// Returns a description of a single frame in a call stack.
+fn frame(context: &ExecutionContext) -> String {
+    // Semicolons separate frames, so they can't be used in the frames
+    // themselves.
+    format!(
+        "{} ({}:{})",
+        context.code.name.string_value().unwrap(),
+        context.code.file.string_value().unwrap(),
+        context.line()
+    )
+    .replace(';', ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::test::setup;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_sample() {
+        let (_machine, _block, process) = setup();
+        let profiler = Profiler::new(Duration::from_millis(1));
+
+        profiler.sample(process.context());
+        profiler.sample(process.context());
+
+        let frame = frame(process.context());
+
+        assert_eq!(profiler.folded_stacks(), format!("{} 2\n", frame));
+    }
+
+    #[test]
+    fn test_frame() {
+        let (_machine, _block, process) = setup();
+
+        assert_eq!(frame(process.context()), "a (a:1)");
+    }
+
+    #[test]
+    fn test_run_and_terminate() {
+        let profiler = Profiler::new(Duration::from_millis(1));
+
+        profiler.terminate();
+        profiler.run();
+
+        assert_eq!(profiler.epoch(), 0);
+    }
+
+    #[test]
+    fn test_write_file() {
+        let (_machine, _block, process) = setup();
+        let profiler = Profiler::new(Duration::from_millis(1));
+        let path = env::temp_dir().join("inko_test_profiler_write_file.txt");
+
+        profiler.sample(process.context());
+        profiler.write_file(&path).unwrap();
+
+        let output = fs::read_to_string(&path).unwrap();
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(output, profiler.folded_stacks());
+    }
+}
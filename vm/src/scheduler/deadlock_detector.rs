@@ -0,0 +1,299 @@
+//! Detecting of programs in which all processes are blocked.
+//!
+//! A program is deadlocked when all its processes are waiting for a message (or
+//! for room in a mailbox) without a timeout, and no process is running or
+//! scheduled to run. In this case no process will ever be woken up, and the
+//! program hangs forever.
+//!
+//! The DeadlockDetector periodically checks for this state. When the program
+//! remains in this state for two consecutive checks, the stack traces of all
+//! blocked processes are printed to STDERR. Depending on the deadlock policy,
+//! the program is then terminated.
+use crate::config::DeadlockPolicy;
+use crate::process::RcProcess;
+use crate::vm::state::{MainProcessResult, State};
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The interval at which to check if the program is deadlocked.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A type for detecting and reporting deadlocked programs.
+pub struct DeadlockDetector {
+    /// What to do when a deadlock is detected.
+    policy: DeadlockPolicy,
+
+    /// The processes suspended without a timeout, mapped to their identifiers.
+    ///
+    /// Processes are only registered when deadlock detection is enabled.
+    /// Processes that are no longer suspended are removed periodically.
+    blocked: Mutex<HashMap<usize, RcProcess>>,
+
+    /// A boolean indicating if the detector should keep running.
+    alive: Mutex<bool>,
+
+    /// Used for waking up the detector when it needs to terminate.
+    cvar: Condvar,
+}
+
+impl DeadlockDetector {
+    pub fn new(policy: DeadlockPolicy) -> Self {
+        DeadlockDetector {
+            policy,
+            blocked: Mutex::new(HashMap::new()),
+            alive: Mutex::new(true),
+            cvar: Condvar::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.policy != DeadlockPolicy::Ignore
+    }
+
+    /// Registers a process that is suspended without a timeout.
+    pub fn register(&self, process: &RcProcess) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.blocked
+            .lock()
+            .insert(process.identifier(), process.clone());
+    }
+
+    pub fn terminate(&self) {
+        *self.alive.lock() = false;
+        self.cvar.notify_all();
+    }
+
+    pub fn run(&self, state: &State) {
+        let mut last_started = None;
+        let mut reported = None;
+
+        while self.wait() {
+            self.remove_resumed_processes();
+
+            if !is_deadlocked(state) {
+                last_started = None;
+                continue;
+            }
+
+            // A process may be popped from a queue but not yet be running when
+            // we perform our check. To handle this we only consider the program
+            // to be deadlocked if no job started since the previous check.
+            let started = started_jobs(state);
+
+            if last_started != Some(started) {
+                last_started = Some(started);
+                continue;
+            }
+
+            if reported == Some(started) {
+                continue;
+            }
+
+            reported = Some(started);
+
+            let report = self.report();
+
+            eprintln!("{}", report);
+
+            if self.policy == DeadlockPolicy::Terminate {
+                state.set_main_process_result(MainProcessResult::Panicked(
+                    report,
+                ));
+                state.terminate(1);
+                return;
+            }
+        }
+    }
+
+    /// Returns a report containing the stack traces of all blocked processes.
+    pub fn report(&self) -> String {
+        let mut buffer =
+            "All processes are blocked, and none of them can make \
+                          progress"
+                .to_string();
+
+        for process in self.blocked_processes() {
+            let reason = if process.is_waiting_for_message() {
+                "a message"
+            } else {
+                "room in a mailbox"
+            };
+
+            buffer.push_str(&format!(
+                "\n\n{}\nProcess {:#x} is waiting for {}",
+                process.stacktrace(),
+                process.identifier(),
+                reason
+            ));
+        }
+
+        buffer
+    }
+
+    /// Returns all blocked processes, starting with the main process.
+    fn blocked_processes(&self) -> Vec<RcProcess> {
+        let mut processes = self
+            .blocked
+            .lock()
+            .values()
+            .filter(|process| process.is_suspended())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        processes
+            .sort_by_key(|process| (!process.is_main(), process.identifier()));
+
+        processes
+    }
+
+    fn remove_resumed_processes(&self) {
+        self.blocked.lock().retain(|_, process| {
+            process.is_suspended() && !process.is_terminated()
+        });
+    }
+
+    /// Waits for the next check, returning `false` if the detector should
+    /// terminate.
+    fn wait(&self) -> bool {
+        let mut alive = self.alive.lock();
+
+        if *alive {
+            self.cvar.wait_for(&mut alive, CHECK_INTERVAL);
+        }
+
+        *alive
+    }
+}
+
+/// Returns true if processes are alive, but none of them can make progress.
+fn is_deadlocked(state: &State) -> bool {
+    let scheduler = &state.scheduler;
+
+    scheduler.live_processes() > 0
+        && scheduler.primary_pool.state.is_idle()
+        && scheduler.blocking_pool.state.is_idle()
+        && state.timeout_worker.suspended_processes() == 0
+        && state.network_poller.waiting_processes() == 0
+        && state.io_pool.pending_operations() == 0
+}
+
+fn started_jobs(state: &State) -> usize {
+    let scheduler = &state.scheduler;
+
+    scheduler.primary_pool.state.started_jobs()
+        + scheduler.blocking_pool.state.started_jobs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::test::setup;
+    use std::time::Duration;
+
+    #[test]
+    fn test_register_when_disabled() {
+        let (_machine, _block, process) = setup();
+        let detector = DeadlockDetector::new(DeadlockPolicy::Ignore);
+
+        detector.register(&process);
+
+        assert!(detector.blocked.lock().is_empty());
+    }
+
+    #[test]
+    fn test_register_when_enabled() {
+        let (_machine, _block, process) = setup();
+        let detector = DeadlockDetector::new(DeadlockPolicy::Report);
+
+        detector.register(&process);
+        detector.register(&process);
+
+        assert_eq!(detector.blocked.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_remove_resumed_processes() {
+        let (_machine, _block, process) = setup();
+        let detector = DeadlockDetector::new(DeadlockPolicy::Report);
+
+        process.suspend_without_timeout();
+        detector.register(&process);
+        detector.remove_resumed_processes();
+
+        assert_eq!(detector.blocked.lock().len(), 1);
+
+        process.acquire_rescheduling_rights();
+        detector.remove_resumed_processes();
+
+        assert!(detector.blocked.lock().is_empty());
+    }
+
+    #[test]
+    fn test_report() {
+        let (_machine, _block, process) = setup();
+        let detector = DeadlockDetector::new(DeadlockPolicy::Report);
+
+        process.waiting_for_message();
+        process.suspend_without_timeout();
+        detector.register(&process);
+
+        let report = detector.report();
+
+        assert!(report.starts_with("All processes are blocked"));
+        assert!(report.contains(&process.stacktrace()));
+        assert!(report.ends_with(&format!(
+            "Process {:#x} is waiting for a message",
+            process.identifier()
+        )));
+    }
+
+    #[test]
+    fn test_is_deadlocked() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+
+        assert!(is_deadlocked(state));
+
+        state.scheduler.schedule(process.clone());
+
+        assert_eq!(is_deadlocked(state), false);
+
+        state.scheduler.primary_pool.state.pop_global();
+        state
+            .timeout_worker
+            .suspend(process, Duration::from_secs(1));
+
+        assert_eq!(is_deadlocked(state), false);
+    }
+
+    #[test]
+    fn test_run_with_terminate_policy() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let detector = DeadlockDetector::new(DeadlockPolicy::Terminate);
+
+        process.waiting_for_message();
+        process.suspend_without_timeout();
+        detector.register(&process);
+        detector.run(state);
+
+        assert_eq!(state.current_exit_status(), 1);
+        assert!(state.main_process_result.lock().is_some());
+        assert_eq!(state.scheduler.primary_pool.state.is_alive(), false);
+    }
+
+    #[test]
+    fn test_run_after_terminating() {
+        let (machine, _block, _process) = setup();
+        let detector = DeadlockDetector::new(DeadlockPolicy::Terminate);
+
+        detector.terminate();
+        detector.run(&machine.state);
+
+        assert_eq!(machine.state.current_exit_status(), 0);
+    }
+}
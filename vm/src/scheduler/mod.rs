@@ -1,4 +1,5 @@
 //! Task scheduling and execution using work stealing.
+pub mod deadlock_detector;
 pub mod join_list;
 pub mod park_group;
 pub mod pool_state;
@@ -3,7 +3,7 @@ use crate::scheduler::park_group::ParkGroup;
 use crate::scheduler::queue::{Queue, RcQueue};
 use crossbeam_deque::{Injector, Steal};
 use std::iter;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// The maximum number of threads a single pool allows.
 const MAX_THREADS: usize = 255;
@@ -20,6 +20,18 @@ pub struct PoolState<T: Send> {
     /// The global queue on which new jobs will be scheduled,
     global_queue: Injector<T>,
 
+    /// The global queue on which new high priority jobs will be scheduled.
+    priority_queue: Injector<T>,
+
+    /// The number of jobs stored in the global queues.
+    global_jobs: AtomicUsize,
+
+    /// The number of jobs that are being executed.
+    running_jobs: AtomicUsize,
+
+    /// The total number of jobs that started running.
+    started_jobs: AtomicUsize,
+
     /// Used for parking and unparking worker threads.
     park_group: ParkGroup,
 }
@@ -40,16 +52,28 @@ impl<T: Send> PoolState<T> {
             alive: AtomicBool::new(true),
             queues,
             global_queue: Injector::new(),
+            priority_queue: Injector::new(),
+            global_jobs: AtomicUsize::new(0),
+            running_jobs: AtomicUsize::new(0),
+            started_jobs: AtomicUsize::new(0),
             park_group: ParkGroup::new(),
         }
     }
 
     /// Schedules a new job onto the global queue.
     pub fn push_global(&self, value: T) {
+        self.global_jobs.fetch_add(1, Ordering::Release);
         self.global_queue.push(value);
         self.park_group.notify_one();
     }
 
+    /// Schedules a new high priority job onto the global queue.
+    pub fn push_global_priority(&self, value: T) {
+        self.global_jobs.fetch_add(1, Ordering::Release);
+        self.priority_queue.push(value);
+        self.park_group.notify_one();
+    }
+
     /// Schedules a job onto a specific queue.
     ///
     /// This method will panic if the queue index is invalid.
@@ -66,13 +90,63 @@ impl<T: Send> PoolState<T> {
     ///
     /// This method will block the calling thread until a value is available.
     pub fn pop_global(&self) -> Option<T> {
+        self.pop_from(&self.global_queue)
+    }
+
+    /// Pops a high priority job off the global queue.
+    pub fn pop_global_priority(&self) -> Option<T> {
+        if self.priority_queue.is_empty() {
+            return None;
+        }
+
+        self.pop_from(&self.priority_queue)
+    }
+
+    /// Returns the number of jobs stored in the global queues.
+    pub fn global_jobs(&self) -> usize {
+        self.global_jobs.load(Ordering::Acquire)
+    }
+
+    /// Marks a job as running.
+    pub fn start_job(&self) {
+        self.started_jobs.fetch_add(1, Ordering::Release);
+        self.running_jobs.fetch_add(1, Ordering::Release);
+    }
+
+    /// Marks a running job as finished.
+    pub fn finish_job(&self) {
+        self.running_jobs.fetch_sub(1, Ordering::Release);
+    }
+
+    /// Returns the number of jobs that are being executed.
+    pub fn running_jobs(&self) -> usize {
+        self.running_jobs.load(Ordering::Acquire)
+    }
+
+    /// Returns the total number of jobs that started running.
+    pub fn started_jobs(&self) -> usize {
+        self.started_jobs.load(Ordering::Acquire)
+    }
+
+    /// Returns true if no jobs are running, and no jobs are waiting to run.
+    pub fn is_idle(&self) -> bool {
+        self.global_jobs() == 0
+            && self.running_jobs() == 0
+            && self.queues.iter().all(|queue| {
+                queue.local_jobs() == 0 && queue.pending_external() == 0
+            })
+    }
+
+    fn pop_from(&self, queue: &Injector<T>) -> Option<T> {
         loop {
-            match self.global_queue.steal() {
+            match queue.steal() {
                 Steal::Empty => {
                     return None;
                 }
                 Steal::Retry => {}
                 Steal::Success(value) => {
+                    self.global_jobs.fetch_sub(1, Ordering::Release);
+
                     return Some(value);
                 }
             }
@@ -103,7 +177,7 @@ impl<T: Send> PoolState<T> {
 
     /// Returns true if one or more jobs are present in the global queue.
     pub fn has_global_jobs(&self) -> bool {
-        !self.global_queue.is_empty()
+        !self.global_queue.is_empty() || !self.priority_queue.is_empty()
     }
 }
 
@@ -116,7 +190,7 @@ mod tests {
 
     #[test]
     fn test_memory_size() {
-        assert_eq!(mem::size_of::<PoolState<()>>(), 384);
+        assert_eq!(mem::size_of::<PoolState<()>>(), 640);
     }
 
     #[test]
@@ -152,6 +226,83 @@ mod tests {
         assert_eq!(state.pop_global(), None);
     }
 
+    #[test]
+    fn test_pop_global_priority() {
+        let state = PoolState::new(1);
+
+        state.push_global(10);
+        state.push_global_priority(20);
+
+        assert_eq!(state.pop_global_priority(), Some(20));
+        assert_eq!(state.pop_global_priority(), None);
+        assert_eq!(state.pop_global(), Some(10));
+    }
+
+    #[test]
+    fn test_global_jobs() {
+        let state = PoolState::new(1);
+
+        state.push_global(10);
+        state.push_global_priority(20);
+
+        assert_eq!(state.global_jobs(), 2);
+
+        state.pop_global();
+
+        assert_eq!(state.global_jobs(), 1);
+    }
+
+    #[test]
+    fn test_start_job() {
+        let state: PoolState<()> = PoolState::new(1);
+
+        state.start_job();
+
+        assert_eq!(state.running_jobs(), 1);
+        assert_eq!(state.started_jobs(), 1);
+    }
+
+    #[test]
+    fn test_finish_job() {
+        let state: PoolState<()> = PoolState::new(1);
+
+        state.start_job();
+        state.finish_job();
+
+        assert_eq!(state.running_jobs(), 0);
+        assert_eq!(state.started_jobs(), 1);
+    }
+
+    #[test]
+    fn test_is_idle() {
+        let state = PoolState::new(1);
+
+        assert!(state.is_idle());
+
+        state.start_job();
+
+        assert_eq!(state.is_idle(), false);
+
+        state.finish_job();
+        state.push_global(10);
+
+        assert_eq!(state.is_idle(), false);
+
+        state.pop_global();
+        state.schedule_onto_queue(0, 10);
+
+        assert_eq!(state.is_idle(), false);
+
+        state.queues[0].pop_external_job();
+        state.queues[0].push_internal(10);
+
+        assert_eq!(state.is_idle(), false);
+
+        state.queues[0].pop();
+
+        assert!(state.is_idle());
+    }
+
     #[test]
     fn test_terminate() {
         let state: PoolState<()> = PoolState::new(4);
@@ -191,6 +342,15 @@ mod tests {
         assert!(state.has_global_jobs());
     }
 
+    #[test]
+    fn test_has_global_jobs_with_priority_jobs() {
+        let state = PoolState::new(4);
+
+        state.push_global_priority(10);
+
+        assert!(state.has_global_jobs());
+    }
+
     #[test]
     fn test_schedule_onto_queue() {
         let state = PoolState::new(1);
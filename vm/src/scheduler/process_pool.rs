@@ -44,6 +44,11 @@ impl ProcessPool {
         self.state.push_global(job);
     }
 
+    /// Schedules a high priority job onto the global queue.
+    pub fn schedule_priority(&self, job: RcProcess) {
+        self.state.push_global_priority(job);
+    }
+
     /// Informs this pool it should terminate as soon as possible.
     pub fn terminate(&self) {
         self.state.terminate();
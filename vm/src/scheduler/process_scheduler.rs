@@ -1,6 +1,7 @@
 //! Scheduling and execution of lightweight Inko processes.
 use crate::process::RcProcess;
 use crate::scheduler::process_pool::ProcessPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// The ID of the queue that is processed by the main thread.
 const MAIN_THREAD_QUEUE_ID: usize = 0;
@@ -18,6 +19,9 @@ pub struct ProcessScheduler {
     /// The pool to use for executing processes that perform blocking
     /// operations.
     pub blocking_pool: ProcessPool,
+
+    /// The number of processes that have been started but not yet terminated.
+    live_processes: AtomicUsize,
 }
 
 impl ProcessScheduler {
@@ -27,9 +31,24 @@ impl ProcessScheduler {
         ProcessScheduler {
             primary_pool: ProcessPool::new("primary".to_string(), primary),
             blocking_pool: ProcessPool::new("blocking".to_string(), blocking),
+            live_processes: AtomicUsize::new(0),
         }
     }
 
+    /// Returns the number of processes that have been started but not yet
+    /// terminated, including processes that are suspended.
+    pub fn live_processes(&self) -> usize {
+        self.live_processes.load(Ordering::Acquire)
+    }
+
+    pub fn increment_live_processes(&self) {
+        self.live_processes.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn decrement_live_processes(&self) {
+        self.live_processes.fetch_sub(1, Ordering::Release);
+    }
+
     /// Informs the scheduler it needs to terminate as soon as possible.
     pub fn terminate(&self) {
         self.primary_pool.terminate();
@@ -37,7 +56,31 @@ impl ProcessScheduler {
     }
 
     /// Schedules a process in one of the pools.
+    ///
+    /// High priority processes are scheduled before other processes, unless
+    /// they are pinned to a thread.
     pub fn schedule(&self, process: RcProcess) {
+        let priority = process.is_high_priority();
+
+        self.schedule_with_priority(process, priority);
+    }
+
+    /// Schedules a process that was suspended after running out of reductions.
+    ///
+    /// Such processes are scheduled like regular processes, regardless of
+    /// their priority. This ensures that high priority processes performing a
+    /// lot of work can't starve other processes.
+    pub fn schedule_preempted(&self, process: RcProcess) {
+        self.schedule_with_priority(process, false);
+    }
+
+    /// Schedules a process onto the main thread.
+    pub fn schedule_on_main_thread(&self, process: RcProcess) {
+        self.primary_pool
+            .schedule_onto_queue(MAIN_THREAD_QUEUE_ID, process);
+    }
+
+    fn schedule_with_priority(&self, process: RcProcess, priority: bool) {
         let pool = if process.is_blocking() {
             &self.blocking_pool
         } else {
@@ -46,21 +89,18 @@ impl ProcessScheduler {
 
         if let Some(thread_id) = process.thread_id() {
             pool.schedule_onto_queue(thread_id as usize, process);
+        } else if priority {
+            pool.schedule_priority(process);
         } else {
             pool.schedule(process);
         }
     }
-
-    /// Schedules a process onto the main thread.
-    pub fn schedule_on_main_thread(&self, process: RcProcess) {
-        self.primary_pool
-            .schedule_onto_queue(MAIN_THREAD_QUEUE_ID, process);
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::process::Priority;
     use crate::vm::test::setup;
 
     #[test]
@@ -73,6 +113,17 @@ mod tests {
         assert_eq!(scheduler.blocking_pool.state.is_alive(), false);
     }
 
+    #[test]
+    fn test_live_processes() {
+        let scheduler = ProcessScheduler::new(1, 1);
+
+        scheduler.increment_live_processes();
+        scheduler.increment_live_processes();
+        scheduler.decrement_live_processes();
+
+        assert_eq!(scheduler.live_processes(), 1);
+    }
+
     #[test]
     fn test_schedule_on_primary() {
         let scheduler = ProcessScheduler::new(1, 1);
@@ -96,6 +147,32 @@ mod tests {
         assert!(scheduler.blocking_pool.state.pop_global() == Some(process));
     }
 
+    #[test]
+    fn test_schedule_with_high_priority() {
+        let scheduler = ProcessScheduler::new(1, 1);
+        let (_machine, _block, process) = setup();
+
+        process.set_priority(Priority::High);
+        scheduler.schedule(process.clone());
+
+        assert!(scheduler.primary_pool.state.pop_global().is_none());
+        assert!(
+            scheduler.primary_pool.state.pop_global_priority() == Some(process)
+        );
+    }
+
+    #[test]
+    fn test_schedule_preempted_with_high_priority() {
+        let scheduler = ProcessScheduler::new(1, 1);
+        let (_machine, _block, process) = setup();
+
+        process.set_priority(Priority::High);
+        scheduler.schedule_preempted(process.clone());
+
+        assert!(scheduler.primary_pool.state.pop_global_priority().is_none());
+        assert!(scheduler.primary_pool.state.pop_global() == Some(process));
+    }
+
     #[test]
     fn test_schedule_pinned() {
         let scheduler = ProcessScheduler::new(2, 2);
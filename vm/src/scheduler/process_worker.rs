@@ -65,10 +65,14 @@ impl ProcessWorker {
     ///
     /// When in exclusive mode, only the currently running job will be allowed
     /// to run on this worker. All other jobs are pushed back into the global
-    /// queue.
+    /// queue, retaining their priority.
     pub fn enter_exclusive_mode(&mut self) {
         self.queue.move_external_jobs();
 
+        while let Some(job) = self.queue.pop_priority() {
+            self.state.push_global_priority(job);
+        }
+
         while let Some(job) = self.queue.pop() {
             self.state.push_global(job);
         }
@@ -130,6 +134,18 @@ impl Worker<RcProcess> for ProcessWorker {
         &self.queue
     }
 
+    fn pop_job(&self) -> Option<RcProcess> {
+        // Jobs from the global queue must not run while in exclusive mode, as
+        // they would take over the thread of the exclusive job.
+        if self.mode == Mode::Exclusive {
+            return self.queue.pop();
+        }
+
+        self.state
+            .pop_global_priority()
+            .or_else(|| self.queue.pop())
+    }
+
     fn run(&mut self) {
         while self.state.is_alive() {
             match self.mode {
@@ -151,7 +167,9 @@ impl Worker<RcProcess> for ProcessWorker {
         // needed.
         let machine = unsafe { &mut *self.machine.get() };
 
+        self.state.start_job();
         machine.run(self, &job);
+        self.state.finish_job();
     }
 }
 
@@ -180,6 +198,18 @@ mod tests {
         assert_eq!(worker.state.queues[0].has_local_jobs(), false);
     }
 
+    #[test]
+    fn test_run_global_priority_jobs() {
+        let (machine, _block, process) = setup();
+        let mut worker = worker(machine.clone());
+
+        worker.state.push_global_priority(process.clone());
+        worker.run();
+
+        assert!(worker.state.pop_global_priority().is_none());
+        assert_eq!(worker.state.queues[0].has_local_jobs(), false);
+    }
+
     #[test]
     fn test_run_with_external_jobs() {
         let (machine, _block, process) = setup();
@@ -248,6 +278,21 @@ mod tests {
         assert_eq!(worker.queue.has_external_jobs(), false);
     }
 
+    #[test]
+    fn test_run_exclusive_iteration_with_global_priority_jobs() {
+        let (machine, block, process) = setup();
+        let process2 = process::process_allocate(&machine.state, &block);
+        let mut worker = worker(machine.clone());
+
+        worker.enter_exclusive_mode();
+        worker.state.push_global_priority(process2.clone());
+        worker.queue.push_external(process);
+        worker.run();
+
+        assert_eq!(worker.queue.has_external_jobs(), false);
+        assert!(worker.state.pop_global_priority() == Some(process2));
+    }
+
     #[test]
     fn test_enter_exclusive_mode() {
         let (machine, block, process) = setup();
@@ -263,6 +308,20 @@ mod tests {
         assert!(worker.queue.pop_external_job().is_none());
     }
 
+    #[test]
+    fn test_enter_exclusive_mode_with_priority_jobs() {
+        let (machine, block, process) = setup();
+        let process2 = process::process_allocate(&machine.state, &block);
+        let mut worker = worker(machine.clone());
+
+        worker.queue.push_internal(process.clone());
+        worker.queue.push_internal_priority(process2.clone());
+        worker.enter_exclusive_mode();
+
+        assert!(worker.state.pop_global_priority() == Some(process2));
+        assert!(worker.state.pop_global() == Some(process));
+    }
+
     #[test]
     fn test_leave_exclusive_mode() {
         let (machine, _block, _process) = setup();
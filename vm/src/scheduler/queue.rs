@@ -8,6 +8,9 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 /// own a Queue can push jobs into the queue with minimal overhead, while other
 /// threads can push jobs into the queue using a Multiple Producer Multiple
 /// Consumer (MPMC) channel.
+///
+/// Jobs with a high priority are stored in a separate deque, which is processed
+/// before the regular deque.
 pub struct Queue<T: Send> {
     /// The worker side of the deque, used for producing new jobs. This
     /// structure _can only_ be used by the thread that owns this queue.
@@ -17,9 +20,22 @@ pub struct Queue<T: Send> {
     /// queue using this stealer.
     stealer: Stealer<T>,
 
+    /// The worker side of the deque used for high priority jobs.
+    priority_worker: Worker<T>,
+
+    /// The stealing side of the deque used for high priority jobs.
+    priority_stealer: Stealer<T>,
+
     /// The number of pending jobs that were scheduled externally.
     pending_external: AtomicUsize,
 
+    /// The number of jobs stored in the deques.
+    ///
+    /// This counter is incremented _before_ pushing a job, and decremented
+    /// _after_ removing a job. This ensures the counter is never smaller than
+    /// the actual number of jobs, even when other threads are stealing jobs.
+    local_jobs: AtomicUsize,
+
     /// The Sender to be used by other threads that wish to schedule jobs onto
     /// this queue.
     sender: Sender<T>,
@@ -35,11 +51,16 @@ impl<T: Send> Queue<T> {
     pub fn new() -> Self {
         let worker = Worker::new_fifo();
         let stealer = worker.stealer();
+        let priority_worker = Worker::new_fifo();
+        let priority_stealer = priority_worker.stealer();
         let (sender, receiver) = unbounded();
 
         Queue {
             stealer,
+            priority_worker,
+            priority_stealer,
             pending_external: AtomicUsize::new(0),
+            local_jobs: AtomicUsize::new(0),
             worker,
             sender,
             receiver,
@@ -58,6 +79,12 @@ impl<T: Send> Queue<T> {
         self.pending_external.fetch_add(1, Ordering::Release);
     }
 
+    /// Returns the number of jobs stored in the deques, excluding any jobs
+    /// that were scheduled externally.
+    pub fn local_jobs(&self) -> usize {
+        self.local_jobs.load(Ordering::Acquire)
+    }
+
     pub fn decrement_pending_external(&self) {
         if self.pending_external() > 0 {
             self.pending_external.fetch_sub(1, Ordering::Release);
@@ -68,9 +95,18 @@ impl<T: Send> Queue<T> {
     ///
     /// This method can only be used by the thread that owns the queue.
     pub fn push_internal(&self, value: T) {
+        self.local_jobs.fetch_add(1, Ordering::Release);
         self.worker.push(value);
     }
 
+    /// Pushes a high priority job onto the deque.
+    ///
+    /// This method can only be used by the thread that owns the queue.
+    pub fn push_internal_priority(&self, value: T) {
+        self.local_jobs.fetch_add(1, Ordering::Release);
+        self.priority_worker.push(value);
+    }
+
     /// Pushes a job onto the shared channel.
     ///
     /// This method can be safely used by multiple threads.
@@ -82,26 +118,59 @@ impl<T: Send> Queue<T> {
             .expect("Attempted to schedule a job onto a queue that is dropped");
     }
 
-    /// Pops a value from the worker.
+    /// Pops a value from the worker, giving precedence to high priority jobs.
     pub fn pop(&self) -> Option<T> {
-        self.worker.pop()
+        let job = self.priority_worker.pop().or_else(|| self.worker.pop());
+
+        if job.is_some() {
+            self.local_jobs.fetch_sub(1, Ordering::Release);
+        }
+
+        job
+    }
+
+    /// Pops a high priority job from the worker.
+    pub fn pop_priority(&self) -> Option<T> {
+        let job = self.priority_worker.pop();
+
+        if job.is_some() {
+            self.local_jobs.fetch_sub(1, Ordering::Release);
+        }
+
+        job
     }
 
     /// Steal one or more jobs and push them into the given queue.
     ///
+    /// Up to half the jobs are stolen, giving precedence to high priority jobs.
+    /// Jobs are stolen one at a time, allowing us to keep track of the number
+    /// of jobs stored in both queues.
+    ///
     /// This method can safely be used by different threads. The returned
     /// boolean will be `true` if one or more jobs were stolen, `false`
     /// otherwise.
     pub fn steal_into(&self, queue: &Self) -> bool {
-        loop {
-            match self.stealer.steal_batch(&queue.worker) {
-                Steal::Empty => return false,
-                Steal::Success(_) => {
-                    return true;
-                }
-                _ => {}
-            };
-        }
+        let jobs = self.local_jobs();
+        let limit = jobs - jobs / 2;
+        let mut stolen = 0;
+
+        queue.local_jobs.fetch_add(limit, Ordering::Release);
+
+        stolen += Self::steal_from(
+            &self.priority_stealer,
+            &queue.priority_worker,
+            limit,
+        );
+
+        stolen +=
+            Self::steal_from(&self.stealer, &queue.worker, limit - stolen);
+
+        self.local_jobs.fetch_sub(stolen, Ordering::Release);
+        queue
+            .local_jobs
+            .fetch_sub(limit - stolen, Ordering::Release);
+
+        stolen > 0
     }
 
     /// Pops a job from the public channel, without first moving it to the
@@ -134,6 +203,8 @@ impl<T: Send> Queue<T> {
 
         let mut received = 0;
 
+        self.local_jobs.fetch_add(remaining, Ordering::Release);
+
         for job in self.receiver.try_iter().take(remaining) {
             received += 1;
 
@@ -141,13 +212,38 @@ impl<T: Send> Queue<T> {
         }
 
         self.pending_external.fetch_sub(received, Ordering::Release);
+        self.local_jobs
+            .fetch_sub(remaining - received, Ordering::Release);
 
         received > 0
     }
 
+    /// Steals up to `limit` jobs from a stealer, returning the number of jobs
+    /// that were stolen.
+    fn steal_from(
+        stealer: &Stealer<T>,
+        worker: &Worker<T>,
+        limit: usize,
+    ) -> usize {
+        let mut stolen = 0;
+
+        while stolen < limit {
+            match stealer.steal() {
+                Steal::Success(job) => {
+                    worker.push(job);
+                    stolen += 1;
+                }
+                Steal::Empty => break,
+                Steal::Retry => {}
+            }
+        }
+
+        stolen
+    }
+
     /// Returns true if there are one or more jobs stored in our local worker.
     pub fn has_local_jobs(&self) -> bool {
-        !self.worker.is_empty()
+        !self.worker.is_empty() || !self.priority_worker.is_empty()
     }
 
     /// Returns true if there are one or more jobs stored in the external queue.
@@ -190,6 +286,76 @@ mod tests {
         assert_eq!(queue.pop(), Some(10));
     }
 
+    #[test]
+    fn test_pop_with_priority_jobs() {
+        let queue = Queue::new();
+
+        queue.push_internal(10);
+        queue.push_internal_priority(20);
+
+        assert_eq!(queue.pop(), Some(20));
+        assert_eq!(queue.pop(), Some(10));
+    }
+
+    #[test]
+    fn test_pop_priority() {
+        let queue = Queue::new();
+
+        queue.push_internal(10);
+        queue.push_internal_priority(20);
+
+        assert_eq!(queue.pop_priority(), Some(20));
+        assert!(queue.pop_priority().is_none());
+        assert_eq!(queue.local_jobs(), 1);
+    }
+
+    #[test]
+    fn test_steal_with_priority_jobs() {
+        let queue1 = Queue::new();
+        let queue2 = Queue::new();
+
+        queue1.push_internal(10);
+        queue1.push_internal_priority(20);
+
+        assert!(queue1.steal_into(&queue2));
+        assert_eq!(queue2.pop(), Some(20));
+        assert!(queue2.pop().is_none());
+
+        assert!(queue1.steal_into(&queue2));
+        assert_eq!(queue2.pop(), Some(10));
+    }
+
+    #[test]
+    fn test_local_jobs() {
+        let queue = Queue::new();
+
+        queue.push_internal(10);
+        queue.push_internal_priority(20);
+
+        assert_eq!(queue.local_jobs(), 2);
+
+        queue.pop();
+
+        assert_eq!(queue.local_jobs(), 1);
+    }
+
+    #[test]
+    fn test_steal_half_of_the_jobs() {
+        let queue1 = Queue::new();
+        let queue2 = Queue::new();
+
+        for i in 0..4 {
+            queue1.push_internal(i);
+        }
+
+        assert!(queue1.steal_into(&queue2));
+        assert_eq!(queue1.local_jobs(), 2);
+        assert_eq!(queue2.local_jobs(), 2);
+        assert_eq!(queue2.pop(), Some(0));
+        assert_eq!(queue2.pop(), Some(1));
+        assert!(queue2.pop().is_none());
+    }
+
     #[test]
     fn test_steal() {
         let queue1 = Queue::new();
@@ -215,6 +381,7 @@ mod tests {
         assert_eq!(queue.pending_external(), 3);
         assert!(queue.move_external_jobs());
         assert_eq!(queue.pending_external(), 0);
+        assert_eq!(queue.local_jobs(), 3);
         assert_eq!(queue.pop(), Some(10));
         assert_eq!(queue.pop(), Some(20));
         assert_eq!(queue.pop(), Some(30));
@@ -251,6 +418,15 @@ mod tests {
         assert!(queue.has_local_jobs());
     }
 
+    #[test]
+    fn test_has_local_jobs_with_priority_jobs() {
+        let queue = Queue::new();
+
+        queue.push_internal_priority(10);
+
+        assert!(queue.has_local_jobs());
+    }
+
     #[test]
     fn test_pop_external_job() {
         let queue = Queue::new();
@@ -60,6 +60,9 @@ pub struct TimeoutWorker {
     /// The number of timeouts that have been invalidated by sending a message
     /// to the process, before the timeout expired.
     expired: AtomicUsize,
+
+    /// The number of processes that are suspended with a timeout.
+    suspended: AtomicUsize,
 }
 
 unsafe impl Sync for TimeoutWorker {}
@@ -78,6 +81,7 @@ impl TimeoutWorker {
         TimeoutWorker {
             inner: UnsafeCell::new(inner),
             expired: AtomicUsize::new(0),
+            suspended: AtomicUsize::new(0),
             sender,
         }
     }
@@ -86,6 +90,7 @@ impl TimeoutWorker {
         let timeout = Timeout::with_rc(duration);
 
         process.suspend_with_timeout(timeout.clone());
+        self.suspended.fetch_add(1, Ordering::AcqRel);
 
         self.sender
             .send(Message::Suspend(process, timeout))
@@ -98,8 +103,15 @@ impl TimeoutWorker {
             .expect("Failed to terminate because the channel was closed");
     }
 
+    /// Records that a process was rescheduled before its timeout expired.
     pub fn increase_expired_timeouts(&self) {
         self.expired.fetch_add(1, Ordering::AcqRel);
+        self.suspended.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Returns the number of processes that are suspended with a timeout.
+    pub fn suspended_processes(&self) -> usize {
+        self.suspended.load(Ordering::Acquire)
     }
 
     pub fn run(&self, scheduler: &ProcessScheduler) {
@@ -136,6 +148,8 @@ impl TimeoutWorker {
         let (expired, time_until_expiration) =
             inner.timeouts.processes_to_reschedule();
 
+        self.suspended.fetch_sub(expired.len(), Ordering::AcqRel);
+
         for process in expired {
             scheduler.schedule(process);
         }
@@ -243,6 +257,7 @@ mod tests {
 
         assert!(worker.inner().receiver.recv().is_ok());
         assert!(process.acquire_rescheduling_rights().are_acquired());
+        assert_eq!(worker.suspended_processes(), 1);
     }
 
     #[test]
@@ -354,6 +369,7 @@ mod tests {
         worker.reschedule_expired_processes(&scheduler);
 
         assert!(scheduler.primary_pool.state.pop_global() == Some(process));
+        assert_eq!(worker.suspended_processes(), 0);
     }
 
     #[test]
@@ -46,6 +46,17 @@ pub trait Worker<T: Send> {
         self.state().park_while(|| !self.state().has_global_jobs());
     }
 
+    /// Pops the next job to process from the local queue.
+    ///
+    /// High priority jobs scheduled onto the global queue are processed before
+    /// any local jobs, ensuring they don't have to wait for all local jobs to
+    /// finish first.
+    fn pop_job(&self) -> Option<T> {
+        self.state()
+            .pop_global_priority()
+            .or_else(|| self.queue().pop())
+    }
+
     /// Processes all local jobs until we run out of work.
     ///
     /// This method returns true if the worker should self terminate.
@@ -55,7 +66,7 @@ pub trait Worker<T: Send> {
                 return true;
             }
 
-            if let Some(job) = self.queue().pop() {
+            if let Some(job) = self.pop_job() {
                 self.process_job(job);
             } else {
                 return false;
@@ -75,11 +86,15 @@ pub trait Worker<T: Send> {
         false
     }
 
-    /// Steals a single job from the global queue.
+    /// Steals a single job from the global queue, giving precedence to high
+    /// priority jobs.
     ///
     /// This method will return `true` if a job was stolen.
     fn steal_from_global_queue(&self) -> bool {
-        if let Some(job) = self.state().pop_global() {
+        if let Some(job) = self.state().pop_global_priority() {
+            self.queue().push_internal_priority(job);
+            true
+        } else if let Some(job) = self.state().pop_global() {
             self.queue().push_internal(job);
             true
         } else {
@@ -0,0 +1,416 @@
+//! Shapes and the storage of object attributes.
+//!
+//! Objects created by the same code usually define the same attributes, in the
+//! same order. Instead of giving every object its own hash map of attributes,
+//! such objects share a "shape": a structure that maps attribute names to slot
+//! indexes. The attribute values of an object are then stored in a vector,
+//! using the slot indexes of the object's shape.
+//!
+//! Shapes form a tree. The root of this tree is the shape without any
+//! attributes. Adding an attribute to an object moves the object to a child
+//! shape, created the first time an attribute with the given name is added to
+//! an object of the parent shape. These child shapes are called
+//! "transitions".
+//!
+//! Shapes are shared by all processes and are never released. To limit the
+//! memory used by shapes, objects with many attributes (e.g. modules) store
+//! their attributes in a hash map instead. This also happens when an attribute
+//! is removed, as this is rare and would otherwise require a new shape.
+//!
+//! Since shapes outlive the objects using them, only permanent objects (such as
+//! interned strings) can be used as the names of shape attributes. Adding an
+//! attribute with any other name also moves the attributes into a hash map.
+use crate::immix::bucket::PERMANENT;
+use crate::object_pointer::ObjectPointer;
+use ahash::AHashMap;
+use parking_lot::RwLock;
+use std::collections::hash_map;
+use std::iter::Zip;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// The maximum number of attributes to store using a shape.
+///
+/// Finding the slot of an attribute requires a linear search, and every shape
+/// stores the names of all its attributes. Limiting the number of attributes
+/// keeps both lookups and the memory used by shapes in check.
+pub const MAX_SHAPE_ATTRIBUTES: usize = 16;
+
+/// A map of attribute names and their values.
+pub type AttributesMap = AHashMap<ObjectPointer, ObjectPointer>;
+
+/// The root shape, allocated on first use.
+static ROOT: AtomicPtr<Shape> = AtomicPtr::new(ptr::null_mut());
+
+/// The layout of the attributes of one or more objects.
+pub struct Shape {
+    /// The names of the attributes, in the order of their slots.
+    names: Vec<ObjectPointer>,
+
+    /// The shapes to use when adding an attribute to an object of this shape.
+    transitions: RwLock<AHashMap<ObjectPointer, &'static Shape>>,
+}
+
+// Attribute names are permanent objects, so shapes can safely be shared
+// between threads.
+unsafe impl Sync for Shape {}
+unsafe impl Send for Shape {}
+
+impl Shape {
+    /// Returns the shape without any attributes.
+    pub fn root() -> &'static Shape {
+        let mut root = ROOT.load(Ordering::Acquire);
+
+        if root.is_null() {
+            let new_root = Box::into_raw(Box::new(Shape::new(Vec::new())));
+
+            root = match ROOT.compare_exchange(
+                ptr::null_mut(),
+                new_root,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => new_root,
+                Err(existing) => {
+                    // Another thread beat us to it.
+                    drop(unsafe { Box::from_raw(new_root) });
+                    existing
+                }
+            };
+        }
+
+        unsafe { &*root }
+    }
+
+    fn new(names: Vec<ObjectPointer>) -> Self {
+        Shape {
+            names,
+            transitions: RwLock::new(AHashMap::default()),
+        }
+    }
+
+    /// Returns the number of attributes of this shape.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Returns the names of the attributes of this shape.
+    pub fn names(&self) -> &[ObjectPointer] {
+        &self.names
+    }
+
+    /// Returns the slot index of the given attribute name.
+    pub fn slot(&self, name: ObjectPointer) -> Option<usize> {
+        self.names.iter().position(|current| *current == name)
+    }
+
+    /// Returns the shape to use after adding the given attribute.
+    pub fn transition(&'static self, name: ObjectPointer) -> &'static Shape {
+        if let Some(shape) = self.transitions.read().get(&name) {
+            return shape;
+        }
+
+        let mut transitions = self.transitions.write();
+
+        // Another thread may have added the transition while we were waiting
+        // for the lock.
+        transitions.entry(name).or_insert_with(|| {
+            let mut names = Vec::with_capacity(self.names.len() + 1);
+
+            names.extend_from_slice(&self.names);
+            names.push(name);
+
+            Box::leak(Box::new(Shape::new(names)))
+        })
+    }
+}
+
+/// The attributes of an object.
+pub enum Attributes {
+    /// Attributes stored in slots, using a shape shared with other objects.
+    Slots(&'static Shape, Vec<ObjectPointer>),
+
+    /// Attributes stored in a hash map owned by a single object.
+    Map(Box<AttributesMap>),
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Attributes::Slots(Shape::root(), Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Attributes::Slots(_, values) => values.len(),
+            Attributes::Map(map) => map.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the shape of these attributes, if any.
+    pub fn shape(&self) -> Option<&'static Shape> {
+        match self {
+            Attributes::Slots(shape, _) => Some(shape),
+            Attributes::Map(_) => None,
+        }
+    }
+
+    pub fn get(&self, name: ObjectPointer) -> Option<ObjectPointer> {
+        match self {
+            Attributes::Slots(shape, values) => {
+                shape.slot(name).map(|index| values[index])
+            }
+            Attributes::Map(map) => map.get(&name).cloned(),
+        }
+    }
+
+    pub fn insert(&mut self, name: ObjectPointer, value: ObjectPointer) {
+        match self {
+            Attributes::Slots(shape, values) => {
+                if let Some(index) = shape.slot(name) {
+                    values[index] = value;
+                    return;
+                }
+
+                if shape.len() < MAX_SHAPE_ATTRIBUTES && is_permanent_name(name)
+                {
+                    *shape = shape.transition(name);
+                    values.push(value);
+                    return;
+                }
+            }
+            Attributes::Map(map) => {
+                map.insert(name, value);
+                return;
+            }
+        }
+
+        self.convert_to_map();
+        self.insert(name, value);
+    }
+
+    pub fn remove(&mut self, name: ObjectPointer) -> Option<ObjectPointer> {
+        if let Attributes::Slots(shape, _) = self {
+            shape.slot(name)?;
+        }
+
+        self.convert_to_map();
+
+        match self {
+            Attributes::Map(map) => map.remove(&name),
+            Attributes::Slots(_, _) => None,
+        }
+    }
+
+    /// Returns an iterator over the names and values of the attributes.
+    pub fn iter(&self) -> Iter {
+        match self {
+            Attributes::Slots(shape, values) => {
+                Iter::Slots(shape.names().iter().zip(values.iter()))
+            }
+            Attributes::Map(map) => Iter::Map(map.iter()),
+        }
+    }
+
+    /// Calls the closure for every attribute value.
+    pub fn each_value<F>(&self, mut callback: F)
+    where
+        F: FnMut(&ObjectPointer),
+    {
+        match self {
+            Attributes::Slots(_, values) => values.iter().for_each(callback),
+            Attributes::Map(map) => {
+                for value in map.values() {
+                    callback(value);
+                }
+            }
+        }
+    }
+
+    fn convert_to_map(&mut self) {
+        if let Attributes::Slots(shape, values) = self {
+            let map = shape
+                .names()
+                .iter()
+                .cloned()
+                .zip(values.iter().cloned())
+                .collect::<AttributesMap>();
+
+            *self = Attributes::Map(Box::new(map));
+        }
+    }
+}
+
+/// Returns true if the name can be used for the attributes of a shape.
+///
+/// Names that aren't permanent may be garbage collected, and their memory
+/// reused for different names, so we can't use them for transitions.
+fn is_permanent_name(name: ObjectPointer) -> bool {
+    name.is_tagged_integer()
+        || name.block().bucket().map(|bucket| bucket.age) == Some(PERMANENT)
+}
+
+/// An iterator over the names and values of attributes.
+pub enum Iter<'a> {
+    Slots(Zip<slice::Iter<'a, ObjectPointer>, slice::Iter<'a, ObjectPointer>>),
+    Map(hash_map::Iter<'a, ObjectPointer, ObjectPointer>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (ObjectPointer, ObjectPointer);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Slots(iter) => iter.next(),
+            Iter::Map(iter) => iter.next(),
+        }
+        .map(|(name, value)| (*name, *value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_pointer::RawObjectPointer;
+    use std::mem;
+
+    fn name(id: usize) -> ObjectPointer {
+        // Using unique names ensures the tests don't use shapes created by
+        // other tests, as the shape tree is shared. Tagged integers are used
+        // as these are permanent objects.
+        ObjectPointer::integer((0x1000_0000 + id) as i64)
+    }
+
+    fn value(address: usize) -> ObjectPointer {
+        ObjectPointer::new((address * 16) as RawObjectPointer)
+    }
+
+    #[test]
+    fn test_root() {
+        let root = Shape::root();
+
+        assert!(root.is_empty());
+        assert!(ptr::eq(root, Shape::root()));
+    }
+
+    #[test]
+    fn test_transition() {
+        let root = Shape::root();
+        let shape = root.transition(name(1));
+
+        assert_eq!(shape.len(), 1);
+        assert!(shape.names()[0] == name(1));
+        assert!(ptr::eq(shape, root.transition(name(1))));
+        assert!(!ptr::eq(shape, root.transition(name(2))));
+    }
+
+    #[test]
+    fn test_slot() {
+        let shape = Shape::root().transition(name(3)).transition(name(4));
+
+        assert_eq!(shape.slot(name(3)), Some(0));
+        assert_eq!(shape.slot(name(4)), Some(1));
+        assert_eq!(shape.slot(name(5)), None);
+    }
+
+    #[test]
+    fn test_attributes_insert_and_get() {
+        let mut attributes = Attributes::new();
+
+        attributes.insert(name(10), value(1));
+        attributes.insert(name(11), value(2));
+        attributes.insert(name(10), value(3));
+
+        assert_eq!(attributes.len(), 2);
+        assert!(attributes.get(name(10)) == Some(value(3)));
+        assert!(attributes.get(name(11)) == Some(value(2)));
+        assert!(attributes.get(name(12)).is_none());
+        assert_eq!(attributes.shape().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_attributes_share_shapes() {
+        let mut attributes1 = Attributes::new();
+        let mut attributes2 = Attributes::new();
+
+        attributes1.insert(name(20), value(1));
+        attributes1.insert(name(21), value(2));
+        attributes2.insert(name(20), value(3));
+        attributes2.insert(name(21), value(4));
+
+        assert!(ptr::eq(
+            attributes1.shape().unwrap(),
+            attributes2.shape().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_attributes_insert_too_many_attributes() {
+        let mut attributes = Attributes::new();
+
+        for index in 0..=MAX_SHAPE_ATTRIBUTES {
+            attributes.insert(name(100 + index), value(index));
+        }
+
+        assert!(attributes.shape().is_none());
+        assert_eq!(attributes.len(), MAX_SHAPE_ATTRIBUTES + 1);
+
+        for index in 0..=MAX_SHAPE_ATTRIBUTES {
+            assert!(attributes.get(name(100 + index)) == Some(value(index)));
+        }
+    }
+
+    #[test]
+    fn test_attributes_remove() {
+        let mut attributes = Attributes::new();
+
+        attributes.insert(name(30), value(1));
+        attributes.insert(name(31), value(2));
+
+        assert!(attributes.remove(name(32)).is_none());
+        assert!(attributes.shape().is_some());
+        assert!(attributes.remove(name(30)) == Some(value(1)));
+        assert!(attributes.shape().is_none());
+        assert!(attributes.get(name(30)).is_none());
+        assert!(attributes.get(name(31)) == Some(value(2)));
+    }
+
+    #[test]
+    fn test_attributes_iter() {
+        let mut attributes = Attributes::new();
+
+        attributes.insert(name(40), value(1));
+        attributes.insert(name(41), value(2));
+
+        let pairs = attributes.iter().collect::<Vec<_>>();
+
+        assert_eq!(pairs.len(), 2);
+        assert!(pairs[0] == (name(40), value(1)));
+        assert!(pairs[1] == (name(41), value(2)));
+    }
+
+    #[test]
+    fn test_attributes_each_value() {
+        let mut attributes = Attributes::new();
+        let mut values = Vec::new();
+
+        attributes.insert(name(50), value(1));
+        attributes.each_value(|value| values.push(*value));
+
+        assert_eq!(values.len(), 1);
+        assert!(values[0] == value(1));
+    }
+
+    #[test]
+    fn test_attributes_size() {
+        assert_eq!(mem::size_of::<Attributes>(), 32);
+    }
+}
@@ -1,7 +1,9 @@
 //! Structures for encoding virtual machine instructions.
 
+use std::mem;
+
 /// Enum containing all possible instruction types.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(u8)]
 pub enum Opcode {
     Allocate,
@@ -76,6 +78,7 @@ pub enum Opcode {
     ProcessAddDeferToCaller,
     ProcessCurrent,
     ProcessIdentifier,
+    ProcessReceiveMatching,
     ProcessReceiveMessage,
     ProcessSendMessage,
     ProcessSetBlocking,
@@ -100,9 +103,193 @@ pub enum Opcode {
     StringSize,
     TailCall,
     Throw,
+
+    // The opcodes below are superinstructions: instructions produced by
+    // combining other instructions when loading bytecode. These opcodes can't
+    // be used in bytecode images, and must come after all other opcodes.
+    GetLocalGetAttribute,
+    GetAttributeRunBlockWithReceiver,
+    IntegerCompareGotoIfFalse,
+}
+
+/// The number of opcodes, including superinstructions.
+pub const OPCODE_COUNT: usize = Opcode::IntegerCompareGotoIfFalse as usize + 1;
+
+/// The kind of value an instruction argument refers to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operand {
+    /// A register of the current ExecutionContext.
+    Register,
+
+    /// A local variable of the current binding.
+    Local,
+
+    /// An index into the literals of the current module.
+    Literal,
+
+    /// A literal index split into two arguments: the upper 16 bits, followed by
+    /// the lower 16 bits.
+    WideLiteral,
+
+    /// An index into the code objects of the current CompiledCode.
+    CodeObject,
+
+    /// The index of an instruction to jump to.
+    Jump,
+
+    /// A global variable of the current module.
+    Global,
+
+    /// A range of registers, made up of two arguments: the first register and
+    /// the number of registers.
+    RegisterRange,
+
+    /// A value that is used as-is, such as a flag or a binding depth.
+    Value,
+}
+
+impl Operand {
+    /// Returns the number of instruction arguments this operand occupies.
+    pub fn width(self) -> usize {
+        match self {
+            Operand::WideLiteral | Operand::RegisterRange => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl Opcode {
+    /// Converts a raw byte into an Opcode, if the byte is a valid opcode.
+    ///
+    /// Superinstructions are not valid opcodes, as they can't be used in
+    /// bytecode images.
+    pub fn from_u8(byte: u8) -> Option<Opcode> {
+        if byte <= Opcode::Throw as u8 {
+            Some(unsafe { mem::transmute::<u8, Opcode>(byte) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the operands of instructions using this opcode, in the order
+    /// they are stored in the instruction arguments.
+    #[cfg_attr(feature = "cargo-clippy", allow(match_same_arms))]
+    pub fn operands(self) -> &'static [Operand] {
+        use Operand::*;
+
+        match self {
+            Opcode::ProcessTerminateCurrent => &[],
+            Opcode::BlockGetReceiver
+            | Opcode::Close
+            | Opcode::Exit
+            | Opcode::GeneratorResume
+            | Opcode::GeneratorYield
+            | Opcode::GetFalse
+            | Opcode::GetNil
+            | Opcode::GetTrue
+            | Opcode::MoveResult
+            | Opcode::Panic
+            | Opcode::ProcessCurrent
+            | Opcode::ProcessSuspendCurrent => &[Register],
+            Opcode::Allocate
+            | Opcode::AllocatePermanent
+            | Opcode::ArrayLength
+            | Opcode::ByteArrayFromArray
+            | Opcode::ByteArrayLength
+            | Opcode::CopyBlocks
+            | Opcode::CopyRegister
+            | Opcode::ExternalFunctionLoad
+            | Opcode::GeneratorValue
+            | Opcode::GetBuiltinPrototype
+            | Opcode::GetPrototype
+            | Opcode::ModuleGet
+            | Opcode::ModuleLoad
+            | Opcode::ProcessAddDeferToCaller
+            | Opcode::ProcessIdentifier
+            | Opcode::ProcessReceiveMessage
+            | Opcode::ProcessSetBlocking
+            | Opcode::ProcessSetPinned
+            | Opcode::ProcessSpawn
+            | Opcode::StringLength
+            | Opcode::StringSize => &[Register, Register],
+            Opcode::ArrayAt
+            | Opcode::ArrayRemove
+            | Opcode::AttributeExists
+            | Opcode::ByteArrayAt
+            | Opcode::ByteArrayEquals
+            | Opcode::ByteArrayRemove
+            | Opcode::FloatAdd
+            | Opcode::FloatDiv
+            | Opcode::FloatEquals
+            | Opcode::FloatGreater
+            | Opcode::FloatGreaterOrEqual
+            | Opcode::FloatMod
+            | Opcode::FloatMul
+            | Opcode::FloatSmaller
+            | Opcode::FloatSmallerOrEqual
+            | Opcode::FloatSub
+            | Opcode::GetAttribute
+            | Opcode::GetAttributeInSelf
+            | Opcode::IntegerAdd
+            | Opcode::IntegerBitwiseAnd
+            | Opcode::IntegerBitwiseOr
+            | Opcode::IntegerBitwiseXor
+            | Opcode::IntegerDiv
+            | Opcode::IntegerEquals
+            | Opcode::IntegerGreater
+            | Opcode::IntegerGreaterOrEqual
+            | Opcode::IntegerMod
+            | Opcode::IntegerMul
+            | Opcode::IntegerShiftLeft
+            | Opcode::IntegerShiftRight
+            | Opcode::IntegerSmaller
+            | Opcode::IntegerSmallerOrEqual
+            | Opcode::IntegerSub
+            | Opcode::ObjectEquals
+            | Opcode::ProcessReceiveMatching
+            | Opcode::ProcessSendMessage
+            | Opcode::StringByte
+            | Opcode::StringEquals => &[Register, Register, Register],
+            Opcode::ArraySet | Opcode::ByteArraySet | Opcode::SetAttribute => {
+                &[Register, Register, Register, Register]
+            }
+            Opcode::ArrayAllocate | Opcode::RunBlock | Opcode::StringConcat => {
+                &[Register, RegisterRange]
+            }
+            Opcode::ExternalFunctionCall | Opcode::RunBlockWithReceiver => {
+                &[Register, Register, RegisterRange]
+            }
+            Opcode::GeneratorAllocate => {
+                &[Register, Register, Register, RegisterRange]
+            }
+            Opcode::TailCall => &[RegisterRange],
+            Opcode::SetLiteral => &[Register, Literal],
+            Opcode::SetLiteralWide => &[Register, WideLiteral],
+            Opcode::GetLocal | Opcode::LocalExists => &[Register, Local],
+            Opcode::SetLocal => &[Local, Register],
+            Opcode::GetParentLocal => &[Register, Value, Value],
+            Opcode::SetParentLocal => &[Value, Value, Register],
+            Opcode::GetGlobal => &[Register, Global],
+            Opcode::SetGlobal => &[Register, Global, Register],
+            Opcode::SetBlock => &[Register, CodeObject, Register],
+            Opcode::Return | Opcode::Throw => &[Value, Register],
+            Opcode::Goto => &[Jump],
+            Opcode::GotoIfFalse | Opcode::GotoIfTrue => &[Jump, Register],
+            Opcode::GetLocalGetAttribute => {
+                &[Register, Register, Register, Value, Local]
+            }
+            Opcode::GetAttributeRunBlockWithReceiver => {
+                &[Register, Register, Register, Value, RegisterRange]
+            }
+            Opcode::IntegerCompareGotoIfFalse => {
+                &[Register, Register, Register, Jump, Value]
+            }
+        }
+    }
 }
 
 /// A fixed-width VM instruction.
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Instruction {
     /// The instruction opcode/type.
     pub opcode: Opcode,
@@ -134,6 +321,11 @@ impl Instruction {
     pub fn arg(&self, index: usize) -> u16 {
         self.arguments[index]
     }
+
+    /// Sets the value of the given instruction argument.
+    pub fn set_arg(&mut self, index: usize, value: u16) {
+        self.arguments[index] = value;
+    }
 }
 
 #[cfg(test)]
@@ -152,6 +344,29 @@ mod tests {
         assert_eq!(ins.arg(0), 1);
     }
 
+    #[test]
+    fn test_opcode_from_u8() {
+        assert_eq!(Opcode::from_u8(0), Some(Opcode::Allocate));
+        assert_eq!(Opcode::from_u8(Opcode::Throw as u8), Some(Opcode::Throw));
+        assert_eq!(Opcode::from_u8(Opcode::Throw as u8 + 1), None);
+    }
+
+    #[test]
+    fn test_opcode_operands() {
+        assert_eq!(
+            Opcode::SetLiteral.operands(),
+            &[Operand::Register, Operand::Literal]
+        );
+
+        for byte in 0..(OPCODE_COUNT as u8) {
+            let opcode: Opcode = unsafe { mem::transmute(byte) };
+            let width: usize =
+                opcode.operands().iter().map(|op| op.width()).sum();
+
+            assert!(width <= 6, "{:?} uses too many arguments", opcode);
+        }
+    }
+
     #[test]
     fn test_type_size() {
         assert_eq!(size_of::<Instruction>(), 16);
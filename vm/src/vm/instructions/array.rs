@@ -57,9 +57,19 @@ pub fn array_set(
 
 #[inline(always)]
 pub fn array_get(
+    process: &RcProcess,
     array_ptr: ObjectPointer,
     index_ptr: ObjectPointer,
 ) -> Result<ObjectPointer, RuntimeError> {
+    if let Ok(values) = array_ptr.frozen_array_value() {
+        let index = slicing::slice_index_to_usize(index_ptr, values.len())?;
+
+        return values
+            .get(index)
+            .map(|value| process.allocate_frozen(value))
+            .ok_or_else(|| RuntimeError::out_of_bounds(index));
+    }
+
     let vector = array_ptr.array_value()?;
     let index = slicing::slice_index_to_usize(index_ptr, vector.len())?;
 
@@ -90,7 +100,11 @@ pub fn array_length(
     process: &RcProcess,
     array_ptr: ObjectPointer,
 ) -> Result<ObjectPointer, String> {
-    let vector = array_ptr.array_value()?;
+    let length = if let Ok(values) = array_ptr.frozen_array_value() {
+        values.len()
+    } else {
+        array_ptr.array_value()?.len()
+    };
 
-    Ok(process.allocate_usize(vector.len(), state.integer_prototype))
+    Ok(process.allocate_usize(length, state.integer_prototype))
 }
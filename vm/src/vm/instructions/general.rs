@@ -56,12 +56,15 @@ pub fn set_parent_local(
     depth: u16,
     value: ObjectPointer,
 ) -> Result<(), String> {
-    if let Some(binding) = context.binding.find_parent(depth as usize) {
+    if let Some(binding) = context
+        .binding
+        .find_parent_with_local(depth as usize, local)
+    {
         binding.set_local(local, value);
 
         Ok(())
     } else {
-        Err(format!("No binding for depth {}", depth))
+        Err(undefined_parent_local(local, depth))
     }
 }
 
@@ -71,13 +74,23 @@ pub fn get_parent_local(
     local: u16,
     depth: u16,
 ) -> Result<ObjectPointer, String> {
-    if let Some(binding) = context.binding.find_parent(depth as usize) {
+    if let Some(binding) = context
+        .binding
+        .find_parent_with_local(depth as usize, local)
+    {
         Ok(binding.get_local(local))
     } else {
-        Err(format!("No binding for depth {}", depth))
+        Err(undefined_parent_local(local, depth))
     }
 }
 
+fn undefined_parent_local(local: u16, depth: u16) -> String {
+    format!(
+        "The local variable {} doesn't exist in the binding at depth {}",
+        local, depth
+    )
+}
+
 #[inline(always)]
 pub fn set_global(
     state: &RcState,
@@ -26,7 +26,8 @@ pub fn module_load_string(
     state: &RcState,
     name: &str,
 ) -> Result<(ObjectPointer, Block, bool), String> {
-    let (mod_ptr, exec) = state.modules.lock().get_for_execution(name)?;
+    let (mod_ptr, exec) =
+        state.modules.lock().get_for_execution(state, name)?;
     let module = mod_ptr.module_value()?;
     let block = Block::new(module.code(), None, mod_ptr, module);
 
@@ -40,5 +41,5 @@ pub fn module_get(
 ) -> Result<ObjectPointer, String> {
     let name = name_ptr.string_value()?;
 
-    state.modules.lock().get(name)
+    state.modules.lock().get(state, name)
 }
@@ -1,5 +1,6 @@
 //! VM functions for working with Inko objects.
 use crate::immix::copy_object::CopyObject;
+use crate::inline_cache::InlineCache;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
 use crate::process::RcProcess;
@@ -66,6 +67,7 @@ pub fn get_builtin_prototype(
         19 => state.generator_prototype,
         20 => state.trait_prototype,
         21 => state.child_process_prototype,
+        22 => state.process_exit_prototype,
         _ => return Err(format!("Invalid prototype identifier: {}", id_int)),
     };
 
@@ -98,6 +100,101 @@ pub fn get_attribute_in_self(
         .unwrap_or(state.nil_object)
 }
 
+/// Looks up an attribute using an inline cache.
+#[inline(always)]
+pub fn get_attribute_cached(
+    state: &RcState,
+    cache: &InlineCache,
+    rec_ptr: ObjectPointer,
+    name_ptr: ObjectPointer,
+) -> ObjectPointer {
+    let name = state.intern_pointer(name_ptr).unwrap_or(name_ptr);
+
+    // The cache is keyed on the prototype of the receiver, so attributes
+    // defined on the receiver itself must be checked first.
+    if !rec_ptr.is_tagged_integer() {
+        if let Some(value) = rec_ptr.get().lookup_attribute_in_self(name) {
+            return value;
+        }
+    }
+
+    if let Some(proto) = rec_ptr.prototype(state) {
+        lookup_cached(state, cache, proto, name, true)
+    } else {
+        state.nil_object
+    }
+}
+
+/// Looks up an attribute in the receiver using an inline cache.
+#[inline(always)]
+pub fn get_attribute_in_self_cached(
+    state: &RcState,
+    cache: &InlineCache,
+    rec_ptr: ObjectPointer,
+    name_ptr: ObjectPointer,
+) -> ObjectPointer {
+    let name = state.intern_pointer(name_ptr).unwrap_or(name_ptr);
+    let source = if rec_ptr.is_tagged_integer() {
+        state.integer_prototype
+    } else {
+        rec_ptr
+    };
+
+    lookup_cached(state, cache, source, name, false)
+}
+
+#[inline(always)]
+fn lookup_cached(
+    state: &RcState,
+    cache: &InlineCache,
+    source: ObjectPointer,
+    name: ObjectPointer,
+    walk_prototypes: bool,
+) -> ObjectPointer {
+    // The epoch must be read before looking up the attribute. If we were to
+    // read it afterwards, we may cache an outdated result in a newer epoch.
+    let epoch = state.inline_cache_epoch();
+
+    if let Some(value) = cache.lookup(source, name, epoch) {
+        return if value.is_null() {
+            state.nil_object
+        } else {
+            value
+        };
+    }
+
+    let mut cacheable = name.is_permanent();
+    let mut current = Some(source);
+    let mut found = None;
+
+    while let Some(pointer) = current {
+        let object = pointer.get();
+
+        cacheable = cacheable && pointer.is_permanent();
+        found = object.lookup_attribute_in_self(name);
+
+        if found.is_some() || !walk_prototypes {
+            break;
+        }
+
+        current = object.prototype();
+    }
+
+    if let Some(value) = found {
+        if cacheable && value.is_permanent() {
+            cache.store(source, name, epoch, value);
+        }
+
+        value
+    } else {
+        if cacheable {
+            cache.store(source, name, epoch, ObjectPointer::null());
+        }
+
+        state.nil_object
+    }
+}
+
 #[inline(always)]
 pub fn set_attribute(
     state: &RcState,
@@ -123,6 +220,10 @@ pub fn set_attribute(
 
     target_ptr.add_attribute(&process, name, value);
 
+    if target_ptr.is_permanent() {
+        state.invalidate_inline_caches();
+    }
+
     Ok(value)
 }
 
@@ -172,19 +273,23 @@ pub fn copy_blocks(
     let object = target_ptr.get_mut();
     let to_impl = source_ptr.get();
 
-    if let Some(map) = to_impl.attributes_map() {
-        for (key, val) in map.iter() {
+    if let Some(attributes) = to_impl.attributes_ref() {
+        for (key, val) in attributes.iter() {
             if val.block_value().is_err() {
                 continue;
             }
 
             let block =
-                copy_if_permanent!(state.permanent_allocator, *val, target_ptr);
+                copy_if_permanent!(state.permanent_allocator, val, target_ptr);
 
-            if object.lookup_attribute_in_self(*key).is_none() {
-                object.add_attribute(*key, block);
+            if object.lookup_attribute_in_self(key).is_none() {
+                object.add_attribute(key, block);
             }
         }
+
+        if target_ptr.is_permanent() {
+            state.invalidate_inline_caches();
+        }
     }
 
     Ok(())
@@ -200,6 +305,7 @@ mod tests {
     use super::*;
     use crate::config::Config;
     use crate::vm::state::State;
+    use crate::vm::test::setup;
 
     #[test]
     fn test_get_builtin_prototype() {
@@ -219,4 +325,168 @@ mod tests {
             get_builtin_prototype(&state, ObjectPointer::integer(-1)).is_err()
         );
     }
+
+    #[test]
+    fn test_get_attribute_cached() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+        let proto = state.permanent_allocator.lock().allocate_empty();
+        let receiver = process.allocate_empty();
+
+        receiver.set_prototype(proto);
+        proto.get_mut().add_attribute(name, state.true_object);
+
+        let epoch = state.inline_cache_epoch();
+
+        assert!(
+            get_attribute_cached(state, &cache, receiver, name)
+                == state.true_object
+        );
+        assert!(cache.lookup(proto, name, epoch).unwrap() == state.true_object);
+        assert!(
+            get_attribute_cached(state, &cache, receiver, name)
+                == state.true_object
+        );
+    }
+
+    #[test]
+    fn test_get_attribute_cached_with_attribute_in_receiver() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+        let proto = state.permanent_allocator.lock().allocate_empty();
+        let receiver = process.allocate_empty();
+
+        receiver.set_prototype(proto);
+        proto.get_mut().add_attribute(name, state.true_object);
+        get_attribute_cached(state, &cache, receiver, name);
+        receiver.add_attribute(&process, name, state.false_object);
+
+        assert!(
+            get_attribute_cached(state, &cache, receiver, name)
+                == state.false_object
+        );
+    }
+
+    #[test]
+    fn test_get_attribute_cached_with_missing_attribute() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+        let proto = state.permanent_allocator.lock().allocate_empty();
+        let receiver = process.allocate_empty();
+
+        receiver.set_prototype(proto);
+
+        assert!(
+            get_attribute_cached(state, &cache, receiver, name)
+                == state.nil_object
+        );
+        assert!(cache
+            .lookup(proto, name, state.inline_cache_epoch())
+            .unwrap()
+            .is_null());
+    }
+
+    #[test]
+    fn test_get_attribute_cached_with_regular_prototype() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+        let proto = process.allocate_empty();
+        let receiver = process.allocate_empty();
+
+        receiver.set_prototype(proto);
+        proto.add_attribute(&process, name, state.true_object);
+
+        assert!(
+            get_attribute_cached(state, &cache, receiver, name)
+                == state.true_object
+        );
+        assert!(cache
+            .lookup(proto, name, state.inline_cache_epoch())
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_attribute_in_self_cached() {
+        let (machine, _block, _process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+        let proto = state.permanent_allocator.lock().allocate_empty();
+        let receiver = state.permanent_allocator.lock().allocate_empty();
+
+        receiver.set_prototype(proto);
+        proto.get_mut().add_attribute(name, state.true_object);
+
+        assert!(
+            get_attribute_in_self_cached(state, &cache, receiver, name)
+                == state.nil_object
+        );
+
+        receiver.get_mut().add_attribute(name, state.false_object);
+        state.invalidate_inline_caches();
+
+        assert!(
+            get_attribute_in_self_cached(state, &cache, receiver, name)
+                == state.false_object
+        );
+    }
+
+    #[test]
+    fn test_set_attribute_invalidates_inline_caches() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let cache = InlineCache::new();
+        let name = state.intern_string("foo".to_string());
+        let proto = state.permanent_allocator.lock().allocate_empty();
+        let receiver = process.allocate_empty();
+
+        receiver.set_prototype(proto);
+        proto.get_mut().add_attribute(name, state.true_object);
+        get_attribute_cached(state, &cache, receiver, name);
+        set_attribute(state, &process, proto, name, state.false_object)
+            .unwrap();
+
+        assert!(
+            get_attribute_cached(state, &cache, receiver, name)
+                == state.false_object
+        );
+    }
+
+    #[test]
+    fn test_set_attribute_with_regular_object() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let name = state.intern_string("foo".to_string());
+        let object = process.allocate_empty();
+        let epoch = state.inline_cache_epoch();
+
+        set_attribute(state, &process, object, name, state.true_object)
+            .unwrap();
+
+        assert_eq!(state.inline_cache_epoch(), epoch);
+    }
+
+    #[test]
+    fn test_set_attribute_with_non_string_name() {
+        let (machine, _block, process) = setup();
+        let state = &machine.state;
+        let name = process.allocate_empty();
+        let object = process.allocate_empty();
+
+        set_attribute(state, &process, object, name, state.true_object)
+            .unwrap();
+
+        let attributes = object.get().attributes_ref().unwrap();
+
+        assert!(attributes.shape().is_none());
+        assert!(attributes.get(name) == Some(state.true_object));
+    }
 }
@@ -1,15 +1,20 @@
 //! VM functions for working with Inko processes.
 use crate::block::Block;
 use crate::duration;
+use crate::frozen::{FrozenObject, FrozenValue};
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
-use crate::process::{Process, RcProcess, RescheduleRights};
+use crate::object_value::ObjectValue;
+use crate::process::{ExitReason, Process, RcProcess, RescheduleRights};
 use crate::runtime_error::RuntimeError;
 use crate::scheduler::process_worker::ProcessWorker;
 use crate::vm::state::RcState;
+use std::time::{Duration, Instant};
 
 #[inline(always)]
 pub fn process_allocate(state: &RcState, block: &Block) -> RcProcess {
+    state.scheduler.increment_live_processes();
+
     Process::from_block(block, state.global_allocator.clone(), &state.config)
 }
 
@@ -40,23 +45,31 @@ pub fn process_spawn(
     Ok(new_proc_ptr)
 }
 
+/// Sends a message to a process.
+///
+/// If the mailbox of the receiver is full and the sender has to wait for room,
+/// the sender is suspended and `None` is returned.
 #[inline(always)]
 pub fn process_send_message(
     state: &RcState,
     sender: &RcProcess,
     receiver_ptr: ObjectPointer,
     msg: ObjectPointer,
-) -> Result<ObjectPointer, RuntimeError> {
+) -> Result<Option<ObjectPointer>, RuntimeError> {
     let receiver = receiver_ptr.process_value()?;
 
     if receiver == sender {
         receiver.send_message_from_self(msg);
-    } else {
-        receiver.send_message_from_external_process(msg)?;
+    } else if receiver
+        .send_bounded_message_from_external_process(sender, msg)?
+    {
         attempt_to_reschedule_process(state, &receiver);
+    } else {
+        state.deadlock_detector.register(sender);
+        return Ok(None);
     }
 
-    Ok(msg)
+    Ok(Some(msg))
 }
 
 #[inline(always)]
@@ -66,6 +79,7 @@ pub fn process_receive_message(
 ) -> Result<Option<ObjectPointer>, ObjectPointer> {
     if let Some(msg) = process.receive_message() {
         process.no_longer_waiting_for_message();
+        resume_blocked_sender(state, process);
 
         Ok(Some(msg))
     } else if process.is_waiting_for_message() {
@@ -78,6 +92,39 @@ pub fn process_receive_message(
     }
 }
 
+/// Receives the oldest message that matches the given matcher.
+///
+/// A message matches if its prototype is the matcher, or if the message is an
+/// Array of which the first value (the tag) is equal to the matcher.
+#[inline(always)]
+pub fn process_receive_matching(
+    state: &RcState,
+    process: &RcProcess,
+    matcher: ObjectPointer,
+) -> Result<Option<ObjectPointer>, ObjectPointer> {
+    let found = process
+        .receive_matching_message(|msg| message_matches(state, msg, matcher));
+
+    if let Some(msg) = found {
+        process.no_longer_waiting_for_message();
+        process.unset_receive_deadline();
+        resume_blocked_sender(state, process);
+
+        return Ok(Some(msg));
+    }
+
+    if let Some(deadline) = process.receive_deadline() {
+        if Instant::now() >= deadline {
+            process.no_longer_waiting_for_message();
+            process.unset_receive_deadline();
+
+            return Err(state.intern_string("The timeout expired".to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
 #[inline(always)]
 pub fn wait_for_message(
     state: &RcState,
@@ -86,12 +133,55 @@ pub fn wait_for_message(
 ) -> Result<(), String> {
     let wait_for = duration::from_f64(timeout_ptr.float_value()?)?;
 
+    suspend_for_message(state, process, wait_for);
+    Ok(())
+}
+
+/// Suspends a process until it receives a message that may match a selective
+/// receive.
+///
+/// Unlike `wait_for_message`, the timeout is measured from the first time the
+/// process started waiting, instead of restarting every time the process is
+/// woken up by a message that doesn't match.
+#[inline(always)]
+pub fn wait_for_matching_message(
+    state: &RcState,
+    process: &RcProcess,
+    timeout_ptr: ObjectPointer,
+) -> Result<(), String> {
+    let wait_for = duration::from_f64(timeout_ptr.float_value()?)?;
+    let remaining = wait_for.map(|duration| {
+        let now = Instant::now();
+        let deadline = process.receive_deadline().unwrap_or_else(|| {
+            let deadline = now + duration;
+
+            process.set_receive_deadline(deadline);
+            deadline
+        });
+
+        if deadline > now {
+            deadline - now
+        } else {
+            Duration::from_secs(0)
+        }
+    });
+
+    suspend_for_message(state, process, remaining);
+    Ok(())
+}
+
+fn suspend_for_message(
+    state: &RcState,
+    process: &RcProcess,
+    wait_for: Option<Duration>,
+) {
     process.waiting_for_message();
 
     if let Some(duration) = wait_for {
         state.timeout_worker.suspend(process.clone(), duration);
     } else {
         process.suspend_without_timeout();
+        state.deadlock_detector.register(process);
     }
 
     if process.has_messages() {
@@ -100,8 +190,77 @@ pub fn wait_for_message(
         // our process may be suspended until it is sent another message.
         attempt_to_reschedule_process(state, process);
     }
+}
 
-    Ok(())
+/// Reschedules a process waiting for room in the mailbox of the given process.
+fn resume_blocked_sender(state: &RcState, process: &RcProcess) {
+    if let Some(sender) = process.take_blocked_sender() {
+        if sender.acquire_rescheduling_rights().are_acquired() {
+            state.scheduler.schedule(sender);
+        }
+    }
+}
+
+/// Returns true if a message matches the matcher of a selective receive.
+fn message_matches(
+    state: &RcState,
+    message: ObjectPointer,
+    matcher: ObjectPointer,
+) -> bool {
+    if message.prototype(state) == Some(matcher) {
+        return true;
+    }
+
+    if let Ok(values) = message.frozen_array_value() {
+        return values
+            .first()
+            .map(|tag| match tag {
+                FrozenValue::Pointer(tag) => tags_equal(*tag, matcher),
+                FrozenValue::Object(tag) => frozen_tag_equal(tag, matcher),
+            })
+            .unwrap_or(false);
+    }
+
+    match message.array_value() {
+        Ok(values) => values
+            .first()
+            .map(|tag| tags_equal(*tag, matcher))
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Returns true if two message tags are equal.
+///
+/// Messages are copied when sent, so tags are compared by value for Integers
+/// and Strings, and by identity for all other objects.
+fn tags_equal(tag: ObjectPointer, matcher: ObjectPointer) -> bool {
+    if tag == matcher {
+        return true;
+    }
+
+    if let (Ok(a), Ok(b)) = (tag.integer_value(), matcher.integer_value()) {
+        return a == b;
+    }
+
+    if let (Ok(a), Ok(b)) = (tag.string_value(), matcher.string_value()) {
+        return a == b;
+    }
+
+    false
+}
+
+/// Returns true if the tag of a frozen message is equal to a matcher.
+fn frozen_tag_equal(tag: &FrozenObject, matcher: ObjectPointer) -> bool {
+    match tag.value() {
+        ObjectValue::Integer(a) => {
+            matcher.integer_value().map(|b| *a == b).unwrap_or(false)
+        }
+        ObjectValue::String(a) => {
+            matcher.string_value().map(|b| **a == *b).unwrap_or(false)
+        }
+        _ => false,
+    }
 }
 
 #[inline(always)]
@@ -216,6 +375,67 @@ pub fn process_unwind_until_defining_scope(process: &RcProcess) {
     }
 }
 
+/// Allocates a message describing the termination of a process.
+///
+/// The message is allocated using the allocator of `process`, which need not be
+/// the process that terminated.
+pub fn process_exit_message(
+    state: &RcState,
+    process: &RcProcess,
+    terminated: &RcProcess,
+    reason: &ExitReason,
+) -> ObjectPointer {
+    let message =
+        process.allocate(object_value::none(), state.process_exit_prototype);
+
+    let proc_attr = state.intern_string("@process".to_string());
+    let reason_attr = state.intern_string("@reason".to_string());
+    let proc_ptr = process.allocate(
+        object_value::process(terminated.clone()),
+        state.process_prototype,
+    );
+
+    let reason_ptr = match reason {
+        ExitReason::Normal => state.nil_object,
+        ExitReason::Panic(message) => process.allocate(
+            object_value::string(message.clone()),
+            state.string_prototype,
+        ),
+    };
+
+    message.add_attribute(process, proc_attr, proc_ptr);
+    message.add_attribute(process, reason_attr, reason_ptr);
+    message
+}
+
+/// Sends an exit message to all processes monitoring or linked to a
+/// terminating process.
+///
+/// This must be called before the process is terminated, as the message is
+/// allocated on the heap of the terminating process before being copied to the
+/// watchers.
+pub fn process_notify_watchers(
+    state: &RcState,
+    process: &RcProcess,
+    reason: ExitReason,
+) {
+    let watchers = process.take_watchers(reason.clone());
+
+    if watchers.is_empty() {
+        return;
+    }
+
+    let message = process_exit_message(state, process, process, &reason);
+
+    for watcher in watchers {
+        // Exit messages only contain values that can be copied, so sending
+        // them can't fail.
+        let _ = watcher.send_message_from_external_process(message);
+
+        attempt_to_reschedule_process(state, &watcher);
+    }
+}
+
 /// Attempts to reschedule the given process after it was sent a message.
 fn attempt_to_reschedule_process(state: &RcState, process: &RcProcess) {
     // The logic below is necessary as a process' state may change between
@@ -256,6 +476,7 @@ fn attempt_to_reschedule_process(state: &RcState, process: &RcProcess) {
                 if process.has_messages() {
                     process.acquire_rescheduling_rights().are_acquired()
                 } else {
+                    state.deadlock_detector.register(process);
                     false
                 }
             }
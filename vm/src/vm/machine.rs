@@ -1,12 +1,19 @@
 //! Virtual Machine for running instructions
+use crate::bytecode_parser::Image;
+use crate::config::PanicPolicy;
+use crate::execution_context::ExecutionContext;
 use crate::gc::collection::collect as collect_garbage;
+use crate::gc::snapshot::{self, HeapSnapshot};
+use crate::inline_cache::CACHE_ARGUMENT;
 use crate::integer_operations;
+#[cfg(feature = "jit")]
+use crate::jit;
 use crate::network_poller::Worker as NetworkPollerWorker;
 use crate::numeric::division::{FlooredDiv, OverflowingFlooredDiv};
 use crate::numeric::modulo::{Modulo, OverflowingModulo};
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
-use crate::process::RcProcess;
+use crate::process::{ExitReason, RcProcess};
 use crate::runtime_error::RuntimeError;
 use crate::scheduler::join_list::JoinList;
 use crate::scheduler::process_worker::ProcessWorker;
@@ -22,7 +29,7 @@ use crate::vm::instructions::module;
 use crate::vm::instructions::object;
 use crate::vm::instructions::process;
 use crate::vm::instructions::string;
-use crate::vm::state::RcState;
+use crate::vm::state::{MainProcessResult, RcState};
 use num_bigint::BigInt;
 use std::i32;
 use std::ops::{Add, Mul, Sub};
@@ -95,14 +102,62 @@ macro_rules! enter_context {
     }};
 }
 
+macro_rules! get_attribute {
+    ($state:expr, $context:ident, $instruction:ident, $rec:expr, $name:expr) => {{
+        let cache =
+            $context.code.inline_cache($instruction.arg(CACHE_ARGUMENT));
+
+        if let Some(cache) = cache {
+            object::get_attribute_cached(&$state, cache, $rec, $name)
+        } else {
+            object::get_attribute(&$state, $rec, $name)
+        }
+    }};
+}
+
+/// Runs the machine code of a context that was just entered, if any.
+macro_rules! run_machine_code {
+    ($state:expr, $context:ident, $index:ident) => {
+        #[cfg(feature = "jit")]
+        {
+            if $index == 0 {
+                if let Some(resume) = jit::run(&$state, $context) {
+                    $index = resume;
+                }
+            }
+        }
+    };
+}
+
 macro_rules! safepoint_and_reduce {
-    ($vm:expr, $worker:expr, $process:expr, $reductions:expr) => {{
-        let reduce_by = $vm.gc_safepoint(&$process, $worker);
+    (
+        $vm:expr,
+        $worker:expr,
+        $process:expr,
+        $reductions:expr,
+        $context:ident,
+        $index:ident
+    ) => {{
+        let reduce_by = match $vm.gc_safepoint(&$process, $worker) {
+            Ok(reductions) => reductions,
+            Err(RuntimeError::ErrorMessage(msg)) => {
+                throw_error_message!($vm, $process, msg, $context, $index);
+            }
+            Err(RuntimeError::Panic(msg)) => {
+                // The safepoint is reached right after entering a new context,
+                // so the instruction index already points to the instruction
+                // that failed.
+                return Err(msg);
+            }
+            Err(_) => unreachable!(),
+        };
+
+        $process.increment_reductions(reduce_by);
 
         if $reductions >= reduce_by {
             $reductions -= reduce_by;
         } else {
-            $vm.state.scheduler.schedule($process.clone());
+            $vm.state.scheduler.schedule_preempted($process.clone());
             return Ok(());
         }
     }};
@@ -191,15 +246,47 @@ impl Machine {
     /// This method will block the calling thread until the program finishes.
     pub fn start(&self, path: &str) {
         self.parse_image(path);
-        self.schedule_main_process();
+
+        let entry = self
+            .state
+            .modules
+            .lock()
+            .entry_point()
+            .expect("The module entry point is undefined")
+            .clone();
+
+        self.start_module(&entry).unwrap();
+    }
+
+    /// Starts the VM using a bytecode image that has already been parsed, such
+    /// as an image embedded in an executable.
+    ///
+    /// This method will block the calling thread until the program finishes.
+    pub fn start_image(&self, image: Image) {
+        let entry = image.entry_point.clone();
+
+        self.state.modules.lock().set_image(&self.state, image);
+        self.start_module(&entry).unwrap();
+    }
+
+    /// Runs the given module in the main process, blocking the calling thread
+    /// until the VM terminates.
+    ///
+    /// The module must be defined in an image that has already been parsed.
+    pub fn start_module(&self, name: &str) -> Result<(), String> {
+        self.schedule_main_process(name)?;
+        self.install_heap_snapshot_handler();
 
         let secondary_guard = self.start_blocking_threads();
+        let io_guard = self.start_io_threads();
         let timeout_guard = self.start_timeout_worker_thread();
 
         // The network poller doesn't produce a guard, because there's no
         // cross-platform way of waking up the system poller, so we just don't
         // wait for it to finish when terminating.
         let poller_guard = self.start_network_poller_thread();
+        let detector_guard = self.start_deadlock_detector_thread();
+        let profiler_guard = self.start_profiler_thread();
 
         // Starting the primary threads will block this thread, as the main
         // worker will run directly onto the current thread. As such, we must
@@ -210,11 +297,24 @@ impl Machine {
         // don't want to re-panic as this clutters the error output.
         if primary_guard.join().is_err()
             || secondary_guard.join().is_err()
+            || io_guard.join().is_err()
             || timeout_guard.join().is_err()
             || poller_guard.join().is_err()
+            || detector_guard
+                .into_iter()
+                .chain(profiler_guard)
+                .any(|guard| guard.join().is_err())
         {
             self.state.set_exit_status(1);
         }
+
+        if let Some(statistics) = self.state.opcode_statistics.as_ref() {
+            eprint!("{}", statistics.report());
+        }
+
+        self.write_profile();
+
+        Ok(())
     }
 
     fn start_primary_threads(&self) -> JoinList<()> {
@@ -225,6 +325,23 @@ impl Machine {
         self.state.scheduler.blocking_pool.start(self.clone())
     }
 
+    fn start_io_threads(&self) -> JoinList<()> {
+        let handles = (0..self.state.io_pool.threads())
+            .map(|index| {
+                let state = self.state.clone();
+
+                thread::Builder::new()
+                    .name(format!("io worker {}", index))
+                    .spawn(move || {
+                        state.io_pool.run(&state.scheduler);
+                    })
+                    .unwrap()
+            })
+            .collect();
+
+        JoinList::new(handles)
+    }
+
     fn start_timeout_worker_thread(&self) -> thread::JoinHandle<()> {
         let state = self.state.clone();
 
@@ -247,33 +364,90 @@ impl Machine {
             .unwrap()
     }
 
+    fn start_deadlock_detector_thread(&self) -> Option<thread::JoinHandle<()>> {
+        if !self.state.deadlock_detector.is_enabled() {
+            return None;
+        }
+
+        let state = self.state.clone();
+        let handle = thread::Builder::new()
+            .name("deadlock detector".to_string())
+            .spawn(move || {
+                state.deadlock_detector.run(&state);
+            })
+            .unwrap();
+
+        Some(handle)
+    }
+
+    fn start_profiler_thread(&self) -> Option<thread::JoinHandle<()>> {
+        self.state.profiler.as_ref()?;
+
+        let state = self.state.clone();
+        let handle = thread::Builder::new()
+            .name("profiler".to_string())
+            .spawn(move || {
+                if let Some(profiler) = state.profiler.as_ref() {
+                    profiler.run();
+                }
+            })
+            .unwrap();
+
+        Some(handle)
+    }
+
+    fn write_profile(&self) {
+        let profiler = if let Some(profiler) = self.state.profiler.as_ref() {
+            profiler
+        } else {
+            return;
+        };
+
+        if let Some(path) = self.state.config.profile.as_ref() {
+            if let Err(err) = profiler.write_file(path) {
+                eprintln!("{}", err);
+            }
+        }
+    }
+
+    fn install_heap_snapshot_handler(&self) {
+        if self.state.config.heap_snapshot_directory.is_none() {
+            return;
+        }
+
+        if let Err(err) = snapshot::install_signal_handler() {
+            eprintln!("{}", err);
+        }
+    }
+
     fn parse_image(&self, path: &str) {
         self.state.parse_image(path).unwrap();
     }
 
-    fn schedule_main_process(&self) {
-        let entry = self
-            .state
-            .modules
-            .lock()
-            .entry_point()
-            .expect("The module entry point is undefined")
-            .clone();
-
+    fn schedule_main_process(&self, name: &str) -> Result<(), String> {
         let process = {
-            let (_, block, _) =
-                module::module_load_string(&self.state, &entry).unwrap();
+            let (_, block, _) = module::module_load_string(&self.state, name)?;
 
             process::process_allocate(&self.state, &block)
         };
 
         process.set_main();
         self.state.scheduler.schedule_on_main_thread(process);
+
+        Ok(())
     }
 
     pub fn run(&mut self, worker: &mut ProcessWorker, process: &RcProcess) {
+        // A process linked to a process that panicked is terminated the next
+        // time it runs, as it may be running on another thread when the linked
+        // process panics.
+        if let Some(message) = process.take_linked_panic() {
+            self.panic(worker, process, &message);
+            return;
+        }
+
         if let Err(message) = self.run_loop(worker, process) {
-            self.panic(process, &message);
+            self.panic(worker, process, &message);
         }
     }
 
@@ -286,10 +460,24 @@ impl Machine {
         worker: &mut ProcessWorker,
         process: &RcProcess,
     ) -> Result<(), String> {
-        let mut reductions = self.state.config.reductions;
+        let mut reductions =
+            process.priority().reductions(self.state.config.reductions);
         let mut context;
         let mut index;
         let mut instruction;
+        let mut previous = None;
+
+        let mut sample_epoch = self
+            .state
+            .profiler
+            .as_ref()
+            .map(|profiler| profiler.epoch())
+            .unwrap_or(0);
+
+        // Opcode statistics and profiling are rarely enabled, so we check this
+        // once instead of checking the VM state for every instruction.
+        let instrumented = self.state.opcode_statistics.is_some()
+            || self.state.profiler.is_some();
 
         reset_context!(process, context, index);
 
@@ -297,6 +485,17 @@ impl Machine {
             instruction = unsafe { context.code.instruction(index) };
             index += 1;
 
+            if instrumented {
+                context.instruction_index = index;
+
+                self.instrument(
+                    context,
+                    instruction.opcode,
+                    &mut previous,
+                    &mut sample_epoch,
+                );
+            }
+
             match instruction.opcode {
                 Opcode::SetLiteral => {
                     let reg = instruction.arg(0);
@@ -415,7 +614,9 @@ impl Machine {
                     }
 
                     reset_context!(process, context, index);
-                    safepoint_and_reduce!(self, worker, process, reductions);
+                    safepoint_and_reduce!(
+                        self, worker, process, reductions, context, index
+                    );
                 }
                 Opcode::GotoIfFalse => {
                     let val = context.get_register(instruction.arg(1));
@@ -612,7 +813,7 @@ impl Machine {
                     let ary = context.get_register(instruction.arg(1));
                     let idx = context.get_register(instruction.arg(2));
                     let res = try_error!(
-                        array::array_get(ary, idx),
+                        array::array_get(process, ary, idx),
                         self,
                         process,
                         context,
@@ -705,7 +906,13 @@ impl Machine {
                     let reg = instruction.arg(0);
                     let rec = context.get_register(instruction.arg(1));
                     let name = context.get_register(instruction.arg(2));
-                    let res = object::get_attribute(&self.state, rec, name);
+                    let res = get_attribute!(
+                        self.state,
+                        context,
+                        instruction,
+                        rec,
+                        name
+                    );
 
                     context.set_register(reg, res);
                 }
@@ -713,8 +920,19 @@ impl Machine {
                     let reg = instruction.arg(0);
                     let rec = context.get_register(instruction.arg(1));
                     let name = context.get_register(instruction.arg(2));
-                    let res =
-                        object::get_attribute_in_self(&self.state, rec, name);
+                    let cache = context
+                        .code
+                        .inline_cache(instruction.arg(CACHE_ARGUMENT));
+                    let res = if let Some(cache) = cache {
+                        object::get_attribute_in_self_cached(
+                            &self.state,
+                            cache,
+                            rec,
+                            name,
+                        )
+                    } else {
+                        object::get_attribute_in_self(&self.state, rec, name)
+                    };
 
                     context.set_register(reg, res);
                 }
@@ -744,6 +962,13 @@ impl Machine {
                     let reg = instruction.arg(0);
                     let rec = context.get_register(instruction.arg(1));
                     let msg = context.get_register(instruction.arg(2));
+
+                    // If the mailbox of the receiver is full, we may be
+                    // suspended and rescheduled by another thread before we
+                    // return. The instruction index must be saved first, so
+                    // the send is retried when we resume.
+                    context.instruction_index = index - 1;
+
                     let res = try_error!(
                         process::process_send_message(
                             &self.state,
@@ -757,7 +982,43 @@ impl Machine {
                         index
                     );
 
-                    context.set_register(reg, res);
+                    if let Some(res) = res {
+                        context.set_register(reg, res);
+                    } else {
+                        return Ok(());
+                    }
+                }
+                Opcode::ProcessReceiveMatching => {
+                    let reg = instruction.arg(0);
+                    let matcher = context.get_register(instruction.arg(1));
+                    let time = context.get_register(instruction.arg(2));
+
+                    match process::process_receive_matching(
+                        &self.state,
+                        process,
+                        matcher,
+                    ) {
+                        Ok(Some(message)) => {
+                            context.set_register(reg, message);
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(err) => {
+                            throw_value!(self, process, err, context, index);
+                        }
+                    }
+
+                    // See ProcessReceiveMessage for why the instruction index
+                    // must be saved first.
+                    context.instruction_index = index - 1;
+
+                    process::wait_for_matching_message(
+                        &self.state,
+                        process,
+                        time,
+                    )?;
+
+                    return Ok(());
                 }
                 Opcode::ProcessReceiveMessage => {
                     let reg = instruction.arg(0);
@@ -849,6 +1110,7 @@ impl Machine {
 
                     block::run_block(process, context, block, start, args)?;
                     enter_context!(process, context, index);
+                    run_machine_code!(self.state, context, index);
                 }
                 Opcode::SetGlobal => {
                     let reg = instruction.arg(0);
@@ -893,7 +1155,9 @@ impl Machine {
 
                     block::tail_call(context, start, args);
                     reset_context!(process, context, index);
-                    safepoint_and_reduce!(self, worker, process, reductions);
+                    safepoint_and_reduce!(
+                        self, worker, process, reductions, context, index
+                    );
                 }
                 Opcode::CopyBlocks => {
                     let to = context.get_register(instruction.arg(0));
@@ -1070,6 +1334,83 @@ impl Machine {
                     )?;
 
                     enter_context!(process, context, index);
+                    run_machine_code!(self.state, context, index);
+                }
+                Opcode::GetLocalGetAttribute => {
+                    let reg = instruction.arg(0);
+                    let rec_reg = instruction.arg(1);
+                    let rec = general::get_local(context, instruction.arg(4));
+
+                    context.set_register(rec_reg, rec);
+
+                    let name = context.get_register(instruction.arg(2));
+                    let res = get_attribute!(
+                        self.state,
+                        context,
+                        instruction,
+                        rec,
+                        name
+                    );
+
+                    context.set_register(reg, res);
+                }
+                Opcode::GetAttributeRunBlockWithReceiver => {
+                    let reg = instruction.arg(0);
+                    let rec = context.get_register(instruction.arg(1));
+                    let name = context.get_register(instruction.arg(2));
+                    let res = get_attribute!(
+                        self.state,
+                        context,
+                        instruction,
+                        rec,
+                        name
+                    );
+
+                    context.set_register(reg, res);
+
+                    // The receiver register may be the same as the register
+                    // the block is stored in.
+                    let rec = context.get_register(instruction.arg(1));
+                    let start = instruction.arg(4);
+                    let args = instruction.arg(5);
+
+                    block::run_block_with_receiver(
+                        process, context, res, rec, start, args,
+                    )?;
+
+                    enter_context!(process, context, index);
+                    run_machine_code!(self.state, context, index);
+                }
+                Opcode::IntegerCompareGotoIfFalse => {
+                    match Opcode::from_u8(instruction.arg(4) as u8) {
+                        Some(Opcode::IntegerSmaller) => {
+                            integer_bool_op!(self.state, context, instruction, <);
+                        }
+                        Some(Opcode::IntegerSmallerOrEqual) => {
+                            integer_bool_op!(self.state, context, instruction, <=);
+                        }
+                        Some(Opcode::IntegerGreater) => {
+                            integer_bool_op!(self.state, context, instruction, >);
+                        }
+                        Some(Opcode::IntegerGreaterOrEqual) => {
+                            integer_bool_op!(self.state, context, instruction, >=);
+                        }
+                        Some(Opcode::IntegerEquals) => {
+                            integer_bool_op!(self.state, context, instruction, ==);
+                        }
+                        _ => {
+                            return Err(format!(
+                                "The integer comparison {} is invalid",
+                                instruction.arg(4)
+                            ));
+                        }
+                    }
+
+                    let val = context.get_register(instruction.arg(0));
+
+                    if is_false!(self.state, val) {
+                        index = instruction.arg(3) as usize;
+                    }
                 }
                 Opcode::ProcessAddDeferToCaller => {
                     let reg = instruction.arg(0);
@@ -1153,7 +1494,9 @@ impl Machine {
                     }
 
                     enter_context!(process, context, index);
-                    safepoint_and_reduce!(self, worker, process, reductions);
+                    safepoint_and_reduce!(
+                        self, worker, process, reductions, context, index
+                    );
                 }
                 Opcode::GeneratorValue => {
                     let reg = instruction.arg(0);
@@ -1211,31 +1554,105 @@ impl Machine {
             worker.leave_exclusive_mode();
         }
 
-        process.terminate(&self.state);
+        process::process_notify_watchers(
+            &self.state,
+            process,
+            ExitReason::Normal,
+        );
 
-        // Terminate once the main process has finished execution.
+        // Terminate once the main process has finished execution. The main
+        // process itself isn't terminated, as this would release the memory
+        // of its result.
         if process.is_main() {
+            let result = process.take_result().unwrap_or(self.state.nil_object);
+
+            self.state
+                .set_main_process_result(MainProcessResult::Finished(
+                    process.clone(),
+                    result,
+                ));
             self.state.terminate(0);
+        } else {
+            process.terminate(&self.state);
         }
 
         Ok(())
     }
 
+    /// Records opcode statistics and profiler samples for an instruction that
+    /// is about to run.
+    ///
+    /// The instruction index of the context must point to the instruction
+    /// after the one that is about to run.
+    #[cold]
+    fn instrument(
+        &self,
+        context: &ExecutionContext,
+        opcode: Opcode,
+        previous: &mut Option<Opcode>,
+        sample_epoch: &mut usize,
+    ) {
+        if let Some(statistics) = self.state.opcode_statistics.as_ref() {
+            if let Some(previous) = *previous {
+                statistics.record(previous, opcode);
+            }
+
+            *previous = Some(opcode);
+        }
+
+        if let Some(profiler) = self.state.profiler.as_ref() {
+            let epoch = profiler.epoch();
+
+            if epoch != *sample_epoch {
+                *sample_epoch = epoch;
+
+                profiler.sample(context);
+            }
+        }
+    }
+
     /// Checks if a garbage collection run should be performed for the given
     /// process.
     ///
-    /// This method returns the number of reductions to apply.
+    /// This method returns the number of reductions to apply. An error is
+    /// returned if the process still exceeds its heap limit after collecting
+    /// garbage.
     fn gc_safepoint(
         &self,
         process: &RcProcess,
         worker: &ProcessWorker,
-    ) -> usize {
-        if process.should_collect_young_generation() {
-            collect_garbage(&self.state, &process, &worker.tracers);
-            GC_REDUCTION_COST
-        } else {
-            METHOD_REDUCTION_COST
+    ) -> Result<usize, RuntimeError> {
+        if let Some(dir) = self.state.config.heap_snapshot_directory.as_ref() {
+            snapshot::check_signals(dir, process);
+        }
+
+        if !process.should_collect_young_generation() {
+            return Ok(METHOD_REDUCTION_COST);
+        }
+
+        let limit_exceeded = process.heap_limit_exceeded();
+
+        collect_garbage(&self.state, &process, &worker.tracers);
+
+        // Requesting a snapshot also requests a full collection, so at this
+        // point the mark bytemaps tell us which objects are live.
+        if let Some(file) = process.take_heap_snapshot_request() {
+            let snapshot = HeapSnapshot::capture(&self.state, process);
+
+            if let Err(err) = snapshot.write_file(file) {
+                eprintln!(
+                    "Failed to write the heap snapshot of process {:#x}: {}",
+                    process.identifier(),
+                    err
+                );
+            }
+        }
+
+        if limit_exceeded && process.heap_limit_exceeded_after_collection() {
+            return Err(process.heap_limit_error());
         }
+
+        Ok(GC_REDUCTION_COST)
     }
 
     fn throw(
@@ -1278,26 +1695,18 @@ impl Machine {
         }
     }
 
-    fn panic(&mut self, process: &RcProcess, message: &str) {
-        let mut frames = Vec::new();
-        let mut buffer = String::new();
-
-        for context in process.contexts() {
-            frames.push(format!(
-                "\"{}\" line {}, in \"{}\"",
-                context.code.file.string_value().unwrap(),
-                context.line().to_string(),
-                context.code.name.string_value().unwrap()
-            ));
-        }
-
-        frames.reverse();
-
-        buffer.push_str("Stack trace (the most recent call comes last):");
-
-        for (index, line) in frames.iter().enumerate() {
-            buffer.push_str(&format!("\n  {}: {}", index, line));
-        }
+    /// Reports a panic in the given process.
+    ///
+    /// Depending on the panic policy, this either terminates the entire
+    /// program, or only the process that panicked. A panic in the main process
+    /// always terminates the program.
+    fn panic(
+        &mut self,
+        worker: &mut ProcessWorker,
+        process: &RcProcess,
+        message: &str,
+    ) {
+        let mut buffer = process.stacktrace();
 
         buffer.push_str(&format!(
             "\nProcess {:#x} panicked: {}",
@@ -1306,6 +1715,27 @@ impl Machine {
         ));
 
         eprintln!("{}", buffer);
-        self.state.terminate(1);
+
+        process::process_notify_watchers(
+            &self.state,
+            process,
+            ExitReason::Panic(buffer.clone()),
+        );
+
+        if process.is_main()
+            || self.state.config.panic_policy == PanicPolicy::Exit
+        {
+            self.state
+                .set_main_process_result(MainProcessResult::Panicked(buffer));
+            self.state.terminate(1);
+            return;
+        }
+
+        if process.is_pinned() {
+            process.unset_thread_id();
+            worker.leave_exclusive_mode();
+        }
+
+        process.terminate(&self.state);
     }
 }
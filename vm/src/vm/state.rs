@@ -6,20 +6,28 @@
 use crate::arc_without_weak::ArcWithoutWeak;
 use crate::config::Config;
 use crate::external_functions::ExternalFunctions;
+use crate::gc::log::GcLog;
+use crate::gc::statistics::GcStatistics;
 use crate::immix::global_allocator::{GlobalAllocator, RcGlobalAllocator};
 use crate::immix::permanent_allocator::PermanentAllocator;
 use crate::immutable_string::ImmutableString;
+use crate::io_pool::IoPool;
 use crate::modules::Modules;
 use crate::network_poller::NetworkPoller;
 use crate::object_pointer::ObjectPointer;
 use crate::object_value;
+use crate::opcode_statistics::OpcodeStatistics;
+use crate::process::RcProcess;
+use crate::profiler::Profiler;
+use crate::scheduler::deadlock_detector::DeadlockDetector;
 use crate::scheduler::process_scheduler::ProcessScheduler;
 use crate::scheduler::timeout_worker::TimeoutWorker;
 use crate::string_pool::StringPool;
 use num_bigint::BigInt;
 use parking_lot::Mutex;
 use std::panic::RefUnwindSafe;
-use std::time;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{self, Duration};
 
 /// A reference counted State.
 pub type RcState = ArcWithoutWeak<State>;
@@ -45,6 +53,20 @@ macro_rules! intern_string {
     }};
 }
 
+/// The outcome of running a program.
+pub enum MainProcessResult {
+    /// The main process finished and returned a value.
+    ///
+    /// The main process isn't terminated when finishing, as terminating it
+    /// releases the memory of the returned value. Instead, the process must be
+    /// terminated once the value is no longer needed.
+    Finished(RcProcess, ObjectPointer),
+
+    /// A process panicked, or all processes were blocked. The message
+    /// describes the reason the program terminated.
+    Panicked(String),
+}
+
 /// The state of a virtual machine.
 pub struct State {
     /// The virtual machine's configuration.
@@ -68,8 +90,22 @@ pub struct State {
     /// The exit status to use when the VM terminates.
     pub exit_status: Mutex<i32>,
 
+    /// The value returned by the main process, or the message of the first
+    /// process to panic.
+    pub main_process_result: Mutex<Option<MainProcessResult>>,
+
     pub timeout_worker: TimeoutWorker,
 
+    /// The statistics of all garbage collections performed.
+    pub gc_statistics: Mutex<GcStatistics>,
+
+    /// The log to write garbage collection statistics to, if any.
+    pub gc_log: Option<GcLog>,
+
+    /// The detector used for reporting programs in which all processes are
+    /// blocked.
+    pub deadlock_detector: DeadlockDetector,
+
     /// The prototype for traits.
     pub trait_prototype: ObjectPointer,
 
@@ -145,17 +181,35 @@ pub struct State {
     /// The prototype to use for child processes.
     pub child_process_prototype: ObjectPointer,
 
+    /// The prototype to use for messages sent when a process terminates.
+    pub process_exit_prototype: ObjectPointer,
+
     /// The commandline arguments passed to an Inko program.
     pub arguments: Vec<ObjectPointer>,
 
     /// The system polling mechanism to use for polling non-blocking sockets.
     pub network_poller: NetworkPoller,
 
+    /// The pool of threads to use for performing file operations.
+    pub io_pool: IoPool,
+
+    /// The number of times every pair of opcodes has been executed, if opcode
+    /// statistics are enabled.
+    pub opcode_statistics: Option<OpcodeStatistics>,
+
+    /// The profiler used for sampling running processes, if profiling is
+    /// enabled.
+    pub profiler: Option<Profiler>,
+
     /// All modules that are available to the current program.
     pub modules: Mutex<Modules>,
 
     /// All external functions that a compiler can use.
     pub external_functions: ExternalFunctions,
+
+    /// The epoch of all inline caches. Incrementing this value invalidates all
+    /// cached attribute lookups.
+    inline_cache_epoch: AtomicUsize,
 }
 
 impl RefUnwindSafe for State {}
@@ -165,6 +219,16 @@ impl State {
         let external_functions = ExternalFunctions::setup()
             .expect("Failed to set up the default external functions");
 
+        Self::with_external_functions(config, arguments, external_functions)
+    }
+
+    /// Creates a State that uses the given external functions, instead of only
+    /// the functions that Inko ships with.
+    pub fn with_external_functions(
+        config: Config,
+        arguments: &[String],
+        external_functions: ExternalFunctions,
+    ) -> RcState {
         let global_alloc = GlobalAllocator::with_rc();
 
         // Boxed since moving around the allocator can break pointers from the
@@ -197,23 +261,47 @@ impl State {
         let generator_prototype = perm_alloc.allocate_empty();
         let trait_prototype = perm_alloc.allocate_empty();
         let child_process_prototype = perm_alloc.allocate_empty();
+        let process_exit_prototype = perm_alloc.allocate_empty();
 
         nil_obj.set_prototype(nil_proto);
         true_obj.set_prototype(boolean_proto);
         false_obj.set_prototype(boolean_proto);
 
+        // A GC log that can't be opened shouldn't prevent the program from
+        // running, so we only report the error.
+        let gc_log = config.gc_log.as_ref().and_then(|path| {
+            GcLog::open(path).map_err(|err| eprintln!("{}", err)).ok()
+        });
+
         let mut state = State {
             scheduler: ProcessScheduler::new(
                 config.primary_threads,
                 config.blocking_threads,
             ),
+            deadlock_detector: DeadlockDetector::new(config.deadlock_policy),
+            io_pool: IoPool::new(config.io_threads),
+            opcode_statistics: if config.opcode_statistics {
+                Some(OpcodeStatistics::new())
+            } else {
+                None
+            },
+            profiler: if config.profile.is_some() {
+                Some(Profiler::new(Duration::from_micros(
+                    config.profile_interval,
+                )))
+            } else {
+                None
+            },
             config,
             permanent_allocator: Mutex::new(perm_alloc),
             global_allocator: global_alloc,
             string_pool: Mutex::new(StringPool::new()),
             start_time: time::Instant::now(),
             exit_status: Mutex::new(0),
+            main_process_result: Mutex::new(None),
             timeout_worker: TimeoutWorker::new(),
+            gc_statistics: Mutex::new(GcStatistics::new()),
+            gc_log,
             integer_prototype: integer_proto,
             float_prototype: float_proto,
             string_prototype: string_proto,
@@ -240,9 +328,11 @@ impl State {
             generator_prototype,
             trait_prototype,
             child_process_prototype,
+            process_exit_prototype,
             network_poller: NetworkPoller::new(),
             modules: Mutex::new(Modules::new()),
             external_functions,
+            inline_cache_epoch: AtomicUsize::new(0),
         };
 
         for argument in arguments {
@@ -308,6 +398,25 @@ impl State {
         self.scheduler.terminate();
         self.timeout_worker.terminate();
         self.network_poller.terminate();
+        self.io_pool.terminate();
+        self.deadlock_detector.terminate();
+
+        if let Some(profiler) = self.profiler.as_ref() {
+            profiler.terminate();
+        }
+    }
+
+    pub fn inline_cache_epoch(&self) -> usize {
+        self.inline_cache_epoch.load(Ordering::Acquire)
+    }
+
+    /// Invalidates all inline caches.
+    ///
+    /// This method must be called _after_ mutating an object that may be
+    /// cached, otherwise another thread may cache the old state in the new
+    /// epoch.
+    pub fn invalidate_inline_caches(&self) {
+        self.inline_cache_epoch.fetch_add(1, Ordering::AcqRel);
     }
 
     pub fn set_exit_status(&self, new_status: i32) {
@@ -318,6 +427,16 @@ impl State {
         *self.exit_status.lock()
     }
 
+    /// Stores the result of the main process, unless a result is already
+    /// stored.
+    pub fn set_main_process_result(&self, result: MainProcessResult) {
+        let mut current = self.main_process_result.lock();
+
+        if current.is_none() {
+            *current = Some(result);
+        }
+    }
+
     pub fn parse_image(&self, path: &str) -> Result<(), String> {
         self.modules.lock().parse_image(&self, path)
     }
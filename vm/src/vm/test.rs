@@ -1,5 +1,8 @@
-//! Functions for testing instruction handlers.
+//! Functions for testing instruction handlers and building bytecode images.
 use crate::block::Block;
+use crate::bytecode_parser::{
+    LITERAL_FLOAT, LITERAL_STRING, SIGNATURE_BYTES, VERSION,
+};
 use crate::compiled_code::CompiledCode;
 use crate::config::Config;
 use crate::module::Module;
@@ -41,7 +44,7 @@ pub fn setup() -> (Machine, Block, RcProcess) {
             vec![Module::new(mod_name, code, Vec::new())],
         );
 
-        let module_ptr = modules.get(&"test").unwrap();
+        let module_ptr = modules.get(&machine.state, "test").unwrap();
         let module = module_ptr.module_value().unwrap();
         let block = Block::new(module.code(), None, module_ptr, module);
         let process = process::process_allocate(&machine.state, &block);
@@ -53,3 +56,128 @@ pub fn setup() -> (Machine, Block, RcProcess) {
 
     (machine, block, process)
 }
+
+/// A literal to include in a module built using `module_image()`.
+pub enum Literal<'a> {
+    String(&'a str),
+    Float(f64),
+}
+
+pub fn pack_u8(value: u8, buffer: &mut Vec<u8>) {
+    buffer.push(value);
+}
+
+pub fn pack_u16(value: u16, buffer: &mut Vec<u8>) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn pack_u32(value: u32, buffer: &mut Vec<u8>) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn pack_u64(value: u64, buffer: &mut Vec<u8>) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+pub fn pack_f64(value: f64, buffer: &mut Vec<u8>) {
+    pack_u64(value.to_bits(), buffer);
+}
+
+pub fn pack_string(value: &str, buffer: &mut Vec<u8>) {
+    pack_u64(value.len() as u64, buffer);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+/// Packs an instruction, using line 1 as its line number.
+pub fn pack_instruction(opcode: Opcode, args: &[u16], buffer: &mut Vec<u8>) {
+    pack_u8(opcode as u8, buffer);
+    pack_u8(args.len() as u8, buffer);
+
+    for arg in args {
+        pack_u16(*arg, buffer);
+    }
+
+    pack_u16(1, buffer);
+}
+
+/// Builds a code object that runs the given instructions.
+///
+/// The name and file of the code object are the first two literals of the
+/// module, and the code object has 8 registers.
+pub fn code(
+    instructions: &[(Opcode, &[u16])],
+    children: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    pack_u32(0, &mut body); // name
+    pack_u32(1, &mut body); // file
+    pack_u16(1, &mut body); // line
+    pack_u64(0, &mut body); // arguments
+    pack_u8(0, &mut body); // required arguments
+    pack_u16(0, &mut body); // locals
+    pack_u16(8, &mut body); // registers
+    pack_u8(0, &mut body); // captures
+    pack_u64(instructions.len() as u64, &mut body);
+
+    for (opcode, args) in instructions {
+        pack_instruction(*opcode, args, &mut body);
+    }
+
+    pack_u64(children.len() as u64, &mut body);
+
+    for child in children {
+        body.extend_from_slice(child);
+    }
+
+    pack_u64(0, &mut body); // catch table entries
+    body
+}
+
+/// Builds a bytecode image with a single module called "main", which is also
+/// the entry point.
+///
+/// The literals available to the module are the string "main" (0), the string
+/// "main.inko" (1), followed by the given literals.
+pub fn module_image(literals: &[Literal], code: Vec<u8>) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    pack_u64(literals.len() as u64 + 2, &mut body);
+
+    for value in &["main", "main.inko"] {
+        pack_u8(LITERAL_STRING, &mut body);
+        pack_string(value, &mut body);
+    }
+
+    for literal in literals {
+        match literal {
+            Literal::String(value) => {
+                pack_u8(LITERAL_STRING, &mut body);
+                pack_string(value, &mut body);
+            }
+            Literal::Float(value) => {
+                pack_u8(LITERAL_FLOAT, &mut body);
+                pack_f64(*value, &mut body);
+            }
+        }
+    }
+
+    body.extend_from_slice(&code);
+
+    let mut payload = Vec::new();
+
+    pack_u64(1, &mut payload); // modules
+    pack_u64(body.len() as u64, &mut payload);
+    payload.extend_from_slice(&body);
+
+    let mut buffer = SIGNATURE_BYTES.to_vec();
+
+    pack_u8(VERSION, &mut buffer);
+    pack_string("0.9.0", &mut buffer); // compiler version
+    pack_u64(0, &mut buffer); // metadata entries
+    pack_string("main", &mut buffer); // entry point
+    pack_u8(0, &mut buffer); // module index
+    pack_u32(crc32fast::hash(&payload), &mut buffer);
+    buffer.extend_from_slice(&payload);
+    buffer
+}